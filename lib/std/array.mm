@@ -0,0 +1,77 @@
+// utilities of arrays given with their lengths as `{arr = [1, 2, 3], len = 3}`.
+// the type of `_` is inferred from the uses, so each function takes arrays of one type
+// in a program.
+
+func sum_from {a: {arr: _, len: Int}, i: Int} : Int {
+    if i < a.len { a.arr[i] + sum_from {a = a, i = i + 1} } else { 0 }
+}
+
+/// the sum of the elements, which is `0` for an empty array
+pub func sum a: {arr: _, len: Int} : Int {
+    sum_from {a = a, i = 0}
+}
+
+func product_from {a: {arr: _, len: Int}, i: Int} : Int {
+    if i < a.len { a.arr[i] * product_from {a = a, i = i + 1} } else { 1 }
+}
+
+/// the product of the elements, which is `1` for an empty array
+pub func product a: {arr: _, len: Int} : Int {
+    product_from {a = a, i = 0}
+}
+
+func max_from {a: {arr: _, len: Int}, i: Int, acc: Int} : Int {
+    if i < a.len {
+        let x = a.arr[i];
+        max_from {a = a, i = i + 1, acc = if x > acc { x } else { acc }}
+    } else {
+        acc
+    }
+}
+
+/// the largest element of a non-empty array
+pub func maximum a: {arr: _, len: Int} : Int {
+    max_from {a = a, i = 1, acc = a.arr[0]}
+}
+
+func min_from {a: {arr: _, len: Int}, i: Int, acc: Int} : Int {
+    if i < a.len {
+        let x = a.arr[i];
+        min_from {a = a, i = i + 1, acc = if x < acc { x } else { acc }}
+    } else {
+        acc
+    }
+}
+
+/// the smallest element of a non-empty array
+pub func minimum a: {arr: _, len: Int} : Int {
+    min_from {a = a, i = 1, acc = a.arr[0]}
+}
+
+func index_from {a: {arr: _, len: Int}, x: _, i: Int} : Int {
+    if i < a.len { if a.arr[i] == x { i } else { index_from {a = a, x = x, i = i + 1} } } else { 0 - 1 }
+}
+
+/// the index of the first element equal to `x`, or `-1` if there is none
+pub func index_of {a: {arr: _, len: Int}, x: _} : Int {
+    index_from {a = a, x = x, i = 0}
+}
+
+/// whether an element is equal to `x`
+pub func contains {a: {arr: _, len: Int}, x: _} : Bool {
+    index_of {a = a, x = x} /= 0 - 1
+}
+
+func count_from {a: {arr: _, len: Int}, x: _, i: Int} : Int {
+    if i < a.len {
+        let n = count_from {a = a, x = x, i = i + 1};
+        if a.arr[i] == x { n + 1 } else { n }
+    } else {
+        0
+    }
+}
+
+/// the number of the elements equal to `x`
+pub func count {a: {arr: _, len: Int}, x: _} : Int {
+    count_from {a = a, x = x, i = 0}
+}
@@ -0,0 +1,44 @@
+// classification of ASCII characters
+
+/// whether `c` is one of `0` to `9`
+pub func is_digit c: Char : Bool {
+    if c < '0' { false } else { c < ':' }
+}
+
+/// whether `c` is one of `a` to `z`
+pub func is_lower c: Char : Bool {
+    if c < 'a' { false } else { c < '{' }
+}
+
+/// whether `c` is one of `A` to `Z`
+pub func is_upper c: Char : Bool {
+    if c < 'A' { false } else { c < '[' }
+}
+
+pub func is_alpha c: Char : Bool {
+    if is_lower c { true } else { is_upper c }
+}
+
+pub func is_alnum c: Char : Bool {
+    if is_alpha c { true } else { is_digit c }
+}
+
+/// whether `c` is a space or a control character such as a tab and a line break
+pub func is_space c: Char : Bool {
+    c < '!'
+}
+
+/// the digit denoting `n` from `0` to `9`, or `?` if `n` is out of the range
+pub func digit_char n: Int : Char {
+    let digits = ['0', '1', '2', '3', '4', '5', '6', '7', '8', '9'];
+    if n < 0 { '?' } else { if n > 9 { '?' } else { digits[n] } }
+}
+
+func find_digit {c: Char, i: Int} : Int {
+    if i > 9 { 0 - 1 } else { if digit_char i == c { i } else { find_digit {c = c, i = i + 1} } }
+}
+
+/// the number which the digit `c` denotes, or `-1` if `c` is not a digit
+pub func digit_value c: Char : Int {
+    find_digit {c = c, i = 0}
+}
@@ -0,0 +1,64 @@
+// integer arithmetic
+
+/// `x` without its sign
+pub func abs x: Int : Int {
+    if x < 0 { 0 - x } else { x }
+}
+
+/// `-1`, `0` or `1` as `x` is negative, zero or positive
+pub func sign x: Int : Int {
+    if x < 0 { 0 - 1 } else { if x > 0 { 1 } else { 0 } }
+}
+
+/// the smaller of `a` and `b`
+pub func min {a: Int, b: Int} : Int {
+    if a < b { a } else { b }
+}
+
+/// the larger of `a` and `b`
+pub func max {a: Int, b: Int} : Int {
+    if a > b { a } else { b }
+}
+
+/// `x` limited to the range from `low` to `high`
+pub func clamp {x: Int, low: Int, high: Int} : Int {
+    if x < low { low } else { if x > high { high } else { x } }
+}
+
+/// `x * x`
+pub func square x: Int : Int {
+    x * x
+}
+
+/// `base` raised to the power of `exp`, which is `1` for a negative `exp`
+pub func pow {base: Int, exp: Int} : Int {
+    if exp < 1 {
+        1
+    } else {
+        let half = pow {base = base, exp = exp / 2};
+        if exp / 2 * 2 == exp { half * half } else { half * half * base }
+    }
+}
+
+/// the remainder of `a / b`, which has the sign of `a`
+pub func rem {a: Int, b: Int} : Int {
+    a - a / b * b
+}
+
+/// the greatest common divisor of `a` and `b`, which is never negative
+pub func gcd {a: Int, b: Int} : Int {
+    if b == 0 { abs a } else { gcd {a = b, b = rem {a = a, b = b}} }
+}
+
+/// the least common multiple of `a` and `b`, which is never negative
+pub func lcm {a: Int, b: Int} : Int {
+    if a == 0 { 0 } else { abs (a / gcd {a = a, b = b} * b) }
+}
+
+pub func is_even x: Int : Bool {
+    rem {a = x, b = 2} == 0
+}
+
+pub func is_odd x: Int : Bool {
+    rem {a = x, b = 2} /= 0
+}
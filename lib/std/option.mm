@@ -0,0 +1,30 @@
+// an integer which may be absent
+
+/// `value` is meaningful only if `some` is `true`
+pub type Option = {some: Bool, value: Int};
+
+pub func some value: Int : Option {
+    {some = true, value = value}
+}
+
+pub func none _: Unit : Option {
+    {some = false, value = 0}
+}
+
+pub func is_some opt: Option : Bool {
+    opt.some
+}
+
+pub func is_none opt: Option : Bool {
+    if opt.some { false } else { true }
+}
+
+/// the value of `opt`, or `default` if it is absent
+pub func unwrap_or {opt: Option, default: Int} : Int {
+    if opt.some { opt.value } else { default }
+}
+
+/// `opt` if it has a value, otherwise `other`
+pub func or {opt: Option, other: Option} : Option {
+    if opt.some { opt } else { other }
+}
@@ -0,0 +1,5 @@
+// the definitions imported into every module implicitly,
+// which `prelude = false` in `[build]` of `mumrik-conf.toml` disables
+import std.math.{abs, min, max};
+import std.option.{Option, some, none, is_some, is_none};
+import std.result.{Result, ok, err, is_ok, is_err};
@@ -0,0 +1,30 @@
+// an integer or an error code telling why it could not be computed
+
+/// `value` is meaningful only if `ok` is `true`, and `error` only if it is `false`
+pub type Result = {ok: Bool, value: Int, error: Int};
+
+pub func ok value: Int : Result {
+    {ok = true, value = value, error = 0}
+}
+
+pub func err error: Int : Result {
+    {ok = false, value = 0, error = error}
+}
+
+pub func is_ok res: Result : Bool {
+    res.ok
+}
+
+pub func is_err res: Result : Bool {
+    if res.ok { false } else { true }
+}
+
+/// the value of `res`, or `default` if it is an error
+pub func unwrap_or {res: Result, default: Int} : Int {
+    if res.ok { res.value } else { default }
+}
+
+/// the error code of `res`, or `default` if it has a value
+pub func error_or {res: Result, default: Int} : Int {
+    if res.ok { default } else { res.error }
+}
@@ -0,0 +1,55 @@
+// operations on strings, which are arrays of characters given with their lengths as
+// `{chars = ['a', 'b'], len = 2}`. the type of `_` is inferred from the uses, so each function
+// takes arrays of one size in a program. pad the shorter strings to the size of the longest,
+// e.g) `{chars = ['h', 'i', '.', '.'], len = 2}` beside `{chars = ['h', 'e', 'l', 'o'], len = 4}`,
+// as only the first `len` characters are read.
+import std.char;
+
+func equal_from {a: {chars: _, len: Int}, b: {chars: _, len: Int}, i: Int} : Bool {
+    if i < a.len { if a.chars[i] == b.chars[i] { equal_from {a = a, b = b, i = i + 1} } else { false } } else { true }
+}
+
+/// whether `a` and `b` have the same characters
+pub func equal {a: {chars: _, len: Int}, b: {chars: _, len: Int}} : Bool {
+    if a.len == b.len { equal_from {a = a, b = b, i = 0} } else { false }
+}
+
+func prefix_from {s: {chars: _, len: Int}, prefix: {chars: _, len: Int}, i: Int} : Bool {
+    if i < prefix.len { if s.chars[i] == prefix.chars[i] { prefix_from {s = s, prefix = prefix, i = i + 1} } else { false } } else { true }
+}
+
+/// whether `s` begins with the characters of `prefix`
+pub func starts_with {s: {chars: _, len: Int}, prefix: {chars: _, len: Int}} : Bool {
+    if prefix.len > s.len { false } else { prefix_from {s = s, prefix = prefix, i = 0} }
+}
+
+func find_from {s: {chars: _, len: Int}, c: Char, i: Int} : Int {
+    if i < s.len { if s.chars[i] == c { i } else { find_from {s = s, c = c, i = i + 1} } } else { 0 - 1 }
+}
+
+/// the index of the first `c` in `s`, or `-1` if there is none
+pub func find {s: {chars: _, len: Int}, c: Char} : Int {
+    find_from {s = s, c = c, i = 0}
+}
+
+func digits_from {s: {chars: _, len: Int}, i: Int} : Bool {
+    if i < s.len { if char.is_digit s.chars[i] { digits_from {s = s, i = i + 1} } else { false } } else { true }
+}
+
+/// whether `s` is a decimal integer such as `42` and `-7`
+pub func is_integer s: {chars: _, len: Int} : Bool {
+    if s.len == 0 {
+        false
+    } else {
+        if s.chars[0] == '-' { if s.len > 1 { digits_from {s = s, i = 1} } else { false } } else { digits_from {s = s, i = 0} }
+    }
+}
+
+func parse_from {s: {chars: _, len: Int}, i: Int, acc: Int} : Int {
+    if i < s.len { parse_from {s = s, i = i + 1, acc = acc * 10 + char.digit_value s.chars[i]} } else { acc }
+}
+
+/// the decimal integer `s`, which must satisfy `is_integer`
+pub func parse_int s: {chars: _, len: Int} : Int {
+    if s.chars[0] == '-' { 0 - parse_from {s = s, i = 1, acc = 0} } else { parse_from {s = s, i = 0, acc = 0} }
+}
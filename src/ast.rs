@@ -1,12 +1,14 @@
 use ident::Ident;
+use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+mod children;
 mod free_vars;
 mod is_occurs;
 mod printer;
 mod subst;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Position {
     pub start: usize,
     pub end: usize,
@@ -18,19 +20,44 @@ impl Position {
     }
 }
 
+// whether the parameter of a function binds `name`, which may be a field of a record parameter
+pub fn binds_param(param_name: &Ident, param_type: &Type, name: &Ident) -> bool {
+    match param_type {
+        Type::Record(ref fields) if param_name.is_omitted_param_name() => {
+            fields.iter().any(|(label, _)| name == label)
+        }
+        _ => name == param_name,
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Import {
     pub dirs: Vec<Ident>,
     pub module_name: Ident,
+    // the name qualifying the definitions of the module, e.g) `c` of `import a.b as c;`
+    pub alias: Ident,
+    // the definitions brought into scope unqualified, e.g) `x` and `y` of `import a.b.{x, y};`
+    pub items: Option<Vec<Ident>>,
+    pub pos: Position,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Test {
+    pub name: String,
+    pub body: Expr,
+    pub pos: Position,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Program {
     pub imports: Vec<Import>,
+    pub tests: Vec<Test>,
+    // the top-level definitions declared `pub`, which are visible to importers
+    pub public: Vec<Ident>,
     pub expr: Expr,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Expr {
     Const(Literal),
     Var(Ident, Type, Position),
@@ -42,20 +69,27 @@ pub enum Expr {
         body: Box<Expr>,
         left: Box<Expr>,
         pos: Position,
+        // the `///` comment of a top-level function
+        doc: Option<String>,
     },
     Apply(Box<Expr>, Box<Expr>, Position),
-    Let(Ident, Type, Box<Expr>, Box<Expr>, Position),
-    LetType(Ident, Type, Box<Expr>),
+    // the last is the `///` comment of a top-level definition
+    Let(Ident, Type, Box<Expr>, Box<Expr>, Position, Option<String>),
+    LetType(Ident, Type, Box<Expr>, Option<String>),
     If(Box<Expr>, Box<Expr>, Box<Expr>, Position),
     BinOp(BinOp, Box<Expr>, Box<Expr>, Position),
     RecordGet(Box<Expr>, Type, Ident, Position),
     ArrayGet(Box<Expr>, Box<Expr>, Position),
     Assign(Box<Expr>, Box<Expr>, Position),
     Println(Box<Expr>),
+    // a call to the C function `name` with the arguments in the order of its parameters,
+    // e.g) the body of `extern func putchar c: Int :Int;`
+    Extern(Ident, Vec<Expr>, Type, Position),
+    Assert(Box<Expr>, Position),
     EmptyMark,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Literal {
     Number(i32),
     Bool(bool),
@@ -65,7 +99,7 @@ pub enum Literal {
     Array(Vec<Expr>, Type),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BinOp {
     Add,
     Sub,
@@ -77,7 +111,7 @@ pub enum BinOp {
     Gt,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Type {
     Int,
     Bool,
@@ -0,0 +1,28 @@
+use super::*;
+
+impl Expr {
+    // the direct subexpressions of `self`
+    pub fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Const(Literal::Record(ref fields)) => fields.values().collect(),
+            Expr::Const(Literal::Array(ref elems, _)) => elems.iter().collect(),
+            Expr::Const(_) | Expr::Var(_, _, _) | Expr::EmptyMark => vec![],
+            Expr::Func {
+                box ref body,
+                box ref left,
+                ..
+            } => vec![body, left],
+            Expr::Let(_, _, box ref e1, box ref e2, _, _)
+            | Expr::Apply(box ref e1, box ref e2, _)
+            | Expr::BinOp(_, box ref e1, box ref e2, _)
+            | Expr::ArrayGet(box ref e1, box ref e2, _)
+            | Expr::Assign(box ref e1, box ref e2, _) => vec![e1, e2],
+            Expr::If(box ref cond, box ref e1, box ref e2, _) => vec![cond, e1, e2],
+            Expr::Extern(_, ref args, _, _) => args.iter().collect(),
+            Expr::LetType(_, _, box ref e, _)
+            | Expr::RecordGet(box ref e, _, _, _)
+            | Expr::Println(box ref e)
+            | Expr::Assert(box ref e, _) => vec![e],
+        }
+    }
+}
@@ -33,6 +33,7 @@ impl Expr {
                 box body,
                 box left,
                 pos: _,
+                doc: _,
             } => {
                 let mut vars = body.free_term_vars();
                 if param_name.is_omitted_param_name() {
@@ -56,14 +57,14 @@ impl Expr {
                 vars.extend(e2.free_term_vars());
                 vars
             }
-            Expr::Let(ref name, _, box ref e1, box ref e2, _) => {
+            Expr::Let(ref name, _, box ref e1, box ref e2, _, _) => {
                 let mut vars = HashMap::new();
                 vars.extend(e2.free_term_vars());
                 vars.remove(name);
                 vars.extend(e1.free_term_vars());
                 vars
             }
-            Expr::LetType(_, _, box ref e) => e.free_term_vars(),
+            Expr::LetType(_, _, box ref e, _) => e.free_term_vars(),
             Expr::If(box ref cond, box ref e1, box ref e2, _) => {
                 let mut vars = HashMap::new();
                 vars.extend(cond.free_term_vars());
@@ -90,8 +91,69 @@ impl Expr {
                 vars.extend(e2.free_term_vars());
                 vars
             }
-            Expr::Println(box ref e) => e.free_term_vars(),
+            Expr::Println(box ref e) | Expr::Assert(box ref e, _) => e.free_term_vars(),
+            Expr::Extern(_, ref args, _, _) => {
+                let mut vars = HashMap::new();
+                for e in args.iter() {
+                    vars.extend(e.free_term_vars());
+                }
+                vars
+            }
             Expr::EmptyMark => HashMap::new(),
         }
     }
 }
+
+impl Expr {
+    // the type variables in the annotations of `e`, in the order of their first occurrences
+    pub fn type_vars(&self) -> Vec<Ident> {
+        let mut vars = vec![];
+        self.collect_type_vars(&mut vars);
+        vars
+    }
+
+    fn collect_type_vars(&self, vars: &mut Vec<Ident>) {
+        match self {
+            Expr::Const(Literal::Array(_, ref typ))
+            | Expr::Var(_, ref typ, _)
+            | Expr::Let(_, ref typ, _, _, _, _)
+            | Expr::LetType(_, ref typ, _, _)
+            | Expr::Extern(_, _, ref typ, _)
+            | Expr::RecordGet(_, ref typ, _, _) => typ.collect_type_vars(vars),
+            Expr::Func {
+                ref param_type,
+                ref ret_type,
+                ..
+            } => {
+                param_type.collect_type_vars(vars);
+                ret_type.collect_type_vars(vars);
+            }
+            _ => (),
+        }
+        for e in self.children() {
+            e.collect_type_vars(vars);
+        }
+    }
+}
+
+impl Type {
+    fn collect_type_vars(&self, vars: &mut Vec<Ident>) {
+        match self {
+            Type::Func(box ref typ1, box ref typ2) => {
+                typ1.collect_type_vars(vars);
+                typ2.collect_type_vars(vars);
+            }
+            Type::Record(ref fields) => {
+                // sorted by the labels, so that the order does not depend on the map
+                let mut labels: Vec<&Ident> = fields.keys().collect();
+                labels.sort();
+                for label in labels {
+                    fields[label].collect_type_vars(vars);
+                }
+            }
+            Type::Array(box ref typ, _) => typ.collect_type_vars(vars),
+            Type::Var(ref name) if !vars.contains(name) => vars.push(name.clone()),
+            _ => (),
+        }
+    }
+}
@@ -6,6 +6,7 @@ impl Type {
         match self {
             Type::Func(box ref ty1, box ref ty2) => ty1.is_occurs(name) || ty2.is_occurs(name),
             Type::Record(ref fields) => fields.iter().any(|(_, ty)| ty.is_occurs(name)),
+            Type::Array(box ref ty, _) => ty.is_occurs(name),
             Type::Var(ref name_) if name == name_ => true,
             _ => false,
         }
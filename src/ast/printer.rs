@@ -7,6 +7,36 @@ impl fmt::Display for Position {
     }
 }
 
+// e.g) the output of `mumrik build --emit ast`
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for import in self.imports.iter() {
+            write!(f, "import ")?;
+            for dir in import.dirs.iter() {
+                write!(f, "{}.", dir)?;
+            }
+            write!(f, "{}", import.module_name)?;
+            match import.items {
+                Some(ref items) => {
+                    let items: Vec<String> = items.iter().map(|item| format!("{}", item)).collect();
+                    write!(f, ".{{{}}}", items.join(", "))?;
+                }
+                None if import.alias != import.module_name => write!(f, " as {}", import.alias)?,
+                None => (),
+            }
+            writeln!(f, ";")?;
+        }
+        for test in self.tests.iter() {
+            writeln!(f, "test {:?} {{ {} }}", test.name, test.body)?;
+        }
+        if !self.public.is_empty() {
+            let names: Vec<String> = self.public.iter().map(|name| format!("{}", name)).collect();
+            writeln!(f, "// pub: {}", names.join(", "))?;
+        }
+        writeln!(f, "{}", self.expr)
+    }
+}
+
 impl fmt::Display for Expr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
@@ -20,17 +50,18 @@ impl fmt::Display for Expr {
                 box body,
                 box left,
                 pos: _,
+                doc: _,
             } => write!(
                 f,
-                "let rec {} = (func {}:{} :{} => {}); {}",
+                "let rec {} = (func {}:{} :{} => {});\n{}",
                 name, param_name, param_type, ret_type, body, left
             ),
             Expr::Apply(box ref e1, box ref e2, _) => write!(f, "({}) ({})", e1, e2),
-            Expr::Let(ref name, ref typ, box ref e1, box ref e2, _) => {
-                write!(f, "let {}: {} = {}; {}", name, typ, e1, e2)
+            Expr::Let(ref name, ref typ, box ref e1, box ref e2, _, _) => {
+                write!(f, "let {}: {} = {};\n{}", name, typ, e1, e2)
             }
-            Expr::LetType(ref name, ref typ, box ref e) => {
-                write!(f, "let type {} = {}; {}", name, typ, e)
+            Expr::LetType(ref name, ref typ, box ref e, _) => {
+                write!(f, "let type {} = {};\n{}", name, typ, e)
             }
             Expr::If(box cond, box e1, box e2, _) => {
                 write!(f, "if {} {{ {} }} else {{ {} }}", cond, e1, e2)
@@ -40,7 +71,16 @@ impl fmt::Display for Expr {
             Expr::ArrayGet(box ref e1, box ref e2, _) => write!(f, "{}[{}]", e1, e2),
             Expr::Assign(box ref e1, box ref e2, _) => write!(f, "{} <- {}", e1, e2),
             Expr::Println(box ref e) => write!(f, "println {}", e),
-            Expr::EmptyMark => unreachable!(),
+            Expr::Extern(ref name, ref args, _, _) => {
+                write!(f, "extern {}(", name)?;
+                for e in args {
+                    write!(f, "{}, ", e)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Assert(box ref e, _) => write!(f, "assert {}", e),
+            // e.g) the rest of a library
+            Expr::EmptyMark => Ok(()),
         }
     }
 }
@@ -7,7 +7,7 @@ impl Expr {
             name,
             typ,
             |e, name, _| match &e {
-                Expr::LetType(ref name_, _, _) if name_ == name => Some(e),
+                Expr::LetType(ref name_, _, _, _) if name_ == name => Some(e),
                 _ => None,
             },
             |_, _, _| None,
@@ -17,6 +17,21 @@ impl Expr {
             },
         )
     }
+    // same as `subst_type`, but also in the scope of the type definition of `name`, e.g) to apply
+    // the solution of the typechecker, whose variables are the same in the whole program
+    pub fn subst_type_everywhere(self, name: &Ident, typ: &Type) -> Expr {
+        aux_expr(
+            self,
+            name,
+            typ,
+            |_, _, _| None,
+            |_, _, _| None,
+            |typ_, name, typ| match typ_ {
+                Type::Var(name_) if name == &name_ => Some(typ.clone()),
+                _ => None,
+            },
+        )
+    }
     pub fn subst_expr(self, name: &Ident, expr: &Expr) -> Expr {
         aux_expr(
             self,
@@ -24,7 +39,7 @@ impl Expr {
             expr,
             |e, name, expr| match &e {
                 Expr::Var(ref name_, _, _) if name == name_ => Some(expr.clone()),
-                Expr::Let(ref name_, _, _, _, _) if name == name_ => Some(e),
+                Expr::Let(ref name_, _, _, _, _, _) if name == name_ => Some(e),
                 Expr::Func {
                     name: ref func_name,
                     ref param_name,
@@ -53,6 +68,114 @@ impl Expr {
             |_, _, _| None,
         )
     }
+    // renames the free occurrences of the variable `from`, keeping their types and positions
+    pub fn rename_var(self, from: &Ident, to: &Ident) -> Expr {
+        aux_expr(
+            self,
+            from,
+            to,
+            |e, from, to| match e {
+                Expr::Var(ref name, ref typ, pos) if name == from => {
+                    Some(Expr::Var(to.clone(), typ.clone(), pos))
+                }
+                _ => shadow(&e, from, &|e| e.rename_var(from, to)),
+            },
+            |_, _, _| None,
+            |_, _, _| None,
+        )
+    }
+    // renames the function `from` both where it is defined and where it is referred to, e.g) an
+    // anonymous function named by `Ident::fresh`, which no other definition shadows
+    pub fn rename_func(self, from: &Ident, to: &Ident) -> Expr {
+        aux_expr(
+            self,
+            from,
+            to,
+            |e, from, to| match e {
+                Expr::Var(ref name, ref typ, pos) if name == from => {
+                    Some(Expr::Var(to.clone(), typ.clone(), pos))
+                }
+                Expr::Func {
+                    name,
+                    param_name,
+                    param_type,
+                    ret_type,
+                    box body,
+                    box left,
+                    pos,
+                    doc,
+                } if &name == from => Some(Expr::Func {
+                    name: to.clone(),
+                    param_name: param_name,
+                    param_type: param_type,
+                    ret_type: ret_type,
+                    body: box body.rename_func(from, to),
+                    left: box left.rename_func(from, to),
+                    pos: pos,
+                    doc: doc,
+                }),
+                _ => None,
+            },
+            |_, _, _| None,
+            |_, _, _| None,
+        )
+    }
+    // replaces `module.name` with the variable `members[name]` unless `module` is shadowed.
+    // unknown members become the variable `module.name`, which can not be defined in a program.
+    pub fn qualify(self, module: &Ident, members: &HashMap<Ident, Ident>) -> Expr {
+        aux_expr(
+            self,
+            module,
+            members,
+            |e, module, members| match e {
+                Expr::RecordGet(box Expr::Var(ref name, _, _), _, ref label, pos)
+                    if name == module =>
+                {
+                    let member = members
+                        .get(label)
+                        .cloned()
+                        .unwrap_or_else(|| Ident::new(&format!("{}.{}", module, label)));
+                    Some(Expr::Var(member, Type::Var(Ident::fresh()), pos))
+                }
+                _ => shadow(&e, module, &|e| e.qualify(module, members)),
+            },
+            |_, _, _| None,
+            |_, _, _| None,
+        )
+    }
+}
+
+// `e` with `f` applied to the parts in which `name` is still free, if `e` binds `name`
+fn shadow(e: &Expr, name: &Ident, f: &dyn Fn(Expr) -> Expr) -> Option<Expr> {
+    match e {
+        Expr::Let(ref name_, ref typ, box ref e1, ref e2, pos, ref doc) if name == name_ => Some(
+            Expr::Let(name_.clone(), typ.clone(), box f(e1.clone()), e2.clone(), *pos, doc.clone()),
+        ),
+        Expr::Func {
+            name: ref func_name,
+            ..
+        } if name == func_name => Some(e.clone()),
+        Expr::Func {
+            name: ref func_name,
+            ref param_name,
+            ref param_type,
+            ref ret_type,
+            ref body,
+            box ref left,
+            pos,
+            doc,
+        } if binds_param(param_name, param_type, name) => Some(Expr::Func {
+            name: func_name.clone(),
+            param_name: param_name.clone(),
+            param_type: param_type.clone(),
+            ret_type: ret_type.clone(),
+            body: body.clone(),
+            left: box f(left.clone()),
+            pos: *pos,
+            doc: doc.clone(),
+        }),
+        _ => None,
+    }
 }
 
 impl Type {
@@ -93,6 +216,7 @@ fn aux_expr<T>(
             box body,
             box left,
             pos,
+            doc,
         } => Expr::Func {
             name: func_name,
             param_name: param_name,
@@ -101,23 +225,26 @@ fn aux_expr<T>(
             body: box aux_expr(body, name, v, ef, lf, tf),
             left: box aux_expr(left, name, v, ef, lf, tf),
             pos: pos,
+            doc: doc,
         },
         Expr::Apply(box e1, box e2, pos) => Expr::Apply(
             box aux_expr(e1, name, v, ef, lf, tf),
             box aux_expr(e2, name, v, ef, lf, tf),
             pos,
         ),
-        Expr::Let(name_, typ, box e1, box e2, pos) => Expr::Let(
+        Expr::Let(name_, typ, box e1, box e2, pos, doc) => Expr::Let(
             name_,
             aux_type(typ, name, v, ef, lf, tf),
             box aux_expr(e1, name, v, ef, lf, tf),
             box aux_expr(e2, name, v, ef, lf, tf),
             pos,
+            doc,
         ),
-        Expr::LetType(name_, typ, box e) => Expr::LetType(
+        Expr::LetType(name_, typ, box e, doc) => Expr::LetType(
             name_,
             aux_type(typ, name, v, ef, lf, tf),
             box aux_expr(e, name, v, ef, lf, tf),
+            doc,
         ),
         Expr::If(box cond, box e1, box e2, pos) => Expr::If(
             box aux_expr(cond, name, v, ef, lf, tf),
@@ -148,6 +275,15 @@ fn aux_expr<T>(
             pos,
         ),
         Expr::Println(box e) => Expr::Println(box aux_expr(e, name, v, ef, lf, tf)),
+        Expr::Extern(symbol, args, typ, pos) => Expr::Extern(
+            symbol,
+            args.into_iter()
+                .map(|e| aux_expr(e, name, v, ef, lf, tf))
+                .collect(),
+            aux_type(typ, name, v, ef, lf, tf),
+            pos,
+        ),
+        Expr::Assert(box e, pos) => Expr::Assert(box aux_expr(e, name, v, ef, lf, tf), pos),
         Expr::EmptyMark => Expr::EmptyMark,
     }
 }
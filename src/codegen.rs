@@ -1,6 +1,16 @@
 use ast::*;
-use std::path::Path;
+use ident::Ident;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 mod auxprocess;
+mod export;
+mod object;
+mod printer;
+
+pub use self::export::{export, exports, header, Export};
+pub use self::object::{unit, write_llvm_ir, write_unit_llvm_ir, Interface, Unit};
+pub use self::printer::print_nf;
 
 fn conv_toplevel_expr(e: Expr) -> nf::Nf {
     match e {
@@ -12,13 +22,14 @@ fn conv_toplevel_expr(e: Expr) -> nf::Nf {
             box body,
             box left,
             pos: _,
+            doc: _,
         } => {
             let mut nf = conv_toplevel_expr(left);
             let body = conv_expr(body);
             let body = if param_name.is_omitted_param_name() {
                 if let Type::Record(fields) = param_type.clone() {
                     let param_name = param_name.clone().to_nf_ident();
-                    fields
+                    sorted_fields(fields)
                         .into_iter()
                         .enumerate()
                         .fold(body, |acc, (n, (name, typ))| {
@@ -46,6 +57,11 @@ fn conv_toplevel_expr(e: Expr) -> nf::Nf {
             });
             nf
         }
+        // e.g) a library
+        Expr::EmptyMark => nf::Nf {
+            funcs: vec![],
+            body: None,
+        },
         _ => nf::Nf {
             funcs: vec![],
             body: Some(conv_expr(e)),
@@ -62,7 +78,7 @@ fn conv_expr(e: Expr) -> nf::Expr {
         },
         Expr::Func { .. } => unreachable!(),
         Expr::Apply(box e1, box e2, _) => nf::Expr::Call(box conv_expr(e1), vec![conv_expr(e2)]),
-        Expr::Let(name, typ, box e1, box e2, _) => nf::Expr::Let(
+        Expr::Let(name, typ, box e1, box e2, _, _) => nf::Expr::Let(
             name.to_nf_ident(),
             if let Type::Func(_, _) = typ {
                 nf::Type::Pointer(box conv_ty(typ))
@@ -72,7 +88,7 @@ fn conv_expr(e: Expr) -> nf::Expr {
             box conv_expr(e1),
             box conv_expr(e2),
         ),
-        Expr::LetType(_, _, _) => unreachable!(),
+        Expr::LetType(_, _, _, _) => unreachable!(),
         Expr::If(box cond, box e1, box e2, _) => {
             nf::Expr::If(box conv_expr(cond), box conv_expr(e1), box conv_expr(e2))
         }
@@ -81,7 +97,7 @@ fn conv_expr(e: Expr) -> nf::Expr {
         }
         Expr::RecordGet(box e, typ, label, _) => {
             if let Type::Record(fields) = typ {
-                let idx = fields
+                let idx = sorted_fields(fields)
                     .iter()
                     .position(|(label_, _)| &label == label_)
                     .unwrap();
@@ -110,7 +126,12 @@ fn conv_expr(e: Expr) -> nf::Expr {
             }
         }
         Expr::Println(box e) => nf::Expr::PrintNum(box conv_expr(e)),
-        Expr::EmptyMark => unreachable!(),
+        Expr::Extern(name, args, _, _) => nf::Expr::Call(
+            box nf::Expr::Var(name.to_nf_ident()),
+            args.into_iter().map(conv_expr).collect(),
+        ),
+        Expr::Assert(_, _) => unreachable!(), // only in tests
+        Expr::EmptyMark => nf::Expr::Const(nf::Literal::Int(0)), // dummy, e.g) the rest of a library
     }
 }
 
@@ -121,7 +142,10 @@ fn conv_lit(lit: Literal) -> nf::Literal {
         Literal::Char(c) => nf::Literal::Char(c),
         Literal::Unit => nf::Literal::Int(0), // dummy,
         Literal::Record(fields) => {
-            let elems = fields.into_iter().map(|(_, e)| conv_expr(e)).collect();
+            let elems = sorted_fields(fields)
+                .into_iter()
+                .map(|(_, e)| conv_expr(e))
+                .collect();
             nf::Literal::Tuple(elems)
         }
         Literal::Array(elems, typ) => {
@@ -143,6 +167,14 @@ fn conv_binop(op: BinOp) -> nf::BinOp {
     }
 }
 
+// the fields of a record in the order of their labels, which is the layout of its tuple, so that
+// object files compiled apart agree on it
+fn sorted_fields<T>(fields: HashMap<Ident, T>) -> Vec<(Ident, T)> {
+    let mut fields: Vec<(Ident, T)> = fields.into_iter().collect();
+    fields.sort_by(|(label1, _), (label2, _)| label1.cmp(label2));
+    fields
+}
+
 fn conv_ty(ty: Type) -> nf::Type {
     match ty {
         Type::Int => nf::Type::Int,
@@ -151,7 +183,12 @@ fn conv_ty(ty: Type) -> nf::Type {
         Type::Unit => nf::Type::Int, // dummy
         Type::Func(box ty1, box ty2) => nf::Type::Func(vec![conv_ty(ty1)], box conv_ty(ty2)),
         Type::Record(fields) => {
-            nf::Type::Tuple(fields.into_iter().map(|(_, typ)| conv_ty(typ)).collect())
+            nf::Type::Tuple(
+                sorted_fields(fields)
+                    .into_iter()
+                    .map(|(_, typ)| conv_ty(typ))
+                    .collect(),
+            )
         }
         Type::Array(box elem_typ, len) => nf::Type::Array(box conv_ty(elem_typ), len),
         Type::Var(_) | Type::EmptyMark => unreachable!(),
@@ -196,34 +233,276 @@ fn exec_command(command_name: &str, args: Vec<&str>) {
     }
 }
 
-pub fn codegen(expr: Expr, filename: &Path) {
-    let nf = conv_toplevel_expr(auxprocess::pre(expr));
+// runs `command_name` on the files of the user, e.g) the C compiler on the C files of the config,
+// whose failure is theirs rather than of the compiler
+fn run_tool(command_name: &str, args: Vec<&str>) -> Result<(), String> {
+    let result = std::process::Command::new(command_name)
+        .args(args)
+        .output()
+        .map_err(|err| format!("can not execute `{}`: {}", command_name, err))?;
+    if result.status.success() {
+        return Ok(());
+    }
+    let mut msg = format!("`{}` failed with {}", command_name, result.status);
+    for output in [&result.stdout, &result.stderr].iter() {
+        let output = String::from_utf8_lossy(output);
+        if !output.trim().is_empty() {
+            msg += &format!("\n{}", output.trim());
+        }
+    }
+    Err(msg)
+}
+
+// the C functions which `e` calls with their declarations, see `c_declaration`
+fn extern_funcs(e: &Expr, funcs: &mut Vec<(nf::Func, String)>) {
+    if let Expr::Extern(ref name, ref args, ref ret_type, _) = e {
+        let nf_name = name.clone().to_nf_ident();
+        if !funcs.iter().any(|(func, _)| func.name == nf_name) {
+            let param_types: Vec<Type> = args
+                .iter()
+                .map(|arg| match arg {
+                    Expr::Var(_, ref typ, _) => typ.clone(),
+                    _ => unreachable!(),
+                })
+                .collect();
+            let params = param_types
+                .iter()
+                .enumerate()
+                .map(|(idx, typ)| {
+                    (
+                        Ident::new(&format!("<arg-{}>", idx)).to_nf_ident(),
+                        conv_ty(typ.clone()),
+                    )
+                })
+                .collect();
+            funcs.push((
+                stand_in(nf_name, params, conv_ty(ret_type.clone())),
+                c_declaration(name, &param_types, ret_type),
+            ));
+        }
+    }
+    for child in e.children() {
+        extern_funcs(child, funcs);
+    }
+}
+
+// the LLVM type of a value which C functions take or return, with the attribute extending it to
+// `int` as C does
+fn c_type(typ: &Type) -> (&'static str, Option<&'static str>) {
+    match typ {
+        Type::Bool => ("i1", Some("zeroext")),
+        Type::Char => ("i8", Some("signext")),
+        // `Unit` is returned as an `Int` which is never read, see `conv_ty`
+        _ => ("i32", None),
+    }
+}
+
+// the declaration of the C function `name`, e.g) `declare zeroext i1 @is_ok(i32, i8 signext)`
+fn c_declaration(name: &Ident, param_types: &[Type], ret_type: &Type) -> String {
+    let params: Vec<String> = param_types
+        .iter()
+        .map(|param_type| match c_type(param_type) {
+            (typ, Some(ext)) => format!("{} {}", typ, ext),
+            (typ, None) => typ.to_string(),
+        })
+        .collect();
+    let ret_type = match c_type(ret_type) {
+        (typ, Some(ext)) => format!("{} {}", ext, typ),
+        (typ, None) => typ.to_string(),
+    };
+    format!("declare {} @{}({})", ret_type, name, params.join(", "))
+}
+
+// renames the functions declared by `extern func` in the program, whose names are those of the
+// C functions they call, e.g) `putchar` calling `putchar`
+fn rename_extern_funcs(e: Expr) -> Expr {
+    match e {
+        Expr::Func {
+            name,
+            param_name,
+            param_type,
+            ret_type,
+            box body,
+            box left,
+            pos,
+            doc,
+        } => {
+            let left = rename_extern_funcs(left);
+            let (name, left) = match body {
+                Expr::Extern(ref symbol, _, _, _) if symbol == &name => {
+                    let renamed = Ident::new(&format!("{}.<extern>", name));
+                    let left = left.rename_var(&name, &renamed);
+                    (renamed, left)
+                }
+                _ => (name, left),
+            };
+            Expr::Func {
+                name: name,
+                param_name: param_name,
+                param_type: param_type,
+                ret_type: ret_type,
+                body: box body,
+                left: box left,
+                pos: pos,
+                doc: doc,
+            }
+        }
+        Expr::Let(name, typ, e1, box left, pos, doc) => {
+            Expr::Let(name, typ, e1, box rename_extern_funcs(left), pos, doc)
+        }
+        Expr::LetType(name, typ, box left, doc) => {
+            Expr::LetType(name, typ, box rename_extern_funcs(left), doc)
+        }
+        e => e,
+    }
+}
+
+// a function standing in for the function `name` which another object file or a C library
+// defines, so that the program can call it. it is replaced with the declaration when the LLVM IR
+// is written.
+fn stand_in(
+    name: nf::ident::Ident,
+    params: Vec<(nf::ident::Ident, nf::Type)>,
+    ret_type: nf::Type,
+) -> nf::Func {
+    let args = params
+        .iter()
+        .map(|(param, typ)| match typ {
+            nf::Type::Func(_, _) => nf::Expr::Var(param.clone()),
+            _ => nf::Expr::Load(box nf::Expr::Var(param.clone())),
+        })
+        .collect();
+    nf::Func {
+        name: name.clone(),
+        params: params,
+        ret_type: ret_type,
+        // never called, only its type matters
+        body: nf::Expr::Call(box nf::Expr::Var(name), args),
+    }
+}
 
-    let mut ll_file = tempfile::Builder::new()
-        .suffix(".ll")
-        .tempfile()
-        .expect("failed: create temporary file.");
+// the stand-in of the function `name` of type `typ` of an imported module
+fn imported_stand_in(name: &Ident, typ: &Type) -> nf::Func {
+    let (param_type, ret_type) = match typ {
+        Type::Func(box ref param_type, box ref ret_type) => (param_type, ret_type),
+        _ => unreachable!(),
+    };
+    stand_in(
+        name.clone().to_nf_ident(),
+        vec![(Ident::new("<arg>").to_nf_ident(), conv_ty(param_type.clone()))],
+        conv_ty(ret_type.clone()),
+    )
+}
+
+// lambda lifting and the other preprocesses before the conversion into `nf`, where the functions
+// of `imports` are defined by the other object files
+pub fn lift(expr: Expr, imports: &[&Interface]) -> Expr {
+    let imported = imports
+        .iter()
+        .flat_map(|interface| interface.symbols.iter())
+        .map(|symbol| (symbol.name.clone(), symbol.typ.clone()))
+        .collect();
+    auxprocess::pre(rename_extern_funcs(expr), &imported)
+}
+
+// the lifted program in `nf` with the functions which it calls without defining them, i.e. the C
+// functions and those of `imports`. they are returned to be declared, with the declarations of the
+// C functions, which are not derived from their stand-ins.
+pub fn to_nf(lifted: Expr, imports: &[&Interface]) -> (nf::Nf, HashMap<String, Option<String>>) {
+    let mut externs = vec![];
+    extern_funcs(&lifted, &mut externs);
+    let mut declared: HashMap<String, Option<String>> = externs
+        .iter()
+        .map(|(func, declaration)| (func.name.0.clone(), Some(declaration.clone())))
+        .collect();
+    let mut funcs: Vec<nf::Func> = externs.into_iter().map(|(func, _)| func).collect();
+    let used = lifted.free_term_vars();
+    for symbol in imports.iter().flat_map(|interface| interface.symbols.iter()) {
+        if used.contains_key(&symbol.name) {
+            let func = imported_stand_in(&symbol.name, &symbol.typ);
+            declared.insert(func.name.0.clone(), None);
+            funcs.push(func);
+        }
+    }
+    let mut nf = conv_toplevel_expr(lifted);
+    nf.funcs.append(&mut funcs);
+    (nf, declared)
+}
 
-    if let Err(err) = nf.codegen("output", &mut ll_file) {
+fn write_llvm_ir_named<W: Write>(nf: &nf::Nf, name: &str, out: &mut W) {
+    if let Err(err) = nf.codegen(name, out) {
         eprintln!("\u{001B}[31m[internal codegen error]\u{001B}[39m {}", err);
         eprintln!(
             "please report this issue to akitsu-sanae <akitsu.sanae@gmail.com>, the developer of mumrik language"
         );
         std::process::exit(-1);
     }
+}
 
-    let ll_filename = ll_file.path().to_str().unwrap().to_string();
+// compiles the LLVM IR in `ll_filename` into an assembly (`filetype` is `asm`) or object (`obj`) file
+// which is position independent, since `gcc` links executables as PIE by default and shared
+// libraries need it too
+pub fn llc(ll_filename: &Path, filetype: &str, filename: &Path) {
+    let filetype = format!("-filetype={}", filetype);
+    let mut args = vec![filetype.as_str(), "-relocation-model=pic"];
+    args.extend(vec![
+        ll_filename.to_str().unwrap(),
+        "-o",
+        filename.to_str().unwrap(),
+    ]);
+    exec_command("llc", args);
+}
 
-    let obj_file = tempfile::Builder::new()
-        .suffix(".o")
-        .tempfile()
-        .expect("failed: create temporary file.");
+// compiles the C file `c_source` into a position independent object file with `compiler`,
+// which is invoked like `gcc`
+pub fn compile_c(compiler: &str, c_source: &Path, filename: &Path) -> Result<(), String> {
+    run_tool(
+        compiler,
+        vec![
+            "-c",
+            "-fPIC",
+            c_source.to_str().unwrap(),
+            "-o",
+            filename.to_str().unwrap(),
+        ],
+    )
+}
 
-    let obj_filename = obj_file.path().to_str().unwrap();
+// bundles the object files into the static library `filename`
+pub fn archive<P: AsRef<Path>>(obj_filenames: &[P], filename: &Path) -> Result<(), String> {
+    // `ar` keeps the members of an old archive
+    let _ = std::fs::remove_file(filename);
+    let mut args = vec!["rcs", filename.to_str().unwrap()];
+    args.extend(
+        obj_filenames
+            .iter()
+            .map(|obj_filename| obj_filename.as_ref().to_str().unwrap()),
+    );
+    run_tool("ar", args)
+}
 
-    exec_command(
-        "llc",
-        vec!["-filetype=obj", ll_filename.as_str(), "-o", obj_filename],
+// links the object files with `linker`, which is invoked like `gcc`, together with the C files
+// `c_sources` and the libraries `libs` into an executable, or a shared library if `shared`
+pub fn link<P: AsRef<Path>>(
+    linker: &str,
+    obj_filenames: &[P],
+    c_sources: &[PathBuf],
+    libs: &[String],
+    shared: bool,
+    filename: &Path,
+) -> Result<(), String> {
+    let libs: Vec<String> = libs.iter().map(|lib| format!("-l{}", lib)).collect();
+    let mut args: Vec<&str> = if shared { vec!["-shared"] } else { vec![] };
+    args.extend(
+        obj_filenames
+            .iter()
+            .map(|obj_filename| obj_filename.as_ref().to_str().unwrap()),
     );
-    exec_command("gcc", vec![obj_filename, "-o", filename.to_str().unwrap()]);
+    args.extend(c_sources.iter().map(|c_source| c_source.to_str().unwrap()));
+    args.push("-o");
+    args.push(filename.to_str().unwrap());
+    // libraries follow the objects referring to them
+    args.extend(libs.iter().map(|lib| lib.as_str()));
+    run_tool(linker, args)
 }
+
@@ -4,10 +4,19 @@ use std::collections::HashMap;
 
 type Params = (Ident, HashMap<Ident, Type>);
 
-pub fn pre(e: Expr) -> Expr {
-    let (f, e, appended_params) = lift_impl(e, &vec![]);
+// `imported` are the types of the functions which `e` calls without defining them, e.g) those
+// of the modules compiled into the other object files
+pub fn pre(e: Expr, imported: &HashMap<Ident, Type>) -> Expr {
+    let (f, e, mut appended_params) = lift_impl(e, &imported.keys().cloned().collect());
     let e = f(e);
-    let func_types = gather_func_types(&e);
+    let mut func_types = gather_func_types(&e);
+    func_types.extend(imported.clone());
+    // they capture no variables
+    appended_params.extend(
+        imported
+            .keys()
+            .map(|name| (name.clone(), (Ident::omitted_param_name(), HashMap::new()))),
+    );
     fix_param_type_toplevel(e, &func_types, &appended_params)
 }
 
@@ -23,7 +32,7 @@ fn lift_impl(
                 .filter(|(name, _)| !func_names.clone().contains(name))
                 .collect();
 
-            let (func_name, param_name, param_type, ret_type, body, left, pos) =
+            let (func_name, param_name, param_type, ret_type, body, left, pos, doc) =
                 if let Expr::Func {
                     name,
                     param_name,
@@ -32,9 +41,10 @@ fn lift_impl(
                     box body,
                     box left,
                     pos,
+                    doc,
                 } = e
                 {
-                    (name, param_name, param_type, ret_type, body, left, pos)
+                    (name, param_name, param_type, ret_type, body, left, pos, doc)
                 } else {
                     unreachable!()
                 };
@@ -72,6 +82,7 @@ fn lift_impl(
                     body: box body.clone(),
                     left: box left_f(body_f(e)),
                     pos: pos,
+                    doc: doc.clone(),
                 },
                 left,
                 appended_params,
@@ -115,7 +126,9 @@ fn lift_impl(
         | Expr::Const(Literal::Bool(_))
         | Expr::Const(Literal::Char(_))
         | Expr::Const(Literal::Unit)
-        | Expr::Var(_, _, _) => (box |e: Expr| e, e, HashMap::new()),
+        | Expr::Var(_, _, _)
+        | Expr::Extern(_, _, _, _)
+        | Expr::EmptyMark => (box |e: Expr| e, e, HashMap::new()),
         Expr::Apply(box e1, box e2, pos) => {
             let (f1, e1, appended_params1) = lift_impl(e1, func_names);
             let (f2, e2, appended_params2) = lift_impl(e2, func_names);
@@ -128,7 +141,7 @@ fn lift_impl(
                 appended_params,
             )
         }
-        Expr::Let(name, typ, box e1, box e2, pos) => {
+        Expr::Let(name, typ, box e1, box e2, pos, doc) => {
             let (f1, e1, appended_params1) = lift_impl(e1, func_names);
             let (f2, e2, appended_params2) = lift_impl(e2, func_names);
             let mut appended_params = HashMap::new();
@@ -136,11 +149,12 @@ fn lift_impl(
             appended_params.extend(appended_params2);
             (
                 box move |e: Expr| f1(f2(e)),
-                Expr::Let(name, typ, box e1, box e2, pos),
+                Expr::Let(name, typ, box e1, box e2, pos, doc),
                 appended_params,
             )
         }
-        Expr::LetType(_, _, _) => unreachable!(),
+        // types are resolved by typecheck, e.g) those exported by imported modules
+        Expr::LetType(_, _, box e, _) => lift_impl(e, func_names),
         Expr::If(box cond, box e1, box e2, pos) => {
             let (f_cond, cond, appended_params_cond) = lift_impl(cond, func_names);
             let (f1, e1, appended_params1) = lift_impl(e1, func_names);
@@ -207,7 +221,7 @@ fn lift_impl(
                 appended_params,
             )
         }
-        Expr::EmptyMark => unreachable!(),
+        Expr::Assert(_, _) => unreachable!(),
     }
 }
 
@@ -244,6 +258,7 @@ fn fix_param_type_toplevel(
         box body,
         box left,
         pos,
+        doc,
     } = e
     {
         let body = fix_param_type_inner(body, func_types, appended_params);
@@ -256,6 +271,7 @@ fn fix_param_type_toplevel(
             body: box body,
             left: box left,
             pos: pos,
+            doc: doc,
         }
     } else {
         fix_param_type_inner(e, func_types, appended_params)
@@ -269,8 +285,11 @@ fn fix_param_type_inner(
 ) -> Expr {
     match e {
         Expr::Const(lit) => Expr::Const(fix_param_type_literal(lit, func_types, appended_params)),
-        Expr::Var(_, _, _) => e,
-        Expr::Func { .. } | Expr::LetType(_, _, _) => unreachable!(),
+        // the arguments of a C function are the parameters of its declaration
+        Expr::Var(_, _, _) | Expr::Extern(_, _, _, _) => e,
+        // e.g) the rest of a library
+        Expr::EmptyMark => e,
+        Expr::Func { .. } | Expr::LetType(_, _, _, _) => unreachable!(),
         Expr::Apply(box Expr::Var(func_name, _, f_pos), box arg, app_pos) => {
             let func_type = func_types.get(&func_name).unwrap().clone();
             let appended_param = appended_params.get(&func_name).unwrap().clone();
@@ -283,13 +302,15 @@ fn fix_param_type_inner(
             box Expr::Var(rhs_name, rhs_typ @ Type::Func { .. }, pos),
             box e2,
             _,
+            _,
         ) => e2.subst_expr(&name, &Expr::Var(rhs_name, rhs_typ, pos)),
-        Expr::Let(name, typ, box e1, box e2, pos) => Expr::Let(
+        Expr::Let(name, typ, box e1, box e2, pos, doc) => Expr::Let(
             name,
             typ,
             box fix_param_type_inner(e1, func_types, appended_params),
             box fix_param_type_inner(e2, func_types, appended_params),
             pos,
+            doc,
         ),
         Expr::If(box cond, box e1, box e2, pos) => Expr::If(
             box fix_param_type_inner(cond, func_types, appended_params),
@@ -322,7 +343,7 @@ fn fix_param_type_inner(
         Expr::Println(box e) => {
             Expr::Println(box fix_param_type_inner(e, func_types, appended_params))
         }
-        Expr::EmptyMark => unreachable!(),
+        Expr::Assert(_, _) => unreachable!(),
     }
 }
 
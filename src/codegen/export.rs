@@ -0,0 +1,210 @@
+use super::{conv_ty, sorted_fields};
+use ast::*;
+use ident::Ident;
+use std::collections::HashSet;
+
+// a top-level function of a library, which C programs call by `symbol`
+pub struct Export {
+    pub name: Ident,
+    // e.g) `calc_add` for `add` of the library `calc`
+    pub symbol: String,
+    pub param_name: Ident,
+    pub param_type: Type,
+    pub ret_type: Type,
+    // `///` comments of the function
+    pub doc: Option<String>,
+}
+
+impl Export {
+    // the parameters of the C function, which are the fields of a record parameter in the order
+    // of their labels. `Unit` is passed as no argument.
+    fn c_params(&self) -> Vec<(Ident, Type)> {
+        let mut params: Vec<(Ident, Type)> = match self.param_type {
+            Type::Record(ref fields) => fields
+                .iter()
+                .map(|(label, typ)| (label.clone(), typ.clone()))
+                .collect(),
+            ref typ => vec![(self.param_name.clone(), typ.clone())],
+        };
+        params.retain(|(_, typ)| *typ != Type::Unit);
+        params.sort_by(|(label1, _), (label2, _)| label1.cmp(label2));
+        params
+    }
+}
+
+fn c_type(typ: &Type) -> Option<&'static str> {
+    match typ {
+        Type::Int => Some("int32_t"),
+        Type::Bool => Some("bool"),
+        Type::Char => Some("char"),
+        Type::Unit => Some("void"),
+        _ => None,
+    }
+}
+
+// the parameter, the return type and the doc comment of the top-level function `name` of `e`
+fn find_func<'a>(
+    e: &'a Expr,
+    name: &Ident,
+) -> Option<(&'a Ident, &'a Type, &'a Type, &'a Option<String>)> {
+    match e {
+        Expr::Func {
+            name: ref func_name,
+            ref param_name,
+            ref param_type,
+            ref ret_type,
+            ref doc,
+            ..
+        } if func_name == name => Some((param_name, param_type, ret_type, doc)),
+        Expr::Func { box ref left, .. }
+        | Expr::Let(_, _, _, box ref left, _, _)
+        | Expr::LetType(_, _, box ref left, _) => find_func(left, name),
+        _ => None,
+    }
+}
+
+// whether `name` is a type defined in `e`, which C programs never refer to
+fn is_type(e: &Expr, name: &Ident) -> bool {
+    match e {
+        Expr::LetType(ref type_name, _, _, _) if type_name == name => true,
+        Expr::Func { box ref left, .. }
+        | Expr::Let(_, _, _, box ref left, _, _)
+        | Expr::LetType(_, _, box ref left, _) => is_type(left, name),
+        _ => false,
+    }
+}
+
+// the functions of `names` in the typed program `e`, whose lambda lifted program is `lifted`.
+// the types of `names` are not exported, and the other definitions, e.g) `pub let`, can not be.
+pub fn exports(
+    e: &Expr,
+    lifted: &Expr,
+    names: &[Ident],
+    prefix: &str,
+) -> Result<Vec<Export>, String> {
+    let mut exports = vec![];
+    for name in names.iter() {
+        let (_, param_type, ret_type, doc) = match find_func(e, name) {
+            Some(func) => func,
+            None if is_type(e, name) => continue,
+            None => {
+                return Err(format!(
+                    "`{}` is not a function, which a library can not export, declare it without `pub`",
+                    name
+                ))
+            }
+        };
+        let (lifted_param_name, lifted_param_type, _, _) = find_func(lifted, name).unwrap();
+        if lifted_param_type != param_type {
+            return Err(format!(
+                "`{}` refers to top-level `let`s, which a library never initializes",
+                name
+            ));
+        }
+        let params_ok = match param_type {
+            Type::Record(ref fields) => fields.values().all(|typ| c_type(typ).is_some()),
+            typ => c_type(typ).is_some(),
+        };
+        if !params_ok || c_type(ret_type).is_none() {
+            return Err(format!(
+                "`{}` of type `{}` can not be called from C, which passes only `Int`, `Bool`, `Char`, `Unit` and records of them",
+                name,
+                Type::Func(box param_type.clone(), box ret_type.clone())
+            ));
+        }
+        exports.push(Export {
+            name: name.clone(),
+            symbol: format!("{}_{}", prefix, name),
+            param_name: lifted_param_name.clone(),
+            param_type: param_type.clone(),
+            ret_type: ret_type.clone(),
+            doc: doc.clone(),
+        });
+    }
+    Ok(exports)
+}
+
+fn argument(name: &Ident, typ: &Type) -> nf::Expr {
+    match typ {
+        Type::Unit => nf::Expr::Const(nf::Literal::Int(0)), // dummy
+        _ => nf::Expr::Load(box nf::Expr::Var(name.clone().to_nf_ident())),
+    }
+}
+
+// the C function calling the function of `export`
+fn wrapper(export: &Export) -> nf::Func {
+    let arg = match export.param_type {
+        Type::Record(ref fields) => nf::Expr::Const(nf::Literal::Tuple(
+            sorted_fields(fields.clone())
+                .iter()
+                .map(|(label, typ)| argument(label, typ))
+                .collect(),
+        )),
+        ref typ => argument(&export.param_name, typ),
+    };
+    nf::Func {
+        name: Ident::new(&export.symbol).to_nf_ident(),
+        params: export
+            .c_params()
+            .into_iter()
+            .map(|(name, typ)| (name.to_nf_ident(), conv_ty(typ)))
+            .collect(),
+        ret_type: conv_ty(export.ret_type.clone()),
+        body: nf::Expr::Call(
+            box nf::Expr::Var(export.name.clone().to_nf_ident()),
+            vec![arg],
+        ),
+    }
+}
+
+// adds the C functions of `exports` to the program `nf`, returning their symbols, which are the
+// only functions its object file exports. a library has no `main`, so its top-level `let`s are
+// dropped.
+pub fn export(nf: &mut nf::Nf, exports: &[Export]) -> HashSet<String> {
+    nf.body = None;
+    for export in exports.iter() {
+        nf.funcs.push(wrapper(export));
+    }
+    exports.iter().map(|export| export.symbol.clone()).collect()
+}
+
+// the C header declaring the functions of `exports` of the library `name`
+pub fn header(name: &str, exports: &[Export]) -> String {
+    let guard = format!(
+        "{}_H",
+        name.to_uppercase()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    );
+    let mut out = format!(
+        "// the functions of `{}`, generated by `mumrik build --lib`\n#ifndef {}\n#define {}\n\n",
+        name, guard, guard
+    );
+    out += "#include <stdbool.h>\n#include <stdint.h>\n\n";
+    out += "#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n";
+    for export in exports.iter() {
+        if let Some(ref doc) = export.doc {
+            for line in doc.lines() {
+                out += if line.is_empty() { "//" } else { "// " };
+                out += line;
+                out += "\n";
+            }
+        }
+        let params: Vec<String> = export
+            .c_params()
+            .into_iter()
+            .map(|(name, typ)| format!("{} {}", c_type(&typ).unwrap(), name))
+            .collect();
+        out += &format!(
+            "{} {}({});\n",
+            c_type(&export.ret_type).unwrap(),
+            export.symbol,
+            if params.is_empty() {
+                "void".to_string()
+            } else {
+                params.join(", ")
+            }
+        );
+    }
+    out += "\n#ifdef __cplusplus\n}\n#endif\n\n#endif\n";
+    out
+}
@@ -0,0 +1,296 @@
+use ast::*;
+use ident::Ident;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+// the functions which an object file exports, so that it can be linked without its source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interface {
+    pub module: String,
+    pub symbols: Vec<Symbol>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Symbol {
+    // the mangled name, e.g) `lib.math.gcd`
+    pub name: Ident,
+    pub typ: Type,
+}
+
+// a module which is compiled into its own object file, declaring the functions of its imports
+pub struct Unit {
+    // e.g) `lib.math`
+    pub name: String,
+    // the typed definitions of the module, ending in `EmptyMark`
+    pub expr: Expr,
+    pub interface: Interface,
+    // the interface of each module it imports
+    pub imports: Vec<Interface>,
+}
+
+// whether the definitions `e` of a module can be compiled without the program importing it,
+// i.e. their types are all known and they have no top-level `let`s, which only `main` evaluates.
+// e.g) those of `std.array` take arrays of the type which the program decides.
+fn is_closed(e: &Expr) -> bool {
+    if !e.type_vars().is_empty() {
+        return false;
+    }
+    let mut e = e;
+    loop {
+        e = match e {
+            Expr::Func { box ref left, .. } | Expr::LetType(_, _, box ref left, _) => left,
+            Expr::EmptyMark => return true,
+            _ => return false,
+        }
+    }
+}
+
+// the unit of the module `name` whose definitions are `e` with the mangled names of its `pub`
+// definitions `exports`, where `imports` are the units of the modules it imports. `None` if the
+// module is compiled with the program, which is also the case when any of its imports is.
+pub fn unit(name: &str, e: &Expr, exports: &[Ident], imports: &[Option<&Unit>]) -> Option<Unit> {
+    let imports: Option<Vec<Interface>> = imports
+        .iter()
+        .map(|unit| unit.map(|unit| unit.interface.clone()))
+        .collect();
+    let imports = imports?;
+    if !is_closed(e) {
+        return None;
+    }
+    Some(Unit {
+        name: name.to_string(),
+        expr: e.clone(),
+        interface: interface(name, e, exports),
+        imports: imports,
+    })
+}
+
+// the functions of `exports` among the definitions `e` of the module `name`. lambda lifting keeps
+// their types, since the functions of a unit capture no variables.
+fn interface(name: &str, e: &Expr, exports: &[Ident]) -> Interface {
+    let mut symbols = vec![];
+    let mut e = e;
+    loop {
+        e = match e {
+            Expr::Func {
+                name: ref func_name,
+                ref param_type,
+                ref ret_type,
+                box ref left,
+                ..
+            } => {
+                if exports.contains(func_name) {
+                    symbols.push(Symbol {
+                        name: func_name.clone(),
+                        typ: Type::Func(box param_type.clone(), box ret_type.clone()),
+                    });
+                }
+                left
+            }
+            Expr::Let(_, _, _, box ref left, _, _) | Expr::LetType(_, _, box ref left, _) => left,
+            _ => break,
+        }
+    }
+    Interface {
+        module: name.to_string(),
+        symbols: symbols,
+    }
+}
+
+// the symbol which `s` starts with, e.g) `lib.math.gcd(...` or `"<fresh-1>"(...`
+fn symbol(s: &str) -> &str {
+    if s.starts_with('"') {
+        let end = s[1..].find('"').map_or(s.len(), |idx| idx + 2);
+        &s[..end]
+    } else {
+        let end = s
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "-$._".contains(c)))
+            .unwrap_or(s.len());
+        &s[..end]
+    }
+}
+
+fn unquote(symbol: &str) -> &str {
+    symbol.trim_matches('"')
+}
+
+fn strip_linkage(s: &str) -> &str {
+    ["private ", "internal "]
+        .iter()
+        .fold(s, |s, linkage| s.trim_start_matches(linkage))
+}
+
+// the function which the `define` line `line` defines
+fn defined_symbol(line: &str) -> Option<&str> {
+    if !line.starts_with("define ") {
+        return None;
+    }
+    let rest = &line["define ".len()..];
+    rest.find('@')
+        .map(|idx| unquote(symbol(&rest[idx + 1..])))
+}
+
+// the attribute extending a value of the LLVM type `typ` to `int` as C does, see `c_type`
+fn extension(typ: &str) -> Option<&'static str> {
+    match typ {
+        "i1" => Some("zeroext"),
+        "i8" => Some("signext"),
+        _ => None,
+    }
+}
+
+// the parameters of a signature separated by the commas which no type encloses,
+// e.g) `{i32, i1} %p, i8 %c` into `{i32, i1} %p` and `i8 %c`
+fn split_params(params: &str) -> Vec<&str> {
+    let mut depth = 0;
+    let mut start = 0;
+    let mut result = vec![];
+    for (idx, c) in params.char_indices() {
+        match c {
+            '{' | '[' | '(' | '<' => depth += 1,
+            '}' | ']' | ')' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(params[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => (),
+        }
+    }
+    if !params[start..].trim().is_empty() {
+        result.push(params[start..].trim());
+    }
+    result
+}
+
+// the signature `sig` of a function which other object files call, e.g) `i1 @f(i8 %c) {`, where
+// `Bool` and `Char` are extended as C does, e.g) `zeroext i1 @f(i8 signext %c) {`
+fn extend(sig: &str) -> String {
+    let (ret_type, rest) = match sig.find(" @") {
+        Some(idx) => (&sig[..idx], &sig[idx..]),
+        None => return sig.to_string(),
+    };
+    let open = match rest.find('(') {
+        Some(idx) => idx,
+        None => return sig.to_string(),
+    };
+    let close = match rest.rfind(')') {
+        Some(idx) => idx,
+        None => return sig.to_string(),
+    };
+    let params: Vec<String> = split_params(&rest[open + 1..close])
+        .into_iter()
+        .map(|param| {
+            let mut words = param.splitn(2, ' ');
+            let typ = words.next().unwrap_or("");
+            let name = words.next();
+            match (extension(typ), name) {
+                (Some(ext), None) => format!("{} {}", typ, ext),
+                (Some(ext), Some(name)) if name.starts_with('%') => {
+                    format!("{} {} {}", typ, ext, name)
+                }
+                _ => param.to_string(),
+            }
+        })
+        .collect();
+    let ret_type = match extension(ret_type) {
+        Some(ext) => format!("{} {}", ext, ret_type),
+        None => ret_type.to_string(),
+    };
+    format!(
+        "{}{}{}{}",
+        ret_type,
+        &rest[..open + 1],
+        params.join(", "),
+        &rest[close..]
+    )
+}
+
+// `nf` has no declarations, so the functions which the object file only calls are defined by
+// stand-ins, see `to_nf`. this replaces them in the LLVM IR `ll` with the declarations of
+// `declared`, which are those of their signatures unless given, and gives the functions of
+// `exports` external linkage and the others internal. the functions called across object files
+// extend `Bool` and `Char` as C does, since C programs call those of a library. globals such as
+// string constants are written into every object file, so they become internal not to clash.
+fn declare(
+    ll: &str,
+    declared: &HashMap<String, Option<String>>,
+    exports: &HashSet<String>,
+) -> String {
+    let mut out = String::new();
+    let mut lines = ll.lines();
+    while let Some(line) = lines.next() {
+        match defined_symbol(line) {
+            Some(name) => {
+                let rest = strip_linkage(&line["define ".len()..]);
+                let has_body = line.trim_end().ends_with('{');
+                if let Some(declaration) = declared.get(name) {
+                    match declaration {
+                        Some(declaration) => out += declaration,
+                        None => {
+                            // the signature ends with the last parenthesis before the body
+                            let end = rest.rfind(')').map_or(rest.len(), |idx| idx + 1);
+                            out += "declare ";
+                            out += &extend(&rest[..end]);
+                        }
+                    }
+                    out += "\n";
+                    if has_body {
+                        lines.by_ref().find(|line| *line == "}");
+                    }
+                    continue;
+                }
+                if exports.contains(name) {
+                    out += "define ";
+                    out += &extend(rest);
+                } else {
+                    out += "define internal ";
+                    out += rest;
+                }
+            }
+            None => match line.find(" = ") {
+                Some(idx)
+                    if line.starts_with('@')
+                        && strip_linkage(&line[idx + 3..]) == &line[idx + 3..]
+                        && !line[idx + 3..].starts_with("external ") =>
+                {
+                    out += &line[..idx + 3];
+                    out += "internal ";
+                    out += &line[idx + 3..];
+                }
+                _ => out += line,
+            },
+        }
+        out += "\n";
+    }
+    out
+}
+
+// writes the LLVM IR of the module `name` of `nf`, which exports the functions of `exports` and
+// declares those of `declared`, which the linker resolves
+pub fn write_llvm_ir<W: Write>(
+    name: &str,
+    nf: &nf::Nf,
+    exports: &HashSet<String>,
+    declared: &HashMap<String, Option<String>>,
+    out: &mut W,
+) {
+    let mut ll = vec![];
+    super::write_llvm_ir_named(nf, name, &mut ll);
+    let ll = String::from_utf8(ll).expect("LLVM IR is not UTF-8");
+    out.write_all(declare(&ll, declared, exports).as_bytes())
+        .expect("failed to write LLVM IR");
+}
+
+// writes the LLVM IR of the object file of `unit`, which is lowered apart from the program
+pub fn write_unit_llvm_ir<W: Write>(unit: &Unit, out: &mut W) {
+    let imports: Vec<&Interface> = unit.imports.iter().collect();
+    let (nf, declared) = super::to_nf(super::lift(unit.expr.clone(), &imports), &imports);
+    let exports = unit
+        .interface
+        .symbols
+        .iter()
+        .map(|symbol| format!("{}", symbol.name))
+        .collect();
+    write_llvm_ir(&unit.name, &nf, &exports, &declared, out);
+}
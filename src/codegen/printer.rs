@@ -0,0 +1,94 @@
+// e.g) the output of `mumrik build --emit nf`
+pub fn print_nf(nf: &nf::Nf) -> String {
+    let mut out = String::new();
+    for func in nf.funcs.iter() {
+        let params: Vec<String> = func
+            .params
+            .iter()
+            .map(|(name, typ)| format!("{}: {}", name.0, print_type(typ)))
+            .collect();
+        out += &format!(
+            "func {}({}) :{} {{\n    {}\n}}\n",
+            func.name.0,
+            params.join(", "),
+            print_type(&func.ret_type),
+            print_expr(&func.body)
+        );
+    }
+    if let Some(ref body) = nf.body {
+        out += &format!("{}\n", print_expr(body));
+    }
+    out
+}
+
+fn print_type(typ: &nf::Type) -> String {
+    match typ {
+        nf::Type::Int => "Int".to_string(),
+        nf::Type::Bool => "Bool".to_string(),
+        nf::Type::Char => "Char".to_string(),
+        nf::Type::Pointer(box ref typ) => format!("*{}", print_type(typ)),
+        nf::Type::Func(ref params, box ref ret) => {
+            let params: Vec<String> = params.iter().map(print_type).collect();
+            format!("({}) -> {}", params.join(", "), print_type(ret))
+        }
+        nf::Type::Tuple(ref typs) => {
+            let typs: Vec<String> = typs.iter().map(print_type).collect();
+            format!("({})", typs.join(", "))
+        }
+        nf::Type::Array(box ref typ, ref size) => format!("[{}; {}]", print_type(typ), size),
+    }
+}
+
+fn print_exprs(es: &[nf::Expr]) -> String {
+    let es: Vec<String> = es.iter().map(print_expr).collect();
+    es.join(", ")
+}
+
+fn print_expr(e: &nf::Expr) -> String {
+    match e {
+        nf::Expr::Const(nf::Literal::Int(ref n)) => format!("{}", n),
+        nf::Expr::Const(nf::Literal::Bool(ref b)) => format!("{}", b),
+        nf::Expr::Const(nf::Literal::Char(ref c)) => format!("{:?}", c),
+        nf::Expr::Const(nf::Literal::Tuple(ref es)) => format!("({})", print_exprs(es)),
+        nf::Expr::Const(nf::Literal::Array(ref es, _)) => format!("[{}]", print_exprs(es)),
+        nf::Expr::Var(ref name) => name.0.clone(),
+        nf::Expr::Load(box ref e) => format!("*{}", print_expr(e)),
+        nf::Expr::Call(box ref func, ref args) => {
+            format!("{}({})", print_expr(func), print_exprs(args))
+        }
+        nf::Expr::Let(ref name, ref typ, box ref e1, box ref e2) => format!(
+            "let {}: {} = {};\n    {}",
+            name.0,
+            print_type(typ),
+            print_expr(e1),
+            print_expr(e2)
+        ),
+        nf::Expr::If(box ref cond, box ref e1, box ref e2) => format!(
+            "if {} {{ {} }} else {{ {} }}",
+            print_expr(cond),
+            print_expr(e1),
+            print_expr(e2)
+        ),
+        nf::Expr::BinOp(ref op, box ref e1, box ref e2) => {
+            let op = match op {
+                nf::BinOp::Add => "+",
+                nf::BinOp::Sub => "-",
+                nf::BinOp::Mult => "*",
+                nf::BinOp::Div => "/",
+                nf::BinOp::Eq => "==",
+                nf::BinOp::Neq => "/=",
+                nf::BinOp::Lt => "<",
+                nf::BinOp::Gt => ">",
+            };
+            format!("({}) {} ({})", print_expr(e1), op, print_expr(e2))
+        }
+        nf::Expr::TupleAt(box ref e, ref n) => format!("{}.{}", print_expr(e), n),
+        nf::Expr::ArrayAt(box ref e1, box ref e2) => {
+            format!("{}[{}]", print_expr(e1), print_expr(e2))
+        }
+        nf::Expr::Assign(box ref e1, box ref e2) => {
+            format!("{} <- {}", print_expr(e1), print_expr(e2))
+        }
+        nf::Expr::PrintNum(box ref e) => format!("println {}", print_expr(e)),
+    }
+}
@@ -1,15 +1,53 @@
+use diagnostic::MessageFormat;
 use std::collections::VecDeque;
 use util;
 
-mod build;
+pub mod build;
+mod check;
+mod doc;
+mod eval;
+mod explain;
+mod fmt;
 mod install;
+mod lsp;
 mod new_;
+mod publish;
+mod repl;
 mod run;
+mod test;
+mod tree;
+mod watch;
+mod workspace;
 
 pub trait Command {
     fn work(self: Box<Self>);
 }
 
+pub fn parse_message_format(value: Option<String>) -> MessageFormat {
+    let value = value.unwrap_or_else(|| {
+        panic!(
+            "{}: format is required after `--message-format`",
+            util::alert("error")
+        )
+    });
+    MessageFormat::parse(value.as_str()).unwrap_or_else(|| {
+        panic!(
+            "{}: unknown message format `{}`, expected `human` or `json`",
+            util::alert("error"),
+            value
+        )
+    })
+}
+
+pub fn parse_package(value: Option<String>) -> Option<String> {
+    Some(value.unwrap_or_else(|| {
+        panic!(
+            "{}: member name is required after `--package` or `-p`",
+            util::alert("error")
+        )
+    }))
+}
+
 #[derive(Clone)]
 enum ParamInfo {
     Subcommand {
@@ -102,16 +140,66 @@ pub fn parse_toplevel(mut args: VecDeque<String>) -> Box<dyn Command> {
             desc: "compile the local mumrik program and all of its dependencies",
             parse: build::BuildCommand::parse,
         },
+        ParamInfo::Subcommand {
+            name: "check",
+            desc: "parse and typecheck the local mumrik program without generating code",
+            parse: check::CheckCommand::parse,
+        },
         ParamInfo::Subcommand {
             name: "run",
             desc: "compile and run the local mumrik program",
             parse: run::RunCommand::parse,
         },
+        ParamInfo::Subcommand {
+            name: "test",
+            desc: "run the tests of the local mumrik program",
+            parse: test::TestCommand::parse,
+        },
+        ParamInfo::Subcommand {
+            name: "eval",
+            desc: "run the local mumrik program with the interpreter",
+            parse: eval::EvalCommand::parse,
+        },
+        ParamInfo::Subcommand {
+            name: "repl",
+            desc: "evaluate definitions and expressions interactively",
+            parse: repl::ReplCommand::parse,
+        },
+        ParamInfo::Subcommand {
+            name: "doc",
+            desc: "generate HTML or Markdown documentation of the local mumrik program",
+            parse: doc::DocCommand::parse,
+        },
+        ParamInfo::Subcommand {
+            name: "explain",
+            desc: "print a detailed explanation of an error code",
+            parse: explain::ExplainCommand::parse,
+        },
+        ParamInfo::Subcommand {
+            name: "fmt",
+            desc: "format mumrik source files, keeping comments",
+            parse: fmt::FmtCommand::parse,
+        },
+        ParamInfo::Subcommand {
+            name: "lsp",
+            desc: "run a language server over stdio for editors",
+            parse: lsp::LspCommand::parse,
+        },
         ParamInfo::Subcommand {
             name: "install",
-            desc: "download git repository from github for the local mumrik project",
+            desc: "add a git repository on github to the dependencies of the local mumrik project",
             parse: install::InstallCommand::parse,
         },
+        ParamInfo::Subcommand {
+            name: "publish",
+            desc: "package the local mumrik project into a registry",
+            parse: publish::PublishCommand::parse,
+        },
+        ParamInfo::Subcommand {
+            name: "tree",
+            desc: "print the resolved dependencies of the local mumrik project as a tree",
+            parse: tree::TreeCommand::parse,
+        },
     ];
 
     let program_name = args.pop_front().unwrap();
@@ -1,16 +1,115 @@
 use ast;
 use codegen;
-use command::Command;
+use command::{self, watch, workspace, Command};
 use config;
+use diagnostic::{self, code, Diagnostic, MessageFormat, Span};
+use ident::Ident;
+use lexer;
+use lint;
+use package::{self, sha256::sha256};
 use parser;
-use std::collections::VecDeque;
-use std::path::PathBuf;
+use serde_json;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use stdlib;
+use tempfile;
 use typecheck;
 use util;
 
+mod cache;
+pub mod graph;
+mod module;
+
+use self::graph::{Graph, Node};
+pub use self::graph::files as import_closure;
+pub use self::module::Module;
+
 pub struct BuildCommand {
     pub src: Option<PathBuf>,
     pub output: Option<PathBuf>,
+    pub message_format: MessageFormat,
+    // requested artifacts with their destinations, builds the executable when empty
+    pub emit: Vec<(Emit, Option<PathBuf>)>,
+    pub watch: bool,
+    // the member of the workspace to run in
+    pub package: Option<String>,
+    // whether the program is built as a C library instead of the executable, which `--lib`
+    // forces even without `[lib]`, as `[lib]` of the config says when `None`
+    pub lib: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Emit {
+    Tokens,
+    Ast,
+    TypedAst,
+    Lifted,
+    Nf,
+    LlvmIr,
+    Asm,
+    Obj,
+    Link,
+}
+
+impl Emit {
+    fn parse(s: &str) -> Option<Emit> {
+        match s {
+            "tokens" => Some(Emit::Tokens),
+            "ast" => Some(Emit::Ast),
+            "typed-ast" => Some(Emit::TypedAst),
+            "lifted" => Some(Emit::Lifted),
+            "nf" => Some(Emit::Nf),
+            "llvm-ir" => Some(Emit::LlvmIr),
+            "asm" => Some(Emit::Asm),
+            "obj" => Some(Emit::Obj),
+            "link" => Some(Emit::Link),
+            _ => None,
+        }
+    }
+
+    // the default destination, next to `output`
+    fn default_path(self, output: &PathBuf) -> PathBuf {
+        let extension = match self {
+            Emit::Tokens => "tokens",
+            Emit::Ast => "ast",
+            Emit::TypedAst => "typed-ast",
+            Emit::Lifted => "lifted",
+            Emit::Nf => "nf",
+            Emit::LlvmIr => "ll",
+            Emit::Asm => "s",
+            Emit::Obj => "o",
+            Emit::Link => return output.clone(),
+        };
+        output.with_extension(extension)
+    }
+}
+
+// e.g) `ast,llvm-ir=out.ll,nf=-`, where `-` is stdout
+fn parse_emit(value: Option<String>) -> Vec<(Emit, Option<PathBuf>)> {
+    let value = value.unwrap_or_else(|| {
+        panic!(
+            "{}: artifact kinds are required after `--emit`",
+            util::alert("error")
+        )
+    });
+    value
+        .split(',')
+        .map(|kind| {
+            let (kind, path) = match kind.find('=') {
+                Some(idx) => (&kind[..idx], Some(PathBuf::from(&kind[idx + 1..]))),
+                None => (kind, None),
+            };
+            let kind = Emit::parse(kind).unwrap_or_else(|| {
+                panic!(
+                    "{}: unknown artifact kind `{}`, expected one of {}",
+                    util::alert("error"),
+                    kind,
+                    "`tokens`, `ast`, `typed-ast`, `lifted`, `nf`, `llvm-ir`, `asm`, `obj` or `link`"
+                )
+            });
+            (kind, path)
+        })
+        .collect()
 }
 
 fn print_help(program_name: &str) {
@@ -20,6 +119,18 @@ USAGE: {} build [options...] <filename>
 
 options:
     --output, -o <output-filename>  write brinary code to <output-filename>, default: `a.out`
+    --message-format <format>       `human` or `json`, default: `human`
+    --emit <kind>[=<path>],...      write the artifacts of compiler stages instead of the
+                                    executable, next to <output-filename> by default or to
+                                    stdout with `-`. <kind> is one of `tokens`, `ast`,
+                                    `typed-ast`, `lifted`, `nf`, `llvm-ir`, `asm`, `obj`
+                                    and `link`, the executable
+    --watch                         rebuild whenever the program or the config file changes
+    --lib                           build a C library exporting the `pub` functions into
+                                    `build/lib<name>.a`, `build/lib<name>.so` and
+                                    `build/<name>.h`, which `[lib]` of the config file sets up
+    --package, -p <member>          build only <member> of the workspace, which builds all of
+                                    its members in its root
     --help, -h           print help information
 
 filename: input mumrik program filename"#,
@@ -31,6 +142,11 @@ impl BuildCommand {
     pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
         let mut src = None;
         let mut output = None;
+        let mut message_format = MessageFormat::Human;
+        let mut emit = vec![];
+        let mut watch = false;
+        let mut package = None;
+        let mut lib = None;
 
         while let Some(arg) = args.pop_front() {
             if arg.as_str() == "--help" || arg.as_str() == "-h" {
@@ -46,6 +162,22 @@ impl BuildCommand {
                 output = Some(PathBuf::from(arg[9..].to_string()));
             } else if arg.as_str().starts_with("-o=") {
                 output = Some(PathBuf::from(arg[3..].to_string()));
+            } else if arg.as_str() == "--message-format" {
+                message_format = command::parse_message_format(args.pop_front());
+            } else if arg.as_str().starts_with("--message-format=") {
+                message_format = command::parse_message_format(Some(arg[17..].to_string()));
+            } else if arg.as_str() == "--watch" {
+                watch = true;
+            } else if arg.as_str() == "--package" || arg.as_str() == "-p" {
+                package = command::parse_package(args.pop_front());
+            } else if arg.as_str().starts_with("--package=") {
+                package = command::parse_package(Some(arg[10..].to_string()));
+            } else if arg.as_str() == "--lib" {
+                lib = Some(true);
+            } else if arg.as_str() == "--emit" {
+                emit.append(&mut parse_emit(args.pop_front()));
+            } else if arg.as_str().starts_with("--emit=") {
+                emit.append(&mut parse_emit(Some(arg[7..].to_string())));
             } else if src.is_some() {
                 panic!(
                     "{}: too many command line argument `{}`",
@@ -60,32 +192,459 @@ impl BuildCommand {
         box BuildCommand {
             src: src,
             output: output,
+            message_format: message_format,
+            emit: emit,
+            watch: watch,
+            package: package,
+            lib: lib,
         }
     }
 }
 
 impl Command for BuildCommand {
     fn work(self: Box<BuildCommand>) {
-        let src = if let Some(src) = self.src {
-            src
-        } else {
-            config::CONFIG.lock().unwrap().build.src.clone()
+        let succeeded = workspace::run_members(&self.package, &self.src, self.watch, || {
+            self.build()
+        })
+        .unwrap_or_else(|| self.build());
+        if !succeeded {
+            std::process::exit(-1);
+        }
+    }
+}
+
+impl BuildCommand {
+    // builds the project in the current directory, returns whether it succeeded
+    fn build(&self) -> bool {
+        package::ensure_fetched();
+        let (src, output, lib) = {
+            let config = config::CONFIG.lock().unwrap();
+            let lib = match self.lib {
+                Some(true) => Some(config.lib.clone().unwrap_or_default()),
+                Some(false) => None,
+                None => config.lib.clone(),
+            };
+            (
+                self.src.clone().unwrap_or_else(|| config.build.src.clone()),
+                self.output.clone().unwrap_or_else(|| config.build.output.clone()),
+                lib,
+            )
         };
-        let output = if let Some(output) = self.output {
-            output
-        } else {
-            config::CONFIG.lock().unwrap().build.output.clone()
+        if self.watch {
+            watch::watch(|| watch::import_closure(&src));
+        }
+        if !self.emit.is_empty() {
+            return match emit_artifacts(&src, &output, &self.emit, self.message_format) {
+                Ok(()) => true,
+                Err(diag) => {
+                    diag.emit(self.message_format);
+                    false
+                }
+            };
+        }
+        if let Some(ref lib) = lib {
+            return build_lib(&src, lib, self.message_format);
+        }
+        match check_modules(&src, self.message_format) {
+            Some(checked) => {
+                match compile_objects(checked.expr, &checked.imported, &checked.imports, None)
+                    .and_then(|objects| link(&objects, &output))
+                {
+                    Ok(()) => true,
+                    Err(diag) => {
+                        diag.emit(self.message_format);
+                        false
+                    }
+                }
+            }
+            None => false,
+        }
+    }
+}
+
+// the object files of the imported modules, and of the C files
+fn obj_dir() -> PathBuf {
+    package::build_dir().join("obj")
+}
+
+// links the object files into the executable `output` with the C files and the libraries of
+// the config
+fn link<P: AsRef<Path>>(objects: &[P], output: &Path) -> Result<(), Diagnostic> {
+    let config = config::CONFIG.lock().unwrap();
+    codegen::link(
+        &config.build.linker,
+        objects,
+        &config.build.c_sources,
+        &config.build.link_libs,
+        false,
+        output,
+    )
+    .map_err(command_failed)
+}
+
+// the error of a command run on the files of the project, e.g) the linker
+fn command_failed(msg: String) -> Diagnostic {
+    Diagnostic::error(code::COMMAND_FAILED, msg, None)
+}
+
+// builds the program in `src` as the C library `lib`, whose functions are the `pub` functions
+// of the program, returns whether it succeeded
+fn build_lib(src: &PathBuf, lib: &config::LibConfig, message_format: MessageFormat) -> bool {
+    let checked = match check_modules(src, message_format) {
+        Some(checked) => checked,
+        None => return false,
+    };
+    let expr = checked.expr;
+    let fail = |diag: Diagnostic| {
+        diag.emit(message_format);
+        false
+    };
+    if has_final_expr(&expr) {
+        return fail(Diagnostic::error(
+            code::INVALID_LIBRARY,
+            format!(
+                "`{}` has an expression after its definitions, which a library never evaluates",
+                src.display()
+            ),
+            None,
+        ));
+    }
+    let name = lib.name.clone().unwrap_or_else(|| {
+        match config::CONFIG.lock().unwrap().package {
+            Some(ref package) => package.name.clone(),
+            None => src
+                .file_stem()
+                .map_or("main".to_string(), |stem| stem.to_string_lossy().to_string()),
+        }
+    });
+    // symbols are prefixed by the name, e.g) `my_calc_add` of `add` in `my-calc`
+    let prefix = name.replace(|c: char| !c.is_ascii_alphanumeric() && c != '_', "_");
+    let exports = match codegen::exports(
+        &expr,
+        &codegen::lift(expr.clone(), &[]),
+        &checked.public,
+        &prefix,
+    ) {
+        Ok(exports) => exports,
+        Err(msg) => return fail(Diagnostic::error(code::INVALID_LIBRARY, msg, None)),
+    };
+
+    let mut objects = match compile_objects(
+        expr,
+        &checked.imported,
+        &checked.imports,
+        Some(&exports),
+    ) {
+        Ok(objects) => objects,
+        Err(diag) => return fail(diag),
+    };
+    let (linker, c_sources, link_libs) = {
+        let config = config::CONFIG.lock().unwrap();
+        (
+            config.build.linker.clone(),
+            config.build.c_sources.clone(),
+            config.build.link_libs.clone(),
+        )
+    };
+    let c_dir = obj_dir().join("c");
+    for c_source in c_sources.iter() {
+        if let Err(err) = std::fs::create_dir_all(&c_dir) {
+            return fail(unwritable(&c_dir, err));
+        }
+        let obj = c_object(&c_dir, c_source);
+        if let Err(msg) = codegen::compile_c(&linker, c_source, &obj) {
+            return fail(command_failed(msg));
+        }
+        objects.push(obj);
+    }
+    let dir = package::build_dir();
+    if let Err(err) = std::fs::create_dir_all(&dir) {
+        return fail(unwritable(&dir, err));
+    }
+    for kind in lib.kind.iter() {
+        let result = match kind {
+            config::LibKind::Static => {
+                codegen::archive(&objects, &dir.join(format!("lib{}.a", name)))
+            }
+            config::LibKind::Shared => codegen::link(
+                &linker,
+                &objects,
+                &[],
+                &link_libs,
+                true,
+                &dir.join(format!("lib{}.so", name)),
+            ),
         };
-        let mut entry_modules = vec![];
-        if let Some(src_dir) = src.as_path().parent() {
-            entry_modules.push(src_dir.to_path_buf());
+        if let Err(msg) = result {
+            return fail(command_failed(msg));
         }
-        entry_modules.push(config::CONFIG.lock().unwrap().build.dep.clone());
-        let (expr, _) = read_file(&src, &entry_modules);
-        codegen::codegen(expr, &output);
+    }
+    let header = dir.join(format!("{}.h", name));
+    match std::fs::write(&header, codegen::header(&name, &exports)) {
+        Ok(()) => true,
+        Err(err) => fail(unwritable(&header, err)),
     }
 }
 
+// the object file in `dir` of the C file `c_source`, e.g) `util-1f0c2a9e5b7d3c48.o` of `src/util.c`. it is
+// named after the whole path, since the C files of a library and of the members of a workspace
+// may share the name.
+fn c_object(dir: &Path, c_source: &Path) -> PathBuf {
+    let path = c_source
+        .canonicalize()
+        .unwrap_or_else(|_| c_source.to_path_buf());
+    let hash = sha256(path.to_string_lossy().as_bytes());
+    let stem = c_source.file_stem().unwrap_or_default().to_string_lossy();
+    dir.join(format!("{}-{}.o", stem, &hash[..16]))
+}
+
+// whether the program evaluates an expression after its definitions
+fn has_final_expr(e: &ast::Expr) -> bool {
+    match e {
+        ast::Expr::Let(ref name, _, _, box ref left, _, _)
+            if name != &Ident::new("<dummy-sequence>") =>
+        {
+            has_final_expr(left)
+        }
+        ast::Expr::Func { box ref left, .. } | ast::Expr::LetType(_, _, box ref left, _) => {
+            has_final_expr(left)
+        }
+        ast::Expr::EmptyMark => false,
+        _ => true,
+    }
+}
+
+// compiles each module which the typed program `expr` imports into its own object file if it
+// can be compiled apart, see `codegen::unit`, and the program with the rest of the definitions
+// spliced from `imported` into another. the object file of a module is reused while its
+// definitions and the interfaces of its imports are unchanged, and is also written into
+// `build/obj` with its interface. a library defines the C functions of `lib` in place of `main`.
+fn compile_objects(
+    expr: ast::Expr,
+    imported: &Module,
+    imports: &Imports,
+    lib: Option<&[codegen::Export]>,
+) -> Result<Vec<PathBuf>, Diagnostic> {
+    let mut units: Vec<Option<codegen::Unit>> = vec![];
+    for (node, module) in imports.graph.nodes.iter().zip(imports.modules.iter()) {
+        let exports: Vec<Ident> = module
+            .exports
+            .iter()
+            .map(|(_, mangled)| mangled.clone())
+            .collect();
+        let unit = {
+            let node_imports: Vec<Option<&codegen::Unit>> =
+                node.imports.iter().map(|&idx| units[idx].as_ref()).collect();
+            codegen::unit(&node.name, &module.expr, &exports, &node_imports)
+        };
+        units.push(unit);
+    }
+
+    let dir = obj_dir();
+    std::fs::create_dir_all(&dir).map_err(|err| unwritable(&dir, err))?;
+    let mut objects = vec![];
+    let mut unit_files = HashSet::new();
+    for (node, unit) in imports.graph.nodes.iter().zip(units.iter()) {
+        if let Some(ref unit) = *unit {
+            let obj = cache::unit_object(unit)?;
+            let path = dir.join(format!("{}.o", unit.name));
+            std::fs::copy(&obj, &path).map_err(|err| unwritable(&path, err))?;
+            let path = dir.join(format!("{}.json", unit.name));
+            std::fs::write(&path, serde_json::to_string_pretty(&unit.interface).unwrap())
+                .map_err(|err| unwritable(&path, err))?;
+            objects.push(obj);
+            unit_files.insert(node.file.clone());
+        }
+    }
+
+    let interfaces: Vec<&codegen::Interface> =
+        units.iter().flatten().map(|unit| &unit.interface).collect();
+    let expr = module::drop_modules(expr, &imported.files, &unit_files);
+    let (mut nf, declared) = codegen::to_nf(codegen::lift(expr, &interfaces), &interfaces);
+    let exports = match lib {
+        Some(exports) => codegen::export(&mut nf, exports),
+        None => std::iter::once("main".to_string()).collect(),
+    };
+    let mut ll = vec![];
+    codegen::write_llvm_ir("main", &nf, &exports, &declared, &mut ll);
+    objects.push(cache::object(&ll)?);
+    Ok(objects)
+}
+
+// the error of an output of the compiler which can not be written
+fn unwritable(path: &Path, err: std::io::Error) -> Diagnostic {
+    Diagnostic::error(
+        code::UNWRITABLE_FILE,
+        format!("can not write `{}`: {}", path.display(), err),
+        None,
+    )
+}
+
+fn is_stdout(path: &PathBuf) -> bool {
+    path.as_os_str() == "-"
+}
+
+fn write_artifact(path: &PathBuf, content: &[u8]) -> Result<(), Diagnostic> {
+    use std::io::Write;
+    if is_stdout(path) {
+        std::io::stdout()
+            .write_all(content)
+            .map_err(|err| unwritable(Path::new("<stdout>"), err))
+    } else {
+        std::fs::write(path, content).map_err(|err| unwritable(path, err))
+    }
+}
+
+fn temp_file(suffix: &str) -> Result<tempfile::NamedTempFile, Diagnostic> {
+    tempfile::Builder::new()
+        .suffix(suffix)
+        .tempfile()
+        .map_err(|err| unwritable(&std::env::temp_dir(), err))
+}
+
+// the content of the intermediate file `path`, which the compiler has just written
+fn read_artifact(path: &Path) -> Result<Vec<u8>, Diagnostic> {
+    std::fs::read(path).map_err(|_| {
+        Diagnostic::error(
+            code::UNREADABLE_FILE,
+            format!("can not read file: {}", path.display()),
+            None,
+        )
+    })
+}
+
+// runs `f` with the file to write the artifact into, which is a temporary file for stdout
+fn write_artifact_file<F: FnOnce(&Path) -> Result<(), Diagnostic>>(
+    path: &PathBuf,
+    suffix: &str,
+    f: F,
+) -> Result<(), Diagnostic> {
+    if is_stdout(path) {
+        let file = temp_file(suffix)?;
+        f(file.path())?;
+        write_artifact(path, &read_artifact(file.path())?)
+    } else {
+        f(path)
+    }
+}
+
+// the intermediate file of a stage, kept only when it is requested
+fn stage_file(
+    path: Option<&PathBuf>,
+    suffix: &str,
+) -> Result<(PathBuf, Option<tempfile::NamedTempFile>), Diagnostic> {
+    match path {
+        Some(path) if !is_stdout(path) => Ok((path.clone(), None)),
+        _ => {
+            let file = temp_file(suffix)?;
+            Ok((file.path().to_path_buf(), Some(file)))
+        }
+    }
+}
+
+fn emit_artifacts(
+    src: &PathBuf,
+    output: &PathBuf,
+    emit: &Vec<(Emit, Option<PathBuf>)>,
+    message_format: MessageFormat,
+) -> Result<(), Diagnostic> {
+    let dest = |kind: Emit| {
+        emit.iter()
+            .find(|(emit_kind, _)| *emit_kind == kind)
+            .map(|(_, path)| path.clone().unwrap_or_else(|| kind.default_path(output)))
+    };
+
+    if dest(Emit::Tokens).is_some() || dest(Emit::Ast).is_some() {
+        let input_src = read_text(src)?;
+        if let Some(path) = dest(Emit::Tokens) {
+            let tokens = lexer::tokenize(&input_src).map_err(|pos| {
+                Diagnostic::error(
+                    code::SYNTAX_ERROR,
+                    "unknown character".to_string(),
+                    Some(Span::new(src, &input_src, ast::Position { start: pos, end: pos + 1 })),
+                )
+            })?;
+            let text: String = tokens
+                .iter()
+                .map(|token| {
+                    let span = Span::new(src, &input_src, token.pos);
+                    format!(
+                        "{}:{}\t{:?}\t{:?}\n",
+                        span.line_start, span.column_start, token.kind, token.text
+                    )
+                })
+                .collect();
+            write_artifact(&path, text.as_bytes())?;
+        }
+        if let Some(path) = dest(Emit::Ast) {
+            let program = parser::program(&input_src)
+                .map_err(|err| Diagnostic::from_parse_error(src, &input_src, err))?;
+            write_artifact(&path, format!("{}", program).as_bytes())?;
+        }
+    }
+    if emit.iter().all(|(kind, _)| *kind <= Emit::Ast) {
+        return Ok(());
+    }
+
+    let (expr, _) = check_file(src, message_format).unwrap_or_else(|| std::process::exit(-1));
+    if let Some(path) = dest(Emit::TypedAst) {
+        write_artifact(&path, format!("{}\n", expr).as_bytes())?;
+    }
+    let lifted = codegen::lift(expr, &[]);
+    if let Some(path) = dest(Emit::Lifted) {
+        write_artifact(&path, format!("{}\n", lifted).as_bytes())?;
+    }
+    // the whole program in one module, which only declares the C functions
+    let (nf, declared) = codegen::to_nf(lifted, &[]);
+    if let Some(path) = dest(Emit::Nf) {
+        write_artifact(&path, codegen::print_nf(&nf).as_bytes())?;
+    }
+    if emit.iter().all(|(kind, _)| *kind <= Emit::Nf) {
+        return Ok(());
+    }
+
+    let mut ll = vec![];
+    let exports = std::iter::once("main".to_string()).collect();
+    codegen::write_llvm_ir("main", &nf, &exports, &declared, &mut ll);
+    let ll_dest = dest(Emit::LlvmIr);
+    let (ll_path, _ll_file) = stage_file(ll_dest.as_ref(), ".ll")?;
+    std::fs::write(&ll_path, &ll).map_err(|err| unwritable(&ll_path, err))?;
+    if let Some(ref path) = ll_dest {
+        if is_stdout(path) {
+            write_artifact(path, &ll)?;
+        }
+    }
+    if let Some(path) = dest(Emit::Asm) {
+        write_artifact_file(&path, ".s", |path| {
+            codegen::llc(&ll_path, "asm", path);
+            Ok(())
+        })?;
+    }
+    if dest(Emit::Obj).is_some() || dest(Emit::Link).is_some() {
+        let obj_dest = dest(Emit::Obj);
+        let (obj_path, _obj_file) = stage_file(obj_dest.as_ref(), ".o")?;
+        codegen::llc(&ll_path, "obj", &obj_path);
+        if let Some(ref path) = obj_dest {
+            if is_stdout(path) {
+                write_artifact(path, &read_artifact(&obj_path)?)?;
+            }
+        }
+        if let Some(path) = dest(Emit::Link) {
+            write_artifact_file(&path, "", |path| link(&[&obj_path], path))?;
+        }
+    }
+    Ok(())
+}
+
+pub fn entry_modules(src: &PathBuf) -> Vec<PathBuf> {
+    let mut entry_modules = vec![];
+    if let Some(src_dir) = src.as_path().parent() {
+        entry_modules.push(src_dir.to_path_buf());
+    }
+    entry_modules.push(config::CONFIG.lock().unwrap().build.dep.clone());
+    entry_modules
+}
+
 fn append_import_path(path: PathBuf, import: ast::Import) -> PathBuf {
     let mut path = import.dirs.into_iter().fold(path, |mut acc, dir| {
         acc.push(format!("{}", dir));
@@ -95,146 +654,485 @@ fn append_import_path(path: PathBuf, import: ast::Import) -> PathBuf {
     path
 }
 
-fn imported_filepath(entry_modules: &Vec<PathBuf>, import: ast::Import) -> Option<PathBuf> {
-    for entry_module in entry_modules.iter() {
-        let module_file_path = append_import_path(entry_module.clone(), import.clone());
-        if module_file_path.is_file() {
-            eprintln!("import: {}", module_file_path.to_str().unwrap());
-            return Some(module_file_path);
-        }
-    }
-    eprintln!("unknown import: {:?}", import); // TODO
-    panic!();
+// the file of `import` in the dependency which its first component names,
+// e.g) `foo.util` in the directory of the dependency `foo`
+fn dependency_import_path(import: &ast::Import) -> Option<PathBuf> {
+    let (dep, dirs) = import.dirs.split_first()?;
+    let root = package::root(&format!("{}", dep))?;
+    let import = ast::Import {
+        dirs: dirs.to_vec(),
+        ..import.clone()
+    };
+    Some(append_import_path(root, import))
 }
 
-fn read_file(input_path: &PathBuf, entry_modules: &Vec<PathBuf>) -> (ast::Expr, ast::Type) {
+// the file of the module `import` in `entry_modules`, the dependencies or `std`
+pub fn find_import(entry_modules: &Vec<PathBuf>, import: &ast::Import) -> Option<PathBuf> {
+    entry_modules
+        .iter()
+        .map(|entry_module| append_import_path(entry_module.clone(), import.clone()))
+        .chain(dependency_import_path(import))
+        .find(|module_file_path| module_file_path.is_file())
+        .or_else(|| stdlib::find(import))
+}
+
+// the source text in `input_path`, which is bundled if it is a module of `std`
+pub fn read_text(input_path: &PathBuf) -> Result<String, Diagnostic> {
     use std::io::Read;
+    if let Some(src) = stdlib::read(input_path) {
+        return Ok(src.to_string());
+    }
     let mut input_src = String::new();
     let f = std::fs::File::open(input_path).and_then(|mut f| f.read_to_string(&mut input_src));
     if !f.is_ok() {
-        eprintln!("can not read file: {}", input_path.to_str().unwrap());
-        std::process::exit(-1);
-    }
-
-    let lines: Vec<_> = input_src.split('\n').collect();
-    let program = match parser::program(&input_src) {
-        Ok(program) => program,
-        Err(err) => {
-            let msg = format!(
-                "{}\n{}\u{001B}[31m^\u{001B}[39m\nexpected: {}",
-                lines[err.location.line - 1],
-                " ".repeat(err.location.column - 1),
-                parser::Expected::from(err.expected),
-            );
-            eprintln!(
-                "\u{001B}[31m[syntax error]\u{001B}[39m at ({}, {})\n{}",
-                err.location.line, err.location.column, msg
-            );
-            std::process::exit(-1)
+        return Err(Diagnostic::error(
+            code::UNREADABLE_FILE,
+            format!("can not read file: {}", input_path.to_str().unwrap()),
+            None,
+        ));
+    }
+    Ok(input_src)
+}
+
+// parses and typechecks the program in `input_path`, then pushes lint results into `lints`
+pub fn read_file(
+    input_path: &PathBuf,
+    entry_modules: &Vec<PathBuf>,
+    lints: &mut Vec<Diagnostic>,
+) -> Result<(ast::Expr, ast::Type), Diagnostic> {
+    let input_src = read_text(input_path)?;
+    let (expr, typ, _) = read_source(input_path, &input_src, entry_modules, lints)?;
+    Ok((expr, typ))
+}
+
+// parses the program in `input_path` whose source text is `input_src`
+fn parse(input_path: &PathBuf, input_src: &str) -> Result<ast::Program, Diagnostic> {
+    let program = parse_with_tests(input_path, input_src)?;
+    if let Some(pos) = find_assert(&program.expr) {
+        return Err(Diagnostic::error(
+            code::ASSERT_OUTSIDE_TEST,
+            "`assert` is only allowed in tests".to_string(),
+            Some(Span::new(input_path, input_src, pos)),
+        ));
+    }
+    Ok(program)
+}
+
+// same as `parse`, but allows `assert` in the program
+fn parse_with_tests(input_path: &PathBuf, input_src: &str) -> Result<ast::Program, Diagnostic> {
+    let mut program = parser::program(input_src)
+        .map_err(|err| Diagnostic::from_parse_error(input_path, input_src, err))?;
+    // after the imports of the program, which bring their definitions into scope first
+    program.imports.extend(stdlib::prelude(input_path));
+    Ok(program)
+}
+
+// typechecks the modules of `graph` in order, which reuses their definitions while they are
+// unchanged
+fn load_modules(graph: &Graph, lints: &mut Vec<Diagnostic>) -> Result<Vec<Module>, Diagnostic> {
+    let mut modules: Vec<Module> = vec![];
+    // the modules which each module imports directly or indirectly
+    let mut deps: Vec<BTreeSet<usize>> = vec![];
+    for node in graph.nodes.iter() {
+        let mut node_deps = BTreeSet::new();
+        for &idx in node.imports.iter() {
+            node_deps.insert(idx);
+            node_deps.extend(deps[idx].iter().cloned());
         }
+        let module = match cache::lookup(&node.file, &node.src) {
+            Some(module) => module,
+            None => {
+                let imported = Module::concat(node_deps.iter().map(|&idx| modules[idx].clone()));
+                load_module(graph, node, &modules, imported, lints)?
+            }
+        };
+        modules.push(module);
+        deps.push(node_deps);
+    }
+    Ok(modules)
+}
+
+// typechecks the definitions of the module of `node` after those of its dependencies in
+// `imported`
+fn load_module(
+    graph: &Graph,
+    node: &Node,
+    modules: &[Module],
+    imported: Module,
+    lints: &mut Vec<Diagnostic>,
+) -> Result<Module, Diagnostic> {
+    let lint_num = lints.len();
+    let imports: Vec<&Module> = node.imports.iter().map(|&idx| &modules[idx]).collect();
+    let (program, _, mut warnings) =
+        resolve_imports(&node.file, &node.src, node.program.clone(), &imports)?;
+    let public = program.public;
+    let (expr, definitions) = module::mangle(program.expr, &node.name);
+    let (expr, _) = typecheck::check_module(replace_tail(&imported.expr, expr))
+        .map_err(|err| Diagnostic::from_type_error(&node.file, &node.src, err))?;
+    warnings.append(&mut lint::check_program(&expr, imported.files.len()));
+    lints.append(&mut lint::to_diagnostics(
+        &node.file,
+        &node.src,
+        warnings,
+        &config::CONFIG.lock().unwrap().lint,
+    ));
+    let files = definitions.iter().map(|_| node.file.clone()).collect();
+    let (exports, private): (Vec<_>, Vec<_>) = definitions
+        .into_iter()
+        .partition(|(name, _)| public.contains(name));
+    let module = Module {
+        expr: module::seal_fresh_names(
+            module::drop_definitions(expr, imported.files.len()),
+            &node.name,
+        ),
+        exports: exports,
+        private: private.into_iter().map(|(name, _)| name).collect(),
+        files: files,
     };
+    // modules with diagnostics are checked every time to report them again
+    if lints.len() == lint_num {
+        let deps = node
+            .imports
+            .iter()
+            .map(|&idx| graph.nodes[idx].file.clone())
+            .collect();
+        cache::store(&node.file, &node.src, &deps, &module);
+    }
+    Ok(module)
+}
 
-    let expr = program
-        .imports
+// the modules which a program imports directly or indirectly
+struct Imports {
+    graph: Graph,
+    // the typechecked definitions of each node of `graph`
+    modules: Vec<Module>,
+}
+
+// a typechecked program with the modules which it imports
+struct Checked {
+    expr: ast::Expr,
+    typ: ast::Type,
+    // the definitions spliced from the imported modules
+    imported: Module,
+    imports: Imports,
+    // the top-level definitions of the program declared `pub`
+    public: Vec<Ident>,
+}
+
+// same as `read_file` with the source text of `input_path` given, e.g. an unsaved buffer.
+// also returns the definitions spliced from the imported modules, exported by the names
+// the program refers to them.
+pub fn read_source(
+    input_path: &PathBuf,
+    input_src: &str,
+    entry_modules: &Vec<PathBuf>,
+    lints: &mut Vec<Diagnostic>,
+) -> Result<(ast::Expr, ast::Type, Module), Diagnostic> {
+    read_source_with_imports(input_path, input_src, entry_modules, lints)
+        .map(|checked| (checked.expr, checked.typ, checked.imported))
+}
+
+// same as `read_source`, but also returns the imported modules
+fn read_source_with_imports(
+    input_path: &PathBuf,
+    input_src: &str,
+    entry_modules: &Vec<PathBuf>,
+    lints: &mut Vec<Diagnostic>,
+) -> Result<Checked, Diagnostic> {
+    let program = parse(input_path, input_src)?;
+    let (program, imported, imports, mut warnings) =
+        splice_imports(input_path, input_src, program, entry_modules, lints)?;
+    let (expr, typ) = typecheck::check(replace_tail(&imported.expr, program.expr))
+        .map_err(|err| Diagnostic::from_type_error(input_path, input_src, err))?;
+    warnings.append(&mut lint::check_program(&expr, imported.files.len()));
+    lints.append(&mut lint::to_diagnostics(
+        input_path,
+        input_src,
+        warnings,
+        &config::CONFIG.lock().unwrap().lint,
+    ));
+    Ok(Checked {
+        expr: expr,
+        typ: typ,
+        imported: imported,
+        imports: imports,
+        public: program.public,
+    })
+}
+
+// parses the tests in `input_path` and typechecks each of them as a program,
+// which evaluates the body of the test after the definitions of the file
+pub fn read_tests(
+    input_path: &PathBuf,
+    entry_modules: &Vec<PathBuf>,
+    lints: &mut Vec<Diagnostic>,
+) -> Result<Vec<(ast::Test, ast::Expr)>, Diagnostic> {
+    let input_src = read_text(input_path)?;
+    let program = parse_with_tests(input_path, &input_src)?;
+    if program.tests.is_empty() {
+        return Ok(vec![]);
+    }
+    let (program, imported, _, _) =
+        splice_imports(input_path, &input_src, program, entry_modules, lints)?;
+    let expr = replace_tail(&imported.expr, program.expr);
+    program
+        .tests
         .into_iter()
-        .fold(program.expr, |acc, import| {
-            if let Some(file_pathbuf) = imported_filepath(entry_modules, import) {
-                let (expr, _) = read_file(&file_pathbuf, entry_modules);
-                match expr {
-                    ast::Expr::Let(name, typ, box e, box ast::Expr::EmptyMark, pos) => {
-                        ast::Expr::Let(name, typ, box e, box acc, pos)
-                    }
-                    ast::Expr::Func {
-                        name,
-                        param_name,
-                        param_type,
-                        ret_type,
-                        box body,
-                        left: box ast::Expr::EmptyMark,
-                        pos,
-                    } => ast::Expr::Func {
-                        name: name,
-                        param_name: param_name,
-                        param_type: param_type,
-                        ret_type: ret_type,
-                        body: box body,
-                        left: box acc,
-                        pos: pos,
-                    },
-                    _ => unreachable!(),
-                }
-            } else {
-                acc
-            }
-        });
-
-    match typecheck::check(expr) {
-        Ok((expr, typ)) => (expr, typ),
-        Err(err) => match err {
-            typecheck::Error::RecOccur { pos, var, typ } => {
-                let start = util::pos_to_location(&input_src, pos.start);
-                let end = util::pos_to_location(&input_src, pos.end);
-                eprintln!(
-                    "\u{001B}[31m[type error]\u{001B}[39m at ({}, {})-({}, {})",
-                    start.0, start.1, end.0, end.1
-                );
-                let lines: Vec<_> = input_src.split('\n').collect();
-                eprintln!("```");
-                for line_i in start.0..end.0 {
-                    eprintln!("{}", lines[line_i - 1]);
-                }
-                eprintln!("```");
-                eprintln!("type variable {} occurs recursively in {}", var, typ);
-                std::process::exit(-1)
-            }
-            typecheck::Error::Unify { pos, typ1, typ2 } => {
-                let start = util::pos_to_location(&input_src, pos.start);
-                let end = util::pos_to_location(&input_src, pos.end);
-                eprintln!(
-                    "\u{001B}[31m[type error]\u{001B}[39m at ({}, {})-({}, {})",
-                    start.0, start.1, end.0, end.1
-                );
-                let lines: Vec<_> = input_src.split('\n').collect();
-                eprintln!("```");
-                for line_i in start.0..end.0 {
-                    eprintln!("{}", lines[line_i]);
-                }
-                eprintln!("```");
-                eprintln!("unification failed with `{}` and `{}`", typ1, typ2);
-                std::process::exit(-1)
-            }
-            typecheck::Error::UnboundVar { pos, name } => {
-                let (line, column_start) = util::pos_to_location(&input_src, pos.start);
-                let (_, column_end) = util::pos_to_location(&input_src, pos.end);
-                eprintln!(
-                    "\u{001B}[31m[type error]\u{001B}[39m at line {}, unbound variable: {}",
-                    line, name
-                );
-                let lines: Vec<_> = input_src.split('\n').collect();
-                eprintln!("> {}", lines[line]);
-                eprintln!(
-                    "  {}{}",
-                    " ".repeat(column_start - 1),
-                    "^".repeat(column_end - column_start)
-                );
-                std::process::exit(-1)
+        .map(|test| {
+            let (e, _) = typecheck::check(replace_tail(&expr, test.body.clone()))
+                .map_err(|err| Diagnostic::from_type_error(input_path, &input_src, err))?;
+            Ok((test, e))
+        })
+        .collect()
+}
+
+// the definitions of `e`, followed by `tail` in place of the rest of the program
+fn replace_tail(e: &ast::Expr, tail: ast::Expr) -> ast::Expr {
+    match e {
+        ast::Expr::Let(ref name, ref typ, box ref init, box ref left, ref pos, ref doc)
+            if name != &Ident::new("<dummy-sequence>") =>
+        {
+            ast::Expr::Let(
+                name.clone(),
+                typ.clone(),
+                box init.clone(),
+                box replace_tail(left, tail),
+                *pos,
+                doc.clone(),
+            )
+        }
+        ast::Expr::Func {
+            ref name,
+            ref param_name,
+            ref param_type,
+            ref ret_type,
+            box ref body,
+            box ref left,
+            ref pos,
+            ref doc,
+        } => ast::Expr::Func {
+            name: name.clone(),
+            param_name: param_name.clone(),
+            param_type: param_type.clone(),
+            ret_type: ret_type.clone(),
+            body: box body.clone(),
+            left: box replace_tail(left, tail),
+            pos: *pos,
+            doc: doc.clone(),
+        },
+        ast::Expr::LetType(ref name, ref typ, box ref left, ref doc) => {
+            ast::Expr::LetType(name.clone(), typ.clone(), box replace_tail(left, tail), doc.clone())
+        }
+        _ => tail,
+    }
+}
+
+fn find_assert(e: &ast::Expr) -> Option<ast::Position> {
+    match e {
+        ast::Expr::Assert(_, ref pos) => Some(*pos),
+        _ => e.children().into_iter().filter_map(find_assert).next(),
+    }
+}
+
+fn unknown_member_error(
+    input_path: &PathBuf,
+    input_src: &str,
+    import: &ast::Import,
+    member: &Ident,
+    pos: ast::Position,
+    module: &Module,
+) -> Diagnostic {
+    if module.private.contains(member) {
+        return Diagnostic::error(
+            code::PRIVATE_DEFINITION,
+            format!(
+                "`{}` is private to module `{}`",
+                member,
+                import_name(import)
+            ),
+            Some(Span::new(input_path, input_src, pos)),
+        )
+        .with_note(format!(
+            "declare it as `pub` in module `{}` to use it here",
+            import_name(import)
+        ));
+    }
+    Diagnostic::error(
+        code::UNKNOWN_MEMBER,
+        format!(
+            "module `{}` has no definition `{}`",
+            import_name(import),
+            member
+        ),
+        Some(Span::new(input_path, input_src, pos)),
+    )
+    .with_suggestion(util::similar_name(
+        &format!("{}", member),
+        module.exports.iter().map(|(name, _)| format!("{}", name)),
+    ))
+}
+
+// refers the names of the program to the definitions of its imports, where `imports` are
+// the modules of them, returns it with the names it refers to the definitions by and
+// warnings for unused imports
+fn resolve_imports(
+    input_path: &PathBuf,
+    input_src: &str,
+    program: ast::Program,
+    imports: &[&Module],
+) -> Result<(ast::Program, Vec<(Ident, Ident)>, Vec<lint::Warning>), Diagnostic> {
+    let mut warnings = vec![];
+    let mut exports = vec![];
+    let mut program = program;
+    for (import, module) in program.imports.clone().into_iter().zip(imports.iter()) {
+        let members = module.members();
+        if let Some(ref items) = import.items {
+            if let Some(item) = items.iter().find(|item| !members.contains_key(item)) {
+                return Err(unknown_member_error(
+                    input_path, input_src, &import, item, import.pos, module,
+                ));
             }
-            typecheck::Error::Other { pos, message } => {
-                let start = util::pos_to_location(&input_src, pos.start);
-                let end = util::pos_to_location(&input_src, pos.end);
-                eprintln!(
-                    "\u{001B}[31m[type error]\u{001B}[39m at ({}, {})-({}, {})",
-                    start.0, start.1, end.0, end.1
-                );
-                let lines: Vec<_> = input_src.split('\n').collect();
-                eprintln!("```");
-                for line_i in start.0..end.0 {
-                    eprintln!("{}", lines[line_i]);
-                }
-                eprintln!("```");
-                eprintln!("{}", message);
-                std::process::exit(-1)
+        }
+
+        // the names by which the program refers to the definitions of the module
+        exports.extend(members.iter().filter_map(|(name, member)| match import.items {
+                Some(ref items) if items.contains(name) => Some((name.clone(), member.clone())),
+                Some(_) => None,
+                None => Some((
+                    Ident::new(&format!("{}.{}", import.alias, name)),
+                    member.clone(),
+                )),
+            }));
+        program.expr = module::resolve(program.expr, &import, &members);
+        program.tests = program
+            .tests
+            .into_iter()
+            .map(|test| ast::Test {
+                name: test.name,
+                body: module::resolve(test.body, &import, &members),
+                pos: test.pos,
+            })
+            .collect();
+        let unknown = std::iter::once(&program.expr)
+            .chain(program.tests.iter().map(|test| &test.body))
+            .filter_map(|e| module::unknown_member(e, &import, &members))
+            .next();
+        if let Some((member, pos)) = unknown {
+            return Err(unknown_member_error(
+                input_path, input_src, &import, &member, pos, module,
+            ));
+        }
+
+        let defined_names = members.values().cloned().collect();
+        // an import used only by tests is not unused
+        let used_in_tests = program
+            .tests
+            .iter()
+            .any(|test| lint::check_import(&import, &defined_names, &test.body).is_none());
+        if let Some(warning) = lint::check_import(&import, &defined_names, &program.expr) {
+            if !used_in_tests && !stdlib::is_implicit(&import) {
+                warnings.push(warning);
             }
-        },
+        }
+    }
+    Ok((program, exports, warnings))
+}
+
+// loads the modules which the program imports directly or indirectly, each of them once,
+// returns the program referring to them with their definitions in dependency order, the modules
+// and warnings for unused imports
+fn splice_imports(
+    input_path: &PathBuf,
+    input_src: &str,
+    program: ast::Program,
+    entry_modules: &Vec<PathBuf>,
+    lints: &mut Vec<Diagnostic>,
+) -> Result<(ast::Program, Module, Imports, Vec<lint::Warning>), Diagnostic> {
+    let graph = graph::build(input_path, input_src, &program, entry_modules)?;
+    let modules = load_modules(&graph, lints)?;
+    let (program, exports, warnings) = {
+        let imports: Vec<&Module> = graph.imports.iter().map(|&idx| &modules[idx]).collect();
+        resolve_imports(input_path, input_src, program, &imports)?
+    };
+    // the definitions which the program never uses are dropped, e.g) most of the prelude
+    let used: HashSet<Ident> = std::iter::once(&program.expr)
+        .chain(program.tests.iter().map(|test| &test.body))
+        .flat_map(|e| e.free_term_vars().into_keys())
+        .collect();
+    let mut imported = Module::concat(modules.iter().cloned()).prune(used);
+    imported.exports = exports;
+    let imports = Imports {
+        graph: graph,
+        modules: modules,
+    };
+    Ok((program, imported, imports, warnings))
+}
+
+// reads the program with all of its dependencies and reports diagnostics,
+// returns `None` when the program has errors
+pub fn check_file(
+    src: &PathBuf,
+    message_format: MessageFormat,
+) -> Option<(ast::Expr, ast::Type)> {
+    check_modules(src, message_format).map(|checked| (checked.expr, checked.typ))
+}
+
+// same as `check_file`, but also returns the definitions spliced from the imported modules and
+// the modules themselves
+fn check_modules(src: &PathBuf, message_format: MessageFormat) -> Option<Checked> {
+    let mut lints = vec![];
+    let result = read_text(src).and_then(|input_src| {
+        read_source_with_imports(src, &input_src, &entry_modules(src), &mut lints)
+    });
+    for diag in lints.iter() {
+        diag.emit(message_format);
+    }
+    match result {
+        Ok(_) if diagnostic::has_error(&lints) => None,
+        Ok(checked) => Some(checked),
+        Err(diag) => {
+            diag.emit(message_format);
+            None
+        }
     }
 }
+
+// module files on the search path whose name is similar to the unknown import
+fn similar_module_name(entry_modules: &Vec<PathBuf>, import: &ast::Import) -> Option<String> {
+    let candidates = entry_modules
+        .iter()
+        .filter_map(|entry_module| {
+            let dir = import.dirs.iter().fold(entry_module.clone(), |mut acc, dir| {
+                acc.push(format!("{}", dir));
+                acc
+            });
+            std::fs::read_dir(dir).ok()
+        })
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().map_or(false, |ext| ext == "mm"))
+        .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(String::from))
+        .chain(
+            stdlib::names()
+                .filter(|_| import_name(import).starts_with("std."))
+                .map(String::from),
+        );
+    util::similar_name(&format!("{}", import.module_name), candidates).map(|name| {
+        import
+            .dirs
+            .iter()
+            .map(|dir| format!("{}.", dir))
+            .collect::<String>()
+            + &name
+    })
+}
+
+pub fn import_name(import: &ast::Import) -> String {
+    import
+        .dirs
+        .iter()
+        .chain(std::iter::once(&import.module_name))
+        .map(|name| format!("{}", name))
+        .collect::<Vec<_>>()
+        .join(".")
+}
@@ -0,0 +1,161 @@
+use super::module::Module;
+use super::unwritable;
+use codegen;
+use config;
+use diagnostic::Diagnostic;
+use package::{self, sha256::sha256};
+use serde_derive::{Deserialize, Serialize};
+use serde_json;
+use std::path::{Path, PathBuf};
+
+// changed whenever the meaning of the cached modules changes, which invalidates the old entries
+const ENTRY_VERSION: u32 = 6;
+
+// the typechecked definitions of a module
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    // hash of the source text
+    source: String,
+    // imported files with their keys when the entry was written
+    deps: Vec<(PathBuf, String)>,
+    // the key of the module when the entry was written, see `make_key`
+    key: String,
+    module: Module,
+}
+
+fn cache_dir() -> PathBuf {
+    package::build_dir().join("cache")
+}
+
+// the same across runs and builds of the compiler, unlike `std::hash::Hash`
+fn hash(data: &[u8]) -> String {
+    sha256(data)
+}
+
+fn entry_path(file: &Path) -> PathBuf {
+    let file = file.canonicalize().unwrap_or_else(|_| file.to_path_buf());
+    cache_dir().join(format!("{}.json", hash(file.to_string_lossy().as_bytes())))
+}
+
+fn load(file: &Path) -> Option<Entry> {
+    let json = std::fs::read_to_string(entry_path(file)).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+// identifies the source of a module with the modules it depends on,
+// and the compiler and lint settings that the result depends on
+fn make_key(source: &str, deps: &Vec<(PathBuf, String)>) -> String {
+    let config = config::CONFIG.lock().unwrap();
+    let lint = format!("{:?}", config.lint);
+    let prelude = config.build.prelude;
+    let key = (
+        env!("CARGO_PKG_VERSION"),
+        ENTRY_VERSION,
+        source,
+        deps,
+        lint,
+        prelude,
+    );
+    hash(serde_json::to_string(&key).unwrap().as_bytes())
+}
+
+// the key of the module whose source is `src`, if its entry is still valid
+fn valid_key(src: &str, entry: &Entry) -> Option<String> {
+    if entry.source != hash(src.as_bytes()) {
+        return None;
+    }
+    for (dep, dep_key) in entry.deps.iter() {
+        if current_key(dep)? != *dep_key {
+            return None;
+        }
+    }
+    // e.g) written by another version of the compiler or with other lint settings
+    let key = make_key(&entry.source, &entry.deps);
+    if key != entry.key {
+        return None;
+    }
+    Some(key)
+}
+
+fn current_key(file: &Path) -> Option<String> {
+    let src = super::read_text(&file.to_path_buf()).ok()?;
+    valid_key(&src, &load(file)?)
+}
+
+// the typechecked definitions of the module in `file` whose source is `src`, if they are cached
+pub fn lookup(file: &Path, src: &str) -> Option<Module> {
+    let entry = load(file)?;
+    valid_key(src, &entry)?;
+    Some(entry.module)
+}
+
+// caches the typechecked definitions of a module, which imports `deps`.
+// fails silently, the module is just checked again next time.
+pub fn store(file: &Path, src: &str, deps: &Vec<PathBuf>, module: &Module) {
+    let deps: Option<Vec<(PathBuf, String)>> = deps
+        .iter()
+        .map(|dep| current_key(dep).map(|key| (dep.clone(), key)))
+        .collect();
+    let deps = match deps {
+        Some(deps) => deps,
+        None => return,
+    };
+    let source = hash(src.as_bytes());
+    let key = make_key(&source, &deps);
+    let entry = Entry {
+        source: source,
+        deps: deps,
+        key: key,
+        module: module.clone(),
+    };
+    let json = match serde_json::to_string(&entry) {
+        Ok(json) => json,
+        Err(_) => return,
+    };
+    if std::fs::create_dir_all(cache_dir()).is_ok() {
+        let _ = std::fs::write(entry_path(file), json);
+    }
+}
+
+// the object file compiled from the LLVM IR `ll`, which is reused while the IR is unchanged
+pub fn object(ll: &[u8]) -> Result<PathBuf, Diagnostic> {
+    compile(&hash(ll), || ll.to_vec())
+}
+
+// the object file of `unit`, which is compiled again only when its definitions or the interfaces
+// of its imports change
+pub fn unit_object(unit: &codegen::Unit) -> Result<PathBuf, Diagnostic> {
+    let key = (
+        env!("CARGO_PKG_VERSION"),
+        ENTRY_VERSION,
+        &unit.name,
+        &unit.expr,
+        &unit.imports,
+    );
+    // a `Value` orders the fields of records by their labels, unlike the maps of them
+    let key = serde_json::to_value(&key).unwrap().to_string();
+    compile(&hash(key.as_bytes()), || {
+        let mut ll = vec![];
+        codegen::write_unit_llvm_ir(unit, &mut ll);
+        ll
+    })
+}
+
+// the object file of `key` compiled from the LLVM IR which `ll` writes, only if it is not cached
+fn compile<F: FnOnce() -> Vec<u8>>(key: &str, ll: F) -> Result<PathBuf, Diagnostic> {
+    let dir = cache_dir();
+    let obj_path = dir.join(format!("{}.o", key));
+    if obj_path.is_file() {
+        return Ok(obj_path);
+    }
+    let ll_path = dir.join(format!("{}.ll", key));
+    let tmp_path = dir.join(format!("{}.o.tmp", key));
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&ll_path, ll()))
+        .map_err(|err| unwritable(&ll_path, err))?;
+    codegen::llc(&ll_path, "obj", &tmp_path);
+    // renamed after `llc` succeeded, so that a broken object is never reused
+    std::fs::rename(&tmp_path, &obj_path).map_err(|err| unwritable(&obj_path, err))?;
+    let _ = std::fs::remove_file(&ll_path);
+    Ok(obj_path)
+}
@@ -0,0 +1,175 @@
+use super::{find_import, import_name, parse, read_text, similar_module_name};
+use ast::{Import, Program};
+use diagnostic::{code, Diagnostic, Span};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+// a module which the program imports directly or indirectly
+pub struct Node {
+    pub file: PathBuf,
+    // the name the module is imported by, e.g) `lib.math`
+    pub name: String,
+    pub src: String,
+    pub program: Program,
+    // the node of each import of the module
+    pub imports: Vec<usize>,
+}
+
+// the modules of a program, each of them after the modules it imports
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    // the node of each import of the program
+    pub imports: Vec<usize>,
+}
+
+// an import on the way from the program to the module being visited
+struct Step {
+    importer: PathBuf,
+    importer_src: String,
+    import: Import,
+    // the canonical path of the imported file
+    target: PathBuf,
+}
+
+struct Builder<'a> {
+    entry_modules: &'a Vec<PathBuf>,
+    // the canonical path of the program
+    root: PathBuf,
+    nodes: Vec<Node>,
+    visited: HashMap<PathBuf, usize>,
+    path: Vec<Step>,
+}
+
+fn canonical(file: &PathBuf) -> PathBuf {
+    file.canonicalize().unwrap_or_else(|_| file.clone())
+}
+
+fn span(step: &Step) -> Span {
+    Span::new(&step.importer, &step.importer_src, step.import.pos)
+}
+
+// `steps` lead from the module imported by `last` back to the importer of `last`
+fn cycle_error(steps: &[Step], last: &Step) -> Diagnostic {
+    let name = import_name(&last.import);
+    let names: Vec<String> = std::iter::once(name.clone())
+        .chain(steps.iter().map(|step| import_name(&step.import)))
+        .chain(std::iter::once(name))
+        .collect();
+    steps.iter().fold(
+        Diagnostic::error(
+            code::IMPORT_CYCLE,
+            format!("import cycle: {}", names.join(" -> ")),
+            Some(span(last)),
+        ),
+        |diag, step| {
+            diag.with_related(
+                format!("`{}` is imported here", import_name(&step.import)),
+                span(step),
+            )
+        },
+    )
+}
+
+impl<'a> Builder<'a> {
+    // the nodes of the imports of `program` in `file`, visiting the modules for the first time
+    fn imports(
+        &mut self,
+        file: &PathBuf,
+        src: &str,
+        program: &Program,
+    ) -> Result<Vec<usize>, Diagnostic> {
+        let mut imports = vec![];
+        for import in program.imports.iter() {
+            let imported = find_import(self.entry_modules, import).ok_or_else(|| {
+                Diagnostic::error(
+                    code::UNKNOWN_IMPORT,
+                    format!("unknown import: {}", import_name(import)),
+                    Some(Span::new(file, src, import.pos)),
+                )
+                .with_suggestion(similar_module_name(self.entry_modules, import))
+            })?;
+            let target = canonical(&imported);
+            if let Some(idx) = self.visited.get(&target) {
+                imports.push(*idx);
+                continue;
+            }
+            let step = Step {
+                importer: file.clone(),
+                importer_src: src.to_string(),
+                import: import.clone(),
+                target: target.clone(),
+            };
+            if target == self.root {
+                return Err(cycle_error(&self.path, &step));
+            }
+            if let Some(start) = self.path.iter().position(|step| step.target == target) {
+                return Err(cycle_error(&self.path[start + 1..], &step));
+            }
+            self.path.push(step);
+            let idx = self.visit(imported, import_name(import))?;
+            self.path.pop();
+            self.visited.insert(target, idx);
+            imports.push(idx);
+        }
+        Ok(imports)
+    }
+
+    fn visit(&mut self, file: PathBuf, name: String) -> Result<usize, Diagnostic> {
+        let src = read_text(&file)?;
+        let program = parse(&file, &src)?;
+        let imports = self.imports(&file, &src, &program)?;
+        self.nodes.push(Node {
+            file: file,
+            name: name,
+            src: src,
+            program: program,
+            imports: imports,
+        });
+        Ok(self.nodes.len() - 1)
+    }
+}
+
+// follows the imports of `program` in `input_path`, reading each module once
+pub fn build(
+    input_path: &PathBuf,
+    input_src: &str,
+    program: &Program,
+    entry_modules: &Vec<PathBuf>,
+) -> Result<Graph, Diagnostic> {
+    let mut builder = Builder {
+        entry_modules: entry_modules,
+        root: canonical(input_path),
+        nodes: vec![],
+        visited: HashMap::new(),
+        path: vec![],
+    };
+    let imports = builder.imports(input_path, input_src, program)?;
+    Ok(Graph {
+        nodes: builder.nodes,
+        imports: imports,
+    })
+}
+
+// `input_path` and the files of the modules it imports transitively, as far as they are found
+// when some of them have errors, e.g) to watch them for changes
+pub fn files(input_path: &PathBuf, entry_modules: &Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut files = vec![input_path.clone()];
+    let (src, program) = match read_text(input_path)
+        .and_then(|src| parse(input_path, &src).map(|program| (src, program)))
+    {
+        Ok(parsed) => parsed,
+        Err(_) => return files,
+    };
+    let mut builder = Builder {
+        entry_modules: entry_modules,
+        root: canonical(input_path),
+        nodes: vec![],
+        visited: HashMap::new(),
+        path: vec![],
+    };
+    // on an error, the imports being visited lead to the module which has it
+    let _ = builder.imports(input_path, &src, &program);
+    files.extend(builder.nodes.into_iter().map(|node| node.file));
+    files.extend(builder.path.into_iter().map(|step| step.target));
+    files
+}
@@ -0,0 +1,316 @@
+use ast::{self, Expr, Import, Position, Type};
+use ident::Ident;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+// the definitions of an imported module, renamed to `<module>.<name>` so that they never clash
+// with the names of the importer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Module {
+    // the definitions of the module itself, ending in `EmptyMark`
+    pub expr: Expr,
+    // the `pub` top-level names of the module itself with their renamed names
+    pub exports: Vec<(Ident, Ident)>,
+    // the other top-level names of the module itself, which importers can not refer to
+    pub private: Vec<Ident>,
+    // the file of each definition in `expr`, outermost first
+    pub files: Vec<PathBuf>,
+}
+
+impl Module {
+    pub fn empty() -> Module {
+        Module {
+            expr: Expr::EmptyMark,
+            exports: vec![],
+            private: vec![],
+            files: vec![],
+        }
+    }
+
+    // puts the definitions of `other` after those of `self`, without exporting them
+    pub fn append(&mut self, other: Module) {
+        self.expr = super::replace_tail(&self.expr, other.expr);
+        self.files.extend(other.files);
+    }
+
+    // the definitions of `modules` in order, exporting nothing
+    pub fn concat<I: IntoIterator<Item = Module>>(modules: I) -> Module {
+        modules.into_iter().fold(Module::empty(), |mut acc, module| {
+            acc.append(module);
+            acc
+        })
+    }
+
+    // the renamed name of each exported name, later definitions win
+    pub fn members(&self) -> HashMap<Ident, Ident> {
+        self.exports.iter().cloned().collect()
+    }
+
+    // drops the functions which none of `used` refers to directly or indirectly, e.g) those of
+    // the prelude which the program never calls
+    pub fn prune(self, used: HashSet<Ident>) -> Module {
+        let mut used = used;
+        let mut kept = vec![];
+        let expr = prune_definitions(self.expr, &mut used, &mut kept);
+        // `kept` is in the order of the innermost definition first
+        kept.reverse();
+        let files = self
+            .files
+            .into_iter()
+            .zip(kept)
+            .filter_map(|(file, kept)| if kept { Some(file) } else { None })
+            .collect();
+        Module {
+            expr: expr,
+            exports: self.exports,
+            private: self.private,
+            files: files,
+        }
+    }
+}
+
+fn prune_definitions(e: Expr, used: &mut HashSet<Ident>, kept: &mut Vec<bool>) -> Expr {
+    match e {
+        Expr::Let(name, typ, box init, box left, pos, doc)
+            if name != Ident::new("<dummy-sequence>") =>
+        {
+            let left = prune_definitions(left, used, kept);
+            // initializers are evaluated whether they are used or not
+            used.extend(init.free_term_vars().into_keys());
+            kept.push(true);
+            Expr::Let(name, typ, box init, box left, pos, doc)
+        }
+        Expr::Func {
+            name,
+            param_name,
+            param_type,
+            ret_type,
+            box body,
+            box left,
+            pos,
+            doc,
+        } => {
+            let left = prune_definitions(left, used, kept);
+            if !used.contains(&name) {
+                kept.push(false);
+                return left;
+            }
+            used.extend(
+                body.free_term_vars()
+                    .into_keys()
+                    .filter(|var| !ast::binds_param(&param_name, &param_type, var)),
+            );
+            kept.push(true);
+            Expr::Func {
+                name: name,
+                param_name: param_name,
+                param_type: param_type,
+                ret_type: ret_type,
+                body: box body,
+                left: box left,
+                pos: pos,
+                doc: doc,
+            }
+        }
+        Expr::LetType(name, typ, box left, doc) => {
+            let left = prune_definitions(left, used, kept);
+            kept.push(true);
+            Expr::LetType(name, typ, box left, doc)
+        }
+        e => e,
+    }
+}
+
+// renames the type variables left unknown in the definitions of the module `prefix` to
+// `<prefix#n>` and its anonymous functions to `<prefix#func-n>`, which are the same every time
+// the module is checked, so that they never clash with the fresh names of the importers after
+// being cached
+pub fn seal_fresh_names(e: Expr, prefix: &str) -> Expr {
+    let e = e
+        .type_vars()
+        .into_iter()
+        .filter(|name| name.is_fresh())
+        .enumerate()
+        .fold(e, |e, (idx, name)| {
+            e.subst_type(&name, &Type::Var(Ident::new(&format!("<{}#{}>", prefix, idx))))
+        });
+    let mut funcs = vec![];
+    anonymous_funcs(&e, &mut funcs);
+    funcs.into_iter().enumerate().fold(e, |e, (idx, name)| {
+        e.rename_func(&name, &Ident::new(&format!("<{}#func-{}>", prefix, idx)))
+    })
+}
+
+fn anonymous_funcs(e: &Expr, funcs: &mut Vec<Ident>) {
+    if let Expr::Func { ref name, .. } = e {
+        if name.is_fresh() {
+            funcs.push(name.clone());
+        }
+    }
+    for e in e.children() {
+        anonymous_funcs(e, funcs);
+    }
+}
+
+// renames the top-level definitions of `e` to `<prefix>.<name>`, dropping the final expression
+pub fn mangle(e: Expr, prefix: &str) -> (Expr, Vec<(Ident, Ident)>) {
+    let mangled_name = |name: &Ident| Ident::new(&format!("{}.{}", prefix, name));
+    match e {
+        Expr::Let(name, typ, box init, box left, pos, doc)
+            if name != Ident::new("<dummy-sequence>") =>
+        {
+            let mangled = mangled_name(&name);
+            let (left, mut exports) = mangle(left.rename_var(&name, &mangled), prefix);
+            exports.insert(0, (name, mangled.clone()));
+            (Expr::Let(mangled, typ, box init, box left, pos, doc), exports)
+        }
+        Expr::Func {
+            name,
+            param_name,
+            param_type,
+            ret_type,
+            box body,
+            box left,
+            pos,
+            doc,
+        } => {
+            let mangled = mangled_name(&name);
+            let body = if ast::binds_param(&param_name, &param_type, &name) {
+                body
+            } else {
+                body.rename_var(&name, &mangled)
+            };
+            let (left, mut exports) = mangle(left.rename_var(&name, &mangled), prefix);
+            exports.insert(0, (name, mangled.clone()));
+            let e = Expr::Func {
+                name: mangled,
+                param_name: param_name,
+                param_type: param_type,
+                ret_type: ret_type,
+                body: box body,
+                left: box left,
+                pos: pos,
+                doc: doc,
+            };
+            (e, exports)
+        }
+        Expr::LetType(name, typ, box left, doc) => {
+            let mangled = mangled_name(&name);
+            let left = left.subst_type(&name, &Type::Var(mangled.clone()));
+            let (left, mut exports) = mangle(left, prefix);
+            exports.insert(0, (name, mangled.clone()));
+            (Expr::LetType(mangled, typ, box left, doc), exports)
+        }
+        _ => (Expr::EmptyMark, vec![]),
+    }
+}
+
+// `e` without its outermost `n` definitions
+pub fn drop_definitions(e: Expr, n: usize) -> Expr {
+    if n == 0 {
+        return e;
+    }
+    match e {
+        Expr::Let(_, _, _, box left, _, _)
+        | Expr::Func { left: box left, .. }
+        | Expr::LetType(_, _, box left, _) => drop_definitions(left, n - 1),
+        e => e,
+    }
+}
+
+// `e` without the definitions from `files`, where `def_files` are the files of its outermost
+// definitions, e.g) those of the modules compiled into their own object files
+pub fn drop_modules(e: Expr, def_files: &[PathBuf], files: &HashSet<PathBuf>) -> Expr {
+    let (file, def_files) = match def_files.split_first() {
+        Some(first) => first,
+        None => return e,
+    };
+    let dropped = files.contains(file);
+    match e {
+        Expr::Let(name, typ, init, box left, pos, doc) => {
+            let left = drop_modules(left, def_files, files);
+            if dropped {
+                left
+            } else {
+                Expr::Let(name, typ, init, box left, pos, doc)
+            }
+        }
+        Expr::Func {
+            name,
+            param_name,
+            param_type,
+            ret_type,
+            body,
+            box left,
+            pos,
+            doc,
+        } => {
+            let left = drop_modules(left, def_files, files);
+            if dropped {
+                return left;
+            }
+            Expr::Func {
+                name: name,
+                param_name: param_name,
+                param_type: param_type,
+                ret_type: ret_type,
+                body: body,
+                left: box left,
+                pos: pos,
+                doc: doc,
+            }
+        }
+        Expr::LetType(name, typ, box left, doc) => {
+            let left = drop_modules(left, def_files, files);
+            if dropped {
+                left
+            } else {
+                Expr::LetType(name, typ, box left, doc)
+            }
+        }
+        e => e,
+    }
+}
+
+// refers the names in `e` which `import` brings into scope to the renamed definitions
+pub fn resolve(e: Expr, import: &Import, members: &HashMap<Ident, Ident>) -> Expr {
+    match import.items {
+        Some(ref items) => items.iter().fold(e, |e, item| match members.get(item) {
+            Some(member) => e
+                .rename_var(item, member)
+                .subst_type(item, &Type::Var(member.clone())),
+            None => e,
+        }),
+        None => members
+            .iter()
+            .fold(e.qualify(&import.alias, members), |e, (name, member)| {
+                let qualified = Ident::new(&format!("{}.{}", import.alias, name));
+                e.subst_type(&qualified, &Type::Var(member.clone()))
+            }),
+    }
+}
+
+// the first `alias.name` left unresolved in `e`, where `alias` qualifies the module of `import`
+pub fn unknown_member(
+    e: &Expr,
+    import: &Import,
+    members: &HashMap<Ident, Ident>,
+) -> Option<(Ident, Position)> {
+    match e {
+        Expr::Var(ref name, _, ref pos) if !members.values().any(|member| member == name) => {
+            let qualifier = format!("{}.", import.alias);
+            let name = format!("{}", name);
+            if name.starts_with(&qualifier) {
+                Some((Ident::new(&name[qualifier.len()..]), *pos))
+            } else {
+                None
+            }
+        }
+        _ => e
+            .children()
+            .into_iter()
+            .filter_map(|e| unknown_member(e, import, members))
+            .next(),
+    }
+}
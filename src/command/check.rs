@@ -0,0 +1,101 @@
+use command::{self, build, watch, workspace, Command};
+use config;
+use diagnostic::MessageFormat;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use util;
+
+pub struct CheckCommand {
+    pub src: Option<PathBuf>,
+    pub message_format: MessageFormat,
+    pub watch: bool,
+    // the member of the workspace to run in
+    pub package: Option<String>,
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-check : parse and typecheck a local mumrik program without generating code
+USAGE: {} check [options...] <filename>
+
+options:
+    --message-format <format>  `human` or `json`, default: `human`
+    --watch                    check again whenever the program or the config file changes
+    --package, -p <member>     check only <member> of the workspace, which checks all of
+                               its members in its root
+    --help, -h                 print help information
+
+filename: input mumrik program filename"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+impl CheckCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        let mut src = None;
+        let mut message_format = MessageFormat::Human;
+        let mut watch = false;
+        let mut package = None;
+
+        while let Some(arg) = args.pop_front() {
+            if arg.as_str() == "--help" || arg.as_str() == "-h" {
+                print_help(&program_name);
+            } else if arg.as_str() == "--watch" {
+                watch = true;
+            } else if arg.as_str() == "--package" || arg.as_str() == "-p" {
+                package = command::parse_package(args.pop_front());
+            } else if arg.as_str().starts_with("--package=") {
+                package = command::parse_package(Some(arg[10..].to_string()));
+            } else if arg.as_str() == "--message-format" {
+                message_format = command::parse_message_format(args.pop_front());
+            } else if arg.as_str().starts_with("--message-format=") {
+                message_format = command::parse_message_format(Some(arg[17..].to_string()));
+            } else if src.is_some() {
+                panic!(
+                    "{}: too many command line argument `{}`",
+                    util::alert("error"),
+                    arg
+                );
+            } else {
+                src = Some(PathBuf::from(arg));
+            }
+        }
+
+        box CheckCommand {
+            src: src,
+            message_format: message_format,
+            watch: watch,
+            package: package,
+        }
+    }
+}
+
+impl Command for CheckCommand {
+    fn work(self: Box<CheckCommand>) {
+        let succeeded = workspace::run_members(&self.package, &self.src, self.watch, || {
+            self.check()
+        })
+        .unwrap_or_else(|| self.check());
+        if succeeded {
+            std::process::exit(0)
+        } else {
+            std::process::exit(1)
+        }
+    }
+}
+
+impl CheckCommand {
+    // checks the program of the project in the current directory, returns whether it has no error
+    fn check(&self) -> bool {
+        let src = if let Some(ref src) = self.src {
+            src.clone()
+        } else {
+            config::CONFIG.lock().unwrap().build.src.clone()
+        };
+        if self.watch {
+            watch::watch(|| watch::import_closure(&src));
+        }
+        build::check_file(&src, self.message_format).is_some()
+    }
+}
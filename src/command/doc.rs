@@ -0,0 +1,211 @@
+use command::{self, build, Command};
+use config;
+use diagnostic::{Diagnostic, MessageFormat, Severity};
+use doc::{self, Format, Module};
+use package;
+use parser;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use util;
+
+pub struct DocCommand {
+    pub src: Option<PathBuf>,
+    pub format: Format,
+    pub message_format: MessageFormat,
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-doc : generate documentation of a local mumrik program and the modules it imports
+USAGE: {} doc [options...] <filename>
+
+renders the top-level definitions of each module with their signatures and `///` comments
+into `doc` of the build directory, which is that of the workspace in its members.
+
+options:
+    --format <format>          `html` or `markdown`, default: `html`
+    --message-format <format>  `human` or `json`, default: `human`
+    --help, -h                 print help information
+
+filename: input mumrik program filename"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+fn parse_format(value: Option<String>) -> Format {
+    let value = value.unwrap_or_else(|| {
+        panic!(
+            "{}: format is required after `--format`",
+            util::alert("error")
+        )
+    });
+    Format::parse(value.as_str()).unwrap_or_else(|| {
+        panic!(
+            "{}: unknown format `{}`, expected `html` or `markdown`",
+            util::alert("error"),
+            value
+        )
+    })
+}
+
+impl DocCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        let mut src = None;
+        let mut format = Format::Html;
+        let mut message_format = MessageFormat::Human;
+
+        while let Some(arg) = args.pop_front() {
+            if arg.as_str() == "--help" || arg.as_str() == "-h" {
+                print_help(&program_name);
+            } else if arg.as_str() == "--format" {
+                format = parse_format(args.pop_front());
+            } else if arg.as_str().starts_with("--format=") {
+                format = parse_format(Some(arg[9..].to_string()));
+            } else if arg.as_str() == "--message-format" {
+                message_format = command::parse_message_format(args.pop_front());
+            } else if arg.as_str().starts_with("--message-format=") {
+                message_format = command::parse_message_format(Some(arg[17..].to_string()));
+            } else if src.is_some() {
+                panic!(
+                    "{}: too many command line argument `{}`",
+                    util::alert("error"),
+                    arg
+                );
+            } else {
+                src = Some(PathBuf::from(arg));
+            }
+        }
+
+        box DocCommand {
+            src: src,
+            format: format,
+            message_format: message_format,
+        }
+    }
+}
+
+// typechecks the module in `path` and collects its documented definitions,
+// only the exported ones unless it is the entry of the program. the errors are returned
+// with those of the lints, while their warnings are left to `mumrik check`.
+fn document(
+    name: String,
+    path: &PathBuf,
+    is_entry: bool,
+    entry_modules: &Vec<PathBuf>,
+) -> Result<(Module, Vec<PathBuf>), Vec<Diagnostic>> {
+    let src = build::read_text(path).map_err(|diag| vec![diag])?;
+    let mut lints = vec![];
+    let result = build::read_source(path, &src, entry_modules, &mut lints);
+    let mut errors: Vec<Diagnostic> = lints
+        .into_iter()
+        .filter(|diag| diag.severity == Severity::Error)
+        .collect();
+    let (e, _, spliced) = match result {
+        Ok(result) if errors.is_empty() => result,
+        Ok(_) => return Err(errors),
+        Err(diag) => {
+            errors.push(diag);
+            return Err(errors);
+        }
+    };
+    // `read_source` succeeded, so the source is parsable
+    let program = parser::program(&src).unwrap();
+    let imported = program
+        .imports
+        .iter()
+        .filter_map(|import| build::find_import(entry_modules, import))
+        .collect();
+    let mut items = doc::items(&e, spliced.files.len());
+    // importers see only the `pub` definitions of a module
+    if !is_entry {
+        items.retain(|item| program.public.contains(&item.name));
+    }
+    let module = Module {
+        name: name,
+        imports: program.imports.iter().map(build::import_name).collect(),
+        items: items,
+    };
+    Ok((module, imported))
+}
+
+impl Command for DocCommand {
+    fn work(self: Box<DocCommand>) {
+        package::ensure_fetched();
+        let src = if let Some(src) = self.src {
+            src
+        } else {
+            config::CONFIG.lock().unwrap().build.src.clone()
+        };
+        let entry_modules = build::entry_modules(&src);
+        let name = src
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("main")
+            .to_string();
+
+        let mut modules: Vec<Module> = vec![];
+        // the canonical paths of `modules`, since a module may be imported in many of them
+        let mut paths: Vec<PathBuf> = vec![];
+        let mut queue = VecDeque::new();
+        queue.push_back((name, src.clone()));
+        while let Some((name, path)) = queue.pop_front() {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if paths.contains(&canonical) {
+                continue;
+            }
+            let is_entry = modules.is_empty();
+            match document(name, &path, is_entry, &entry_modules) {
+                Ok((module, imported)) => {
+                    for (import, path) in module.imports.iter().zip(imported) {
+                        queue.push_back((import.clone(), path));
+                    }
+                    modules.push(module);
+                    paths.push(canonical);
+                }
+                Err(diags) => {
+                    for diag in diags.iter() {
+                        diag.emit(self.message_format);
+                    }
+                    std::process::exit(-1);
+                }
+            }
+        }
+
+        let out_dir = package::build_dir().join("doc");
+        let write = |file: String, content: String| {
+            let path = out_dir.join(file);
+            std::fs::write(&path, content).unwrap_or_else(|err| {
+                panic!(
+                    "{}: can not write `{}`: {}",
+                    util::alert("error"),
+                    path.display(),
+                    err
+                )
+            })
+        };
+        std::fs::create_dir_all(&out_dir).unwrap_or_else(|err| {
+            panic!(
+                "{}: can not create `{}`: {}",
+                util::alert("error"),
+                out_dir.display(),
+                err
+            )
+        });
+        for module in modules.iter() {
+            write(
+                format!("{}.{}", module.name, self.format.extension()),
+                doc::render_module(module, self.format),
+            );
+        }
+        write(
+            format!("index.{}", self.format.extension()),
+            doc::render_index(&modules, self.format),
+        );
+        println!(
+            "documented {} modules in {}",
+            modules.len(),
+            out_dir.display()
+        );
+    }
+}
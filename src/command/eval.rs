@@ -0,0 +1,91 @@
+use command::{self, build, Command};
+use config;
+use diagnostic::MessageFormat;
+use eval;
+use package;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use util;
+
+pub struct EvalCommand {
+    pub src: Option<PathBuf>,
+    pub message_format: MessageFormat,
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-eval : run a local mumrik program with the interpreter, without llc and gcc
+USAGE: {} eval [options...] <filename>
+
+the exit status is the value of the program, as that of the compiled executable.
+
+options:
+    --message-format <format>  `human` or `json`, default: `human`
+    --help, -h                 print help information
+
+filename: input mumrik program filename"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+impl EvalCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        let mut src = None;
+        let mut message_format = MessageFormat::Human;
+
+        while let Some(arg) = args.pop_front() {
+            if arg.as_str() == "--help" || arg.as_str() == "-h" {
+                print_help(&program_name);
+            } else if arg.as_str() == "--message-format" {
+                message_format = command::parse_message_format(args.pop_front());
+            } else if arg.as_str().starts_with("--message-format=") {
+                message_format = command::parse_message_format(Some(arg[17..].to_string()));
+            } else if src.is_some() {
+                panic!(
+                    "{}: too many command line argument `{}`",
+                    util::alert("error"),
+                    arg
+                );
+            } else {
+                src = Some(PathBuf::from(arg));
+            }
+        }
+
+        box EvalCommand {
+            src: src,
+            message_format: message_format,
+        }
+    }
+}
+
+// interprets the program in `src`, returns its exit status and output
+pub fn interpret(src: &PathBuf, message_format: MessageFormat) -> (i32, Vec<u8>) {
+    let expr = match build::check_file(src, message_format) {
+        Some((expr, _)) => expr,
+        None => std::process::exit(-1),
+    };
+    match eval::run(expr, vec![]) {
+        (Ok(status), out) => (status, out),
+        (Err(err), out) => {
+            print!("{}", String::from_utf8_lossy(&out));
+            err.to_diagnostic(src, &std::fs::read_to_string(src).unwrap_or_default())
+                .emit(message_format);
+            std::process::exit(-1)
+        }
+    }
+}
+
+impl Command for EvalCommand {
+    fn work(self: Box<EvalCommand>) {
+        package::ensure_fetched();
+        let src = if let Some(src) = self.src {
+            src
+        } else {
+            config::CONFIG.lock().unwrap().build.src.clone()
+        };
+        let (status, out) = interpret(&src, self.message_format);
+        print!("{}", String::from_utf8_lossy(&out));
+        std::process::exit(status)
+    }
+}
@@ -0,0 +1,58 @@
+use command::Command;
+use diagnostic::code;
+use std::collections::VecDeque;
+use util;
+
+pub struct ExplainCommand {
+    pub code: Option<String>,
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-explain : print a detailed explanation of an error code
+USAGE: {} explain [<code>]
+
+code: error code such as `E0003`, all codes are listed when omitted"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+impl ExplainCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        let mut code = None;
+        while let Some(arg) = args.pop_front() {
+            if arg.as_str() == "--help" || arg.as_str() == "-h" {
+                print_help(&program_name);
+            } else if code.is_some() {
+                panic!(
+                    "{}: too many command line argument `{}`",
+                    util::alert("error"),
+                    arg
+                );
+            } else {
+                code = Some(arg);
+            }
+        }
+        box ExplainCommand { code: code }
+    }
+}
+
+impl Command for ExplainCommand {
+    fn work(self: Box<ExplainCommand>) {
+        match self.code {
+            Some(id) => match code::lookup(id.to_uppercase().as_str()) {
+                Some(code) => println!("{}: {}\n\n{}", code.id, code.title, code.explanation),
+                None => {
+                    eprintln!("{}: unknown error code `{}`", util::alert("error"), id);
+                    std::process::exit(-1)
+                }
+            },
+            None => {
+                for code in code::CODES.iter() {
+                    println!("{}  {}", code.id, code.title);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use command::Command;
+use config;
+use diagnostic::{code, Diagnostic, MessageFormat};
+use formatter;
+use package;
+use parser;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use util;
+
+pub struct FmtCommand {
+    pub files: Vec<PathBuf>,
+    pub check: bool,
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-fmt : format mumrik source files
+USAGE: {} fmt [options...] [<filename>...]
+
+options:
+    --check     do not write files, but exit with 1 if any of them is not formatted
+    --help, -h  print help information
+
+filename: mumrik source files to format, default: all `.mm` files under the source directory"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+impl FmtCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        let mut files = vec![];
+        let mut check = false;
+        while let Some(arg) = args.pop_front() {
+            if arg.as_str() == "--help" || arg.as_str() == "-h" {
+                print_help(&program_name);
+            } else if arg.as_str() == "--check" {
+                check = true;
+            } else {
+                files.push(PathBuf::from(arg));
+            }
+        }
+        box FmtCommand {
+            files: files,
+            check: check,
+        }
+    }
+}
+
+// returns whether `path` was already formatted
+fn format_file(path: &PathBuf, check: bool) -> bool {
+    let src = std::fs::read_to_string(path).unwrap_or_else(|_| {
+        Diagnostic::error(
+            code::UNREADABLE_FILE,
+            format!("can not read file: {}", path.to_str().unwrap()),
+            None,
+        )
+        .emit(MessageFormat::Human);
+        std::process::exit(1)
+    });
+    if let Err(err) = parser::program(&src) {
+        Diagnostic::from_parse_error(path, &src, err).emit(MessageFormat::Human);
+        std::process::exit(1)
+    }
+    let formatted = formatter::format(&src).unwrap_or_else(|err| {
+        eprintln!(
+            "{}: failed to format {}: {}",
+            util::alert("internal error"),
+            path.to_str().unwrap(),
+            err
+        );
+        std::process::exit(1)
+    });
+    if formatted == src {
+        return true;
+    }
+    if check {
+        println!("not formatted: {}", path.to_str().unwrap());
+    } else {
+        std::fs::write(path, formatted).unwrap_or_else(|err| {
+            eprintln!(
+                "{}: {}",
+                util::alert(&format!("failed to write {}", path.to_str().unwrap())),
+                err
+            );
+            std::process::exit(1)
+        });
+    }
+    false
+}
+
+impl Command for FmtCommand {
+    fn work(self: Box<FmtCommand>) {
+        let files = if self.files.is_empty() {
+            let src = config::CONFIG.lock().unwrap().build.src.clone();
+            let dir = src
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map_or(PathBuf::from("."), |dir| dir.to_path_buf());
+            util::source_files(&dir, &package::generated_dirs()).unwrap_or_default()
+        } else {
+            self.files
+        };
+        let mut all_formatted = true;
+        for file in files.iter() {
+            all_formatted &= format_file(file, self.check);
+        }
+        if self.check && !all_formatted {
+            std::process::exit(1);
+        }
+    }
+}
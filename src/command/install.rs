@@ -1,5 +1,6 @@
 use command::Command;
 use config;
+use package;
 use std::collections::VecDeque;
 use util;
 
@@ -9,10 +10,12 @@ pub struct InstallCommand {
 
 fn print_help(program_name: &str) {
     println!(
-        r#"mumrik-install : download git repository from github for the local mumrik project
+        r#"mumrik-install : add a git repository on github to the dependencies of the local mumrik project
 USAGE: {} install <repository-name>
 
-repository-name: target github repository name in the form of `user-name/project-name`"#,
+repository-name: target github repository name in the form of `user-name/project-name`,
+                 which is added to `[dependencies]` of `mumrik-conf.toml` as `project-name`
+                 and fetched into the `dep` directory"#,
         program_name
     );
     std::process::exit(0);
@@ -41,23 +44,67 @@ impl InstallCommand {
     }
 }
 
+// `conf` with the dependency `name = value` added to `[dependencies]`
+fn add_dependency(conf: &str, name: &str, value: &str) -> String {
+    let entry = format!("{} = {}\n", name, value);
+    match conf.find("[dependencies]\n") {
+        Some(idx) => {
+            let idx = idx + "[dependencies]\n".len();
+            format!("{}{}{}", &conf[..idx], entry, &conf[idx..])
+        }
+        None => {
+            let separator = if conf.ends_with('\n') { "" } else { "\n" };
+            format!("{}{}\n[dependencies]\n{}", conf, separator, entry)
+        }
+    }
+}
+
 impl Command for InstallCommand {
     fn work(self: Box<InstallCommand>) {
-        let mut dep_dir = config::config_path()
+        let config_path = config::config_path()
             .unwrap_or_else(|| panic!("{}: local mumrik project not found", util::alert("error")));
-        dep_dir.pop();
-        dep_dir.push(config::CONFIG.lock().unwrap().build.dep.clone());
-        println!("{}", dep_dir.as_path().to_str().unwrap());
-        std::env::set_current_dir(dep_dir.as_path()).unwrap();
-
-        let result = std::process::Command::new("git")
-            .arg("clone")
-            .arg(format!("https://github.com/{}", self.repo_name))
-            .output()
-            .unwrap_or_else(|_| panic!("failed to execute `git clone`"));
-
-        if !result.status.success() {
-            std::process::exit(-1);
+        let name = self
+            .repo_name
+            .rsplit('/')
+            .next()
+            .unwrap()
+            .trim_end_matches(".git")
+            .to_string();
+        let conf = std::fs::read_to_string(&config_path).unwrap_or_else(|err| {
+            panic!(
+                "{}: can not read `{}`: {}",
+                util::alert("error"),
+                config_path.display(),
+                err
+            )
+        });
+        let declared = conf
+            .parse::<toml::Value>()
+            .ok()
+            .and_then(|value| value.get("dependencies").cloned())
+            .map_or(false, |deps| deps.get(&name).is_some());
+        if declared {
+            panic!(
+                "{}: `{}` is already a dependency of the project",
+                util::alert("error"),
+                name
+            );
         }
+
+        // written before the config is loaded, so that the new dependency is fetched
+        let conf = add_dependency(
+            &conf,
+            &name,
+            &format!("{{ git = \"https://github.com/{}\" }}", self.repo_name),
+        );
+        std::fs::write(&config_path, conf).unwrap_or_else(|err| {
+            panic!(
+                "{}: can not write `{}`: {}",
+                util::alert("error"),
+                config_path.display(),
+                err
+            )
+        });
+        package::ensure_fetched();
     }
 }
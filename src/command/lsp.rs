@@ -0,0 +1,37 @@
+use command::Command;
+use lsp;
+use std::collections::VecDeque;
+use util;
+
+pub struct LspCommand {}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-lsp : run a language server for mumrik, speaking LSP over stdin and stdout
+USAGE: {} lsp [options...]
+
+options:
+    --help, -h           print help information"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+impl LspCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        while let Some(arg) = args.pop_front() {
+            if arg.as_str() == "--help" || arg.as_str() == "-h" {
+                print_help(&program_name);
+            } else {
+                panic!("{}: unknown argument `{}`", util::alert("error"), arg);
+            }
+        }
+        box LspCommand {}
+    }
+}
+
+impl Command for LspCommand {
+    fn work(self: Box<LspCommand>) {
+        lsp::Server::new().serve();
+    }
+}
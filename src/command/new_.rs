@@ -87,6 +87,8 @@ const BOILERPLACE_MUMRIK_CONF_TOML: &[u8] = br#"
 src = "./src/main.mm"
 output = "./build/output"
 dep = "./dep/"
+
+[dependencies]
 "#;
 
 impl Command for NewCommand {
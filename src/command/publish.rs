@@ -0,0 +1,98 @@
+use command::Command;
+use config;
+use package;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use util;
+
+pub struct PublishCommand {
+    pub registry: Option<PathBuf>,
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-publish : package the local mumrik project into a registry
+USAGE: {} publish [--registry <dir>]
+
+OPTIONS:
+    --registry: the registry directory, `[registry] path` in `mumrik-conf.toml` by default
+
+the modules in the directory of `build.src` are packed into `<name>/<name>-<version>.tar.gz`
+with `[package]` and `[dependencies]`, and listed in `index.toml` of the registry with its checksum"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+impl PublishCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        let mut registry = None;
+        while let Some(arg) = args.pop_front() {
+            match arg.as_str() {
+                "--help" | "-h" => print_help(&program_name),
+                "--registry" => {
+                    registry = Some(PathBuf::from(args.pop_front().unwrap_or_else(|| {
+                        panic!(
+                            "{}: directory is required after `--registry`",
+                            util::alert("error")
+                        )
+                    })))
+                }
+                _ => panic!(
+                    "{}: unknown command line argument `{}`",
+                    util::alert("error"),
+                    arg
+                ),
+            }
+        }
+        box PublishCommand { registry: registry }
+    }
+}
+
+fn canonical(path: PathBuf) -> PathBuf {
+    path.canonicalize().unwrap_or(path)
+}
+
+impl Command for PublishCommand {
+    fn work(self: Box<PublishCommand>) {
+        let project_dir = package::project_dir()
+            .unwrap_or_else(|| panic!("{}: local mumrik project not found", util::alert("error")));
+        let registry = self
+            .registry
+            .or_else(|| package::registry_dir(&project_dir))
+            .unwrap_or_else(|| {
+                panic!(
+                    "{}: no registry is given by `--registry` or `[registry]`",
+                    util::alert("error")
+                )
+            });
+        let config = config::CONFIG.lock().unwrap();
+        let package = config.package.as_ref().unwrap_or_else(|| {
+            panic!(
+                "{}: `[package]` with `name` and `version` is required to publish",
+                util::alert("error")
+            )
+        });
+        let src_dir = project_dir
+            .join(&config.build.src)
+            .parent()
+            .map_or_else(|| project_dir.clone(), Path::to_path_buf);
+        // the fetched dependencies and the build artifacts are not modules of the package
+        let skip = vec![
+            canonical(project_dir.join(&config.build.dep)),
+            canonical(project_dir.join("build")),
+        ];
+        match package::publish(&registry, package, &config.dependencies, &src_dir, &skip) {
+            Ok(entry) => eprintln!(
+                "publish: {} v{} to {}",
+                entry.name,
+                entry.version,
+                registry.join(&entry.tarball).display()
+            ),
+            Err(err) => {
+                eprintln!("{}: {}", util::alert("error"), err);
+                std::process::exit(-1)
+            }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use command::Command;
+use eval;
+use repl::{self, Session};
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use util;
+
+pub struct ReplCommand {}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-repl : evaluate mumrik definitions and expressions interactively
+USAGE: {} repl [options...]
+
+options:
+    --help, -h           print help information"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+impl ReplCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        while let Some(arg) = args.pop_front() {
+            if arg.as_str() == "--help" || arg.as_str() == "-h" {
+                print_help(&program_name);
+            } else {
+                panic!("{}: unknown argument `{}`", util::alert("error"), arg);
+            }
+        }
+        box ReplCommand {}
+    }
+}
+
+fn prompt(text: &str) {
+    print!("{}", text);
+    std::io::stdout().flush().expect("failed to flush stdout");
+}
+
+impl Command for ReplCommand {
+    fn work(self: Box<ReplCommand>) {
+        // the inputs are evaluated on a stack as large as that of `eval::run`
+        std::thread::Builder::new()
+            .stack_size(eval::STACK_SIZE)
+            .spawn(run)
+            .expect("failed to spawn the REPL")
+            .join()
+            .expect("REPL panicked")
+    }
+}
+
+fn run() {
+    println!("mumrik repl, type `:help` for help");
+    let mut session = Session::new(std::io::stdout());
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut input = String::new();
+    prompt("> ");
+    while let Some(Ok(line)) = lines.next() {
+        input += &line;
+        input.push('\n');
+        if repl::is_incomplete(&input) {
+            prompt("| ");
+            continue;
+        }
+        match input.trim() {
+            ":quit" | ":q" => return,
+            ":reset" => {
+                session = session.reset();
+                println!("all definitions are cleared");
+            }
+            _ => match session.input(&input) {
+                Ok(lines) => {
+                    for line in lines {
+                        println!("{}", line);
+                    }
+                }
+                Err(err) => err.emit(),
+            },
+        }
+        input.clear();
+        prompt("> ");
+    }
+    println!();
+}
@@ -1,5 +1,7 @@
-use command::{build::BuildCommand, Command};
+use command::{self, build::BuildCommand, eval, watch, Command};
 use config;
+use diagnostic::MessageFormat;
+use package;
 use std::collections::VecDeque;
 use std::path::PathBuf;
 use util;
@@ -7,12 +9,18 @@ use util;
 pub struct RunCommand {
     pub src: Option<PathBuf>,
     pub output: Option<PathBuf>,
+    pub message_format: MessageFormat,
+    pub interp: bool,
+    pub watch: bool,
 }
 
 impl RunCommand {
     pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
         let mut src = None;
         let mut output = None;
+        let mut message_format = MessageFormat::Human;
+        let mut interp = false;
+        let mut watch = false;
 
         while let Some(arg) = args.pop_front() {
             if arg.as_str() == "--help" || arg.as_str() == "-h" {
@@ -22,6 +30,9 @@ USAGE: {} run [options...] <filename>
 
 options:
     --output, -o <output-filename>  write brinary code to <output-filename>, default: `a.out`
+    --message-format <format>       `human` or `json`, default: `human`
+    --interp                        run with the interpreter instead of compiling
+    --watch                         run again whenever the program or the config file changes
     --help, -h           print help information
 
 filename: input mumrik program filename"#,
@@ -39,6 +50,14 @@ filename: input mumrik program filename"#,
                 output = Some(PathBuf::from(arg[9..].to_string()));
             } else if arg.as_str().starts_with("-o=") {
                 output = Some(PathBuf::from(arg[3..].to_string()));
+            } else if arg.as_str() == "--interp" {
+                interp = true;
+            } else if arg.as_str() == "--watch" {
+                watch = true;
+            } else if arg.as_str() == "--message-format" {
+                message_format = command::parse_message_format(args.pop_front());
+            } else if arg.as_str().starts_with("--message-format=") {
+                message_format = command::parse_message_format(Some(arg[17..].to_string()));
             } else if src.is_some() {
                 panic!(
                     "{}: too many command line argument `{}`",
@@ -53,15 +72,42 @@ filename: input mumrik program filename"#,
         box RunCommand {
             src: src,
             output: output,
+            message_format: message_format,
+            interp: interp,
+            watch: watch,
         }
     }
 }
 
 impl Command for RunCommand {
     fn work(self: Box<RunCommand>) {
+        package::ensure_fetched();
+        if self.watch {
+            let src = self
+                .src
+                .clone()
+                .unwrap_or_else(|| config::CONFIG.lock().unwrap().build.src.clone());
+            watch::watch(|| watch::import_closure(&src));
+        }
+        if self.interp {
+            let src = if let Some(src) = self.src {
+                src
+            } else {
+                config::CONFIG.lock().unwrap().build.src.clone()
+            };
+            let (status, stdout) = eval::interpret(&src, self.message_format);
+            report(status, &stdout, &[]);
+            return;
+        }
+
         let build_command = box BuildCommand {
             src: self.src,
             output: self.output.clone(),
+            message_format: self.message_format,
+            emit: vec![],
+            watch: false,
+            lib: Some(false),
+            package: None,
         };
         build_command.work();
         let output = if let Some(output) = self.output {
@@ -72,25 +118,28 @@ impl Command for RunCommand {
         let result = std::process::Command::new(output)
             .output()
             .expect("internal error: failed to execute output binary");
+        report(result.status.code().unwrap(), &result.stdout, &result.stderr);
+    }
+}
 
-        eprintln!("exit status: {}", result.status.code().unwrap());
-        let stdout = std::str::from_utf8(&result.stdout)
-            .expect("unrecognized output")
-            .trim();
-        if !stdout.is_empty() {
-            println!("-=-=-= stdout =-=-=-");
-            println!("```");
-            println!("{}", stdout);
-            println!("```");
-        }
-        let stderr = std::str::from_utf8(&result.stderr)
-            .expect("unrecognized output")
-            .trim();
-        if !stderr.is_empty() {
-            eprintln!("-=-=-= stderr =-=-=-");
-            eprintln!("```");
-            eprintln!("{}", stderr);
-            eprintln!("```");
-        }
+fn report(status: i32, stdout: &[u8], stderr: &[u8]) {
+    eprintln!("exit status: {}", status);
+    let stdout = std::str::from_utf8(stdout)
+        .expect("unrecognized output")
+        .trim();
+    if !stdout.is_empty() {
+        println!("-=-=-= stdout =-=-=-");
+        println!("```");
+        println!("{}", stdout);
+        println!("```");
+    }
+    let stderr = std::str::from_utf8(stderr)
+        .expect("unrecognized output")
+        .trim();
+    if !stderr.is_empty() {
+        eprintln!("-=-=-= stderr =-=-=-");
+        eprintln!("```");
+        eprintln!("{}", stderr);
+        eprintln!("```");
     }
 }
@@ -0,0 +1,229 @@
+use command::{self, build, watch, workspace, Command};
+use config;
+use diagnostic::{self, MessageFormat};
+use eval;
+use package;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use stdlib;
+use util;
+
+pub struct TestCommand {
+    pub src: Option<PathBuf>,
+    pub message_format: MessageFormat,
+    pub watch: bool,
+    // the member of the workspace to run in
+    pub package: Option<String>,
+}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-test : run the tests of a local mumrik program with the interpreter
+USAGE: {} test [options...] <filename>
+
+runs the `test` declarations in <filename>, in the modules it imports and in the other
+source files under its directory. each test runs in isolation, and the output of a test
+is shown only when it fails. with `--message-format=json`, the results are printed as
+JSON lines, one for each test and for the start and the end of the run.
+
+options:
+    --message-format <format>  `human` or `json`, default: `human`
+    --watch                    run again whenever a source file or the config file changes
+    --package, -p <member>     run only the tests of <member> of the workspace, which runs
+                               those of all its members in its root
+    --help, -h                 print help information
+
+filename: input mumrik program filename"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+impl TestCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        let mut src = None;
+        let mut message_format = MessageFormat::Human;
+        let mut watch = false;
+        let mut package = None;
+
+        while let Some(arg) = args.pop_front() {
+            if arg.as_str() == "--help" || arg.as_str() == "-h" {
+                print_help(&program_name);
+            } else if arg.as_str() == "--watch" {
+                watch = true;
+            } else if arg.as_str() == "--package" || arg.as_str() == "-p" {
+                package = command::parse_package(args.pop_front());
+            } else if arg.as_str().starts_with("--package=") {
+                package = command::parse_package(Some(arg[10..].to_string()));
+            } else if arg.as_str() == "--message-format" {
+                message_format = command::parse_message_format(args.pop_front());
+            } else if arg.as_str().starts_with("--message-format=") {
+                message_format = command::parse_message_format(Some(arg[17..].to_string()));
+            } else if src.is_some() {
+                panic!(
+                    "{}: too many command line argument `{}`",
+                    util::alert("error"),
+                    arg
+                );
+            } else {
+                src = Some(PathBuf::from(arg));
+            }
+        }
+
+        box TestCommand {
+            src: src,
+            message_format: message_format,
+            watch: watch,
+            package: package,
+        }
+    }
+}
+
+// `src`, the other source files under its directory and the modules they import transitively
+fn test_files(src: &PathBuf, entry_modules: &Vec<PathBuf>) -> Vec<PathBuf> {
+    let dir = src
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map_or(PathBuf::from("."), |dir| dir.to_path_buf());
+    let sources = util::source_files(&dir, &package::generated_dirs()).unwrap_or_default();
+    let mut files: Vec<PathBuf> = vec![];
+    let mut seen = vec![];
+    for file in std::iter::once(src.clone())
+        .chain(sources)
+        .flat_map(|file| build::import_closure(&file, entry_modules))
+    {
+        let canonical = file.canonicalize().unwrap_or_else(|_| file.clone());
+        if !seen.contains(&canonical) && !stdlib::is_std_module(&file) {
+            seen.push(canonical);
+            files.push(file);
+        }
+    }
+    files
+}
+
+impl Command for TestCommand {
+    fn work(self: Box<TestCommand>) {
+        let succeeded = workspace::run_members(&self.package, &self.src, self.watch, || {
+            self.test()
+        })
+        .unwrap_or_else(|| self.test());
+        if !succeeded {
+            std::process::exit(1);
+        }
+    }
+}
+
+impl TestCommand {
+    // runs the tests of the project in the current directory, returns whether all of them passed
+    fn test(&self) -> bool {
+        package::ensure_fetched();
+        let src = if let Some(ref src) = self.src {
+            src.clone()
+        } else {
+            config::CONFIG.lock().unwrap().build.src.clone()
+        };
+        let entry_modules = build::entry_modules(&src);
+        if self.watch {
+            watch::watch(|| test_files(&src, &entry_modules));
+        }
+
+        let mut tests = vec![];
+        let mut has_error = false;
+        for file in test_files(&src, &entry_modules) {
+            let mut lints = vec![];
+            match build::read_tests(&file, &entry_modules, &mut lints) {
+                Ok(file_tests) => tests.extend(
+                    file_tests
+                        .into_iter()
+                        .map(|(test, e)| (file.clone(), test, e)),
+                ),
+                Err(diag) => {
+                    diag.emit(self.message_format);
+                    has_error = true;
+                }
+            }
+            for diag in lints.iter() {
+                diag.emit(self.message_format);
+            }
+            has_error |= diagnostic::has_error(&lints);
+        }
+        if has_error {
+            return false;
+        }
+
+        let total = tests.len();
+        let json = self.message_format == MessageFormat::Json;
+        if json {
+            println!(
+                "{}",
+                json!({"type": "suite", "event": "started", "test_count": total})
+            );
+        } else {
+            println!("running {} tests", total);
+        }
+        let mut failed = vec![];
+        for (file, test, e) in tests {
+            if !json {
+                print!("test {} ... ", test.name);
+            }
+            match eval::run(e, vec![]) {
+                (Ok(_), _) if json => println!(
+                    "{}",
+                    json!({"type": "test", "event": "ok", "name": test.name})
+                ),
+                (Ok(_), _) => println!("ok"),
+                (Err(err), out) => {
+                    let out = String::from_utf8_lossy(&out);
+                    if json {
+                        println!(
+                            "{}",
+                            json!({
+                                "type": "test",
+                                "event": "failed",
+                                "name": test.name,
+                                "stdout": out,
+                            })
+                        );
+                    } else {
+                        println!("FAILED");
+                        print!("{}", out);
+                    }
+                    err.to_diagnostic(&file, &std::fs::read_to_string(&file).unwrap_or_default())
+                        .emit(self.message_format);
+                    failed.push(test.name);
+                }
+            }
+        }
+        let passed = total - failed.len();
+        if json {
+            println!(
+                "{}",
+                json!({
+                    "type": "suite",
+                    "event": if failed.is_empty() { "ok" } else { "failed" },
+                    "passed": passed,
+                    "failed": failed.len(),
+                })
+            );
+            return failed.is_empty();
+        }
+        println!();
+        if failed.is_empty() {
+            println!("test result: ok. {} passed; 0 failed", passed);
+            true
+        } else {
+            println!("failures:");
+            for name in failed.iter() {
+                println!("    {}", name);
+            }
+            println!();
+            println!(
+                "test result: FAILED. {} passed; {} failed",
+                passed,
+                failed.len()
+            );
+            false
+        }
+    }
+}
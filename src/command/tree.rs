@@ -0,0 +1,51 @@
+use command::Command;
+use package;
+use std::collections::VecDeque;
+use util;
+
+pub struct TreeCommand {}
+
+fn print_help(program_name: &str) {
+    println!(
+        r#"mumrik-tree : print the resolved dependencies of the local mumrik project as a tree
+USAGE: {} tree
+
+the packages shown before are marked with `(*)` instead of repeating their dependencies"#,
+        program_name
+    );
+    std::process::exit(0);
+}
+
+impl TreeCommand {
+    pub fn parse(program_name: String, mut args: VecDeque<String>) -> Box<dyn Command> {
+        while let Some(arg) = args.pop_front() {
+            if arg.as_str() == "--help" || arg.as_str() == "-h" {
+                print_help(&program_name);
+            } else {
+                panic!(
+                    "{}: too many command line argument `{}`",
+                    util::alert("error"),
+                    arg
+                );
+            }
+        }
+        box TreeCommand {}
+    }
+}
+
+impl Command for TreeCommand {
+    fn work(self: Box<TreeCommand>) {
+        let project_dir = package::project_dir()
+            .unwrap_or_else(|| panic!("{}: local mumrik project not found", util::alert("error")));
+        match package::resolve_project(&project_dir) {
+            Ok(resolution) => print!(
+                "{}",
+                resolution.render_tree(&package::project_label(&project_dir))
+            ),
+            Err(err) => {
+                eprintln!("{}: {}", util::alert("error"), err);
+                std::process::exit(-1)
+            }
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use command::build;
+use config;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(300);
+// editors often write a file in several steps, so wait until changes settle
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+// `src` and the modules it imports transitively
+pub fn import_closure(src: &PathBuf) -> Vec<PathBuf> {
+    build::import_closure(src, &build::entry_modules(src))
+}
+
+fn snapshot(files: &Vec<PathBuf>) -> Vec<Option<SystemTime>> {
+    files
+        .iter()
+        .map(|file| {
+            std::fs::metadata(file)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+// re-runs this command without `--watch` whenever `files` or the config file change, never returns
+pub fn watch<F: Fn() -> Vec<PathBuf>>(files: F) -> ! {
+    let exe = std::env::current_exe().expect("failed to get the path of mumrik");
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| arg.as_str() != "--watch")
+        .collect();
+    loop {
+        let mut watched = files();
+        watched.extend(config::config_path());
+        let mut last = snapshot(&watched);
+
+        // clears the screen
+        print!("\u{001B}[2J\u{001B}[1;1H");
+        std::io::stdout().flush().expect("failed to write to stdout");
+        match std::process::Command::new(&exe).args(&args).status() {
+            Ok(status) => match status.code() {
+                Some(code) => eprintln!("[watch] exited with status {}", code),
+                None => eprintln!("[watch] terminated by a signal"),
+            },
+            Err(err) => eprintln!("[watch] failed to run mumrik: {}", err),
+        }
+        eprintln!("[watch] waiting for changes in {} files...", watched.len());
+
+        let mut current = snapshot(&watched);
+        while current == last {
+            std::thread::sleep(POLL_INTERVAL);
+            current = snapshot(&watched);
+        }
+        while current != last {
+            last = current;
+            std::thread::sleep(DEBOUNCE);
+            current = snapshot(&watched);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use config;
+use package::{self, Member};
+use std::path::PathBuf;
+use util;
+
+// the members which the command runs in, `None` when it runs in the current project as usual:
+// the member `package`, or all members in the root of a workspace
+fn members(package: &Option<String>, src: &Option<PathBuf>) -> Option<Vec<Member>> {
+    let workspace = match (package::workspace(), package) {
+        (Some(workspace), _) => workspace,
+        (None, &Some(_)) => panic!(
+            "{}: `--package` is only available in a workspace",
+            util::alert("error")
+        ),
+        (None, &None) => return None,
+    };
+    let name = match package {
+        Some(ref name) if src.is_some() => panic!(
+            "{}: `--package {}` can not be given with a filename",
+            util::alert("error"),
+            name
+        ),
+        Some(ref name) => name,
+        None => {
+            let project_dir = package::project_dir().and_then(|dir| dir.canonicalize().ok());
+            if src.is_some() || project_dir.as_ref() != Some(&workspace.root) {
+                return None;
+            }
+            return Some(workspace.members);
+        }
+    };
+    match workspace.member(name) {
+        Some(member) => Some(vec![member.clone()]),
+        None => {
+            let help = util::similar_name(
+                name,
+                workspace.members.iter().map(|member| member.name.clone()),
+            )
+            .map_or(String::new(), |name| format!(", did you mean `{}`?", name));
+            panic!(
+                "{}: the workspace has no member `{}`{}",
+                util::alert("error"),
+                name,
+                help
+            )
+        }
+    }
+}
+
+// runs `run` in the directory of each member of the workspace with the config of the member,
+// when it is given `--package` or runs in the root of the workspace. `run` returns whether it
+// succeeded, and this whether all of them did, or `None` if the command runs as usual.
+pub fn run_members<F: FnMut() -> bool>(
+    package: &Option<String>,
+    src: &Option<PathBuf>,
+    watch: bool,
+    mut run: F,
+) -> Option<bool> {
+    let members = members(package, src)?;
+    if watch && members.len() != 1 {
+        panic!(
+            "{}: `--watch` runs in a single member, select it by `--package`",
+            util::alert("error")
+        );
+    }
+    let mut failed = vec![];
+    for member in members.iter() {
+        eprintln!("member: {} ({})", member.name, member.dir.display());
+        // the paths in the config of the member are relative to its directory
+        std::env::set_current_dir(&member.dir).unwrap_or_else(|err| {
+            panic!(
+                "{}: can not enter `{}`: {}",
+                util::alert("error"),
+                member.dir.display(),
+                err
+            )
+        });
+        config::reload();
+        if !run() {
+            failed.push(format!("`{}`", member.name));
+        }
+    }
+    // a single member reports its failure by itself
+    if members.len() > 1 && !failed.is_empty() {
+        eprintln!("{}: failed in {}", util::alert("error"), failed.join(", "));
+    }
+    Some(failed.is_empty())
+}
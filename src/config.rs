@@ -1,4 +1,9 @@
-use serde_derive::Deserialize;
+use lint;
+use serde::de::{self, Deserializer, MapAccess, Visitor};
+use serde::Deserialize;
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 use std::fs::File;
 use std::io::{BufReader, Read};
 use std::path::PathBuf;
@@ -9,7 +14,17 @@ pub const FILENAME: &str = "mumrik-conf.toml";
 
 #[derive(Debug, Deserialize)]
 pub struct Config {
+    // the root of a workspace may have no program of its own
+    #[serde(default = "BuildConfig::default")]
     pub build: BuildConfig,
+    #[serde(default)]
+    pub lint: LintConfig,
+    pub package: Option<PackageConfig>,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+    pub registry: Option<RegistryConfig>,
+    pub lib: Option<LibConfig>,
+    pub workspace: Option<WorkspaceConfig>,
 }
 
 lazy_static! {
@@ -20,6 +35,12 @@ impl Config {
     fn default() -> Config {
         Config {
             build: BuildConfig::default(),
+            lint: LintConfig::default(),
+            package: None,
+            dependencies: BTreeMap::new(),
+            registry: None,
+            lib: None,
+            workspace: None,
         }
     }
 
@@ -29,16 +50,35 @@ impl Config {
                 let mut config = String::new();
                 let mut f = BufReader::new(File::open(config_path).unwrap());
                 f.read_to_string(&mut config).unwrap();
-                toml::from_str(config.as_str()).unwrap_or_else(|err| {
+                let config: Config = toml::from_str(config.as_str()).unwrap_or_else(|err| {
                     eprintln!("{}: {}", util::alert("invalid config file"), err);
                     std::process::exit(-1);
-                })
+                });
+                config.lint.validate();
+                for (name, dep) in config.dependencies.iter() {
+                    if let Err(err) = dep.validate() {
+                        eprintln!(
+                            "{}: dependency `{}` in `[dependencies]`: {}",
+                            util::alert("invalid config file"),
+                            name,
+                            err
+                        );
+                        std::process::exit(-1);
+                    }
+                }
+                config
             }
             None => Config::default(),
         }
     }
 }
 
+// loads the config of the project in the current directory again, e.g) for each member of a
+// workspace
+pub fn reload() {
+    *CONFIG.lock().unwrap() = Config::load();
+}
+
 pub fn config_path() -> Option<PathBuf> {
     let mut dir = std::env::current_dir().unwrap();
     while {
@@ -57,6 +97,26 @@ pub struct BuildConfig {
     pub src: PathBuf,
     pub output: PathBuf,
     pub dep: PathBuf,
+    // the command linking the object files of the modules, invoked like `gcc`
+    #[serde(default = "default_linker")]
+    pub linker: String,
+    // whether the definitions of `std.prelude` are imported into every module implicitly
+    #[serde(default = "default_prelude")]
+    pub prelude: bool,
+    // the libraries linked with the program, e.g) `m` for `-lm`
+    #[serde(default, rename = "link-libs")]
+    pub link_libs: Vec<String>,
+    // the C files compiled with the program, which define the functions of `extern func`
+    #[serde(default, rename = "c-sources")]
+    pub c_sources: Vec<PathBuf>,
+}
+
+fn default_linker() -> String {
+    "gcc".to_string()
+}
+
+fn default_prelude() -> bool {
+    true
 }
 
 impl BuildConfig {
@@ -65,6 +125,170 @@ impl BuildConfig {
             src: PathBuf::from("./main.mm".to_string()),
             output: PathBuf::from("./a.out".to_string()),
             dep: PathBuf::from(".".to_string()),
+            linker: default_linker(),
+            prelude: default_prelude(),
+            link_libs: vec![],
+            c_sources: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LintConfig {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl LintConfig {
+    fn validate(&self) {
+        for name in self.allow.iter().chain(self.deny.iter()) {
+            if lint::Lint::from_name(name).is_none() {
+                eprintln!(
+                    "{}: unknown lint `{}` in `[lint]`",
+                    util::alert("invalid config file"),
+                    name
+                );
+                std::process::exit(-1);
+            }
         }
     }
 }
+
+// e.g) `foo = { git = "https://github.com/user/foo", rev = "v1" }`, `foo = { path = "../foo" }`
+// or `foo = { version = "^1.2" }`, which is looked up in the registry and also written as
+// `foo = "^1.2"`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Dependency {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    // a branch, tag or commit, the default branch when omitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    // a semver requirement, e.g) `^1.2` or `>=1.0, <2.0`. the tags of a git repository are
+    // its versions, e.g) `v1.2.0`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+// the table form of `Dependency`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DependencyTable {
+    git: Option<String>,
+    rev: Option<String>,
+    path: Option<PathBuf>,
+    version: Option<String>,
+}
+
+struct DependencyVisitor;
+
+impl<'de> Visitor<'de> for DependencyVisitor {
+    type Value = Dependency;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a version requirement or a table")
+    }
+
+    fn visit_str<E: de::Error>(self, version: &str) -> Result<Dependency, E> {
+        Ok(Dependency {
+            git: None,
+            rev: None,
+            path: None,
+            version: Some(version.to_string()),
+        })
+    }
+
+    fn visit_map<M: MapAccess<'de>>(self, map: M) -> Result<Dependency, M::Error> {
+        let table = DependencyTable::deserialize(de::value::MapAccessDeserializer::new(map))?;
+        Ok(Dependency {
+            git: table.git,
+            rev: table.rev,
+            path: table.path,
+            version: table.version,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for Dependency {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Dependency, D::Error> {
+        deserializer.deserialize_any(DependencyVisitor)
+    }
+}
+
+impl Dependency {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        match (&self.git, &self.path) {
+            (Some(_), Some(_)) => Err("both `git` and `path` are given"),
+            (None, None) if self.version.is_none() => {
+                Err("either `git`, `path` or `version` is required")
+            }
+            (None, _) if self.rev.is_some() => Err("`rev` is only allowed with `git`"),
+            (Some(_), None) if self.rev.is_some() && self.version.is_some() => {
+                Err("`rev` and `version` can not be given together")
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// the name and the version of the project as a package
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageConfig {
+    pub name: String,
+    pub version: String,
+}
+
+// builds the program as a C library, which exports its `pub` functions
+#[derive(Debug, Clone, Deserialize)]
+pub struct LibConfig {
+    // e.g) `calc` of `libcalc.a`, `libcalc.so` and `calc.h`, the name of the package by default
+    pub name: Option<String>,
+    #[serde(default = "default_lib_kind")]
+    pub kind: Vec<LibKind>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LibKind {
+    // `.a`
+    Static,
+    // `.so`
+    Shared,
+}
+
+fn default_lib_kind() -> Vec<LibKind> {
+    vec![LibKind::Static, LibKind::Shared]
+}
+
+impl Default for LibConfig {
+    fn default() -> LibConfig {
+        LibConfig {
+            name: None,
+            kind: default_lib_kind(),
+        }
+    }
+}
+
+// the directories of the packages of a workspace, relative to the config file
+#[derive(Debug, Clone, Deserialize)]
+pub struct WorkspaceConfig {
+    pub members: Vec<PathBuf>,
+}
+
+// a local directory which has the tarballs of packages listed in its `index.toml`
+#[derive(Debug, Clone, Deserialize)]
+pub struct RegistryConfig {
+    pub path: PathBuf,
+}
+
+// the part of the config file of a package which the projects depending on it read
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    pub package: Option<PackageConfig>,
+    #[serde(default)]
+    pub dependencies: BTreeMap<String, Dependency>,
+}
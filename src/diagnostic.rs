@@ -0,0 +1,272 @@
+use ast::{Position, Type};
+use parser;
+use peg;
+use serde_derive::Serialize;
+use std::path::Path;
+use typecheck;
+use util;
+
+pub mod code;
+use self::code::Code;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
+impl MessageFormat {
+    pub fn parse(s: &str) -> Option<MessageFormat> {
+        match s {
+            "human" => Some(MessageFormat::Human),
+            "json" => Some(MessageFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Span {
+    pub file: String,
+    pub byte_start: usize,
+    pub byte_end: usize,
+    pub line_start: usize,
+    pub column_start: usize,
+    pub line_end: usize,
+    pub column_end: usize,
+    #[serde(skip)]
+    pub lines: Vec<String>,
+}
+
+impl Span {
+    pub fn new(file: &Path, src: &str, pos: Position) -> Span {
+        // positions given by the parser include trailing whitespaces and comments
+        let end = std::cmp::min(pos.end, src.len());
+        let start = std::cmp::min(pos.start, end);
+        let pos = Position {
+            start: start,
+            end: start
+                + src
+                    .get(start..end)
+                    .map_or(end - start, |s| s.trim_end().len()),
+        };
+        let (line_start, column_start) = line_column(src, pos.start);
+        let (line_end, column_end) = line_column(src, pos.end);
+        Span {
+            file: file.to_str().unwrap_or("<unknown>").to_string(),
+            byte_start: pos.start,
+            byte_end: pos.end,
+            line_start: line_start,
+            column_start: column_start,
+            line_end: line_end,
+            column_end: column_end,
+            lines: src
+                .split('\n')
+                .skip(line_start - 1)
+                .take(line_end - line_start + 1)
+                .map(|line| line.to_string())
+                .collect(),
+        }
+    }
+}
+
+// 1-origin line and column of the byte offset `pos` in `src`
+fn line_column(src: &str, pos: usize) -> (usize, usize) {
+    let pos = std::cmp::min(pos, src.len());
+    let before = &src[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = match before.rfind('\n') {
+        Some(idx) => before[idx + 1..].chars().count() + 1,
+        None => before.chars().count() + 1,
+    };
+    (line, column)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RelatedSpan {
+    pub message: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub span: Option<Span>,
+    pub related: Vec<RelatedSpan>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: Code, message: String, span: Option<Span>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            code: code.id,
+            message: message,
+            span: span,
+            related: vec![],
+            notes: vec![],
+        }
+    }
+
+    pub fn warning(code: Code, message: String, span: Option<Span>) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(code, message, span)
+        }
+    }
+
+    pub fn with_related(mut self, message: String, span: Span) -> Diagnostic {
+        self.related.push(RelatedSpan {
+            message: message,
+            span: span,
+        });
+        self
+    }
+
+    pub fn with_note(mut self, note: String) -> Diagnostic {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_suggestion(self, suggestion: Option<String>) -> Diagnostic {
+        match suggestion {
+            Some(suggestion) => self.with_note(format!("help: did you mean `{}`?", suggestion)),
+            None => self,
+        }
+    }
+
+    pub fn from_parse_error(
+        file: &Path,
+        src: &str,
+        err: peg::error::ParseError<peg::str::LineCol>,
+    ) -> Diagnostic {
+        let pos = Position {
+            start: err.location.offset,
+            end: std::cmp::min(err.location.offset + 1, src.len()),
+        };
+        Diagnostic::error(
+            code::SYNTAX_ERROR,
+            "syntax error".to_string(),
+            Some(Span::new(file, src, pos)),
+        )
+        .with_note(format!(
+            "expected: {}",
+            parser::Expected::from(err.expected)
+        ))
+    }
+
+    pub fn from_type_error(file: &Path, src: &str, err: typecheck::Error) -> Diagnostic {
+        match err {
+            typecheck::Error::RecOccur { pos, var, typ } => Diagnostic::error(
+                code::RECURSIVE_TYPE,
+                format!("type variable {} occurs recursively in {}", var, typ),
+                Some(Span::new(file, src, pos)),
+            ),
+            typecheck::Error::Unify { pos, typ1, typ2 } => Diagnostic::error(
+                code::MISMATCHED_TYPES,
+                format!("unification failed with `{}` and `{}`", typ1, typ2),
+                Some(Span::new(file, src, pos)),
+            ),
+            typecheck::Error::UnboundVar {
+                pos,
+                name,
+                suggestion,
+            } => Diagnostic::error(
+                code::UNBOUND_VARIABLE,
+                format!("unbound variable: {}", name),
+                Some(Span::new(file, src, pos)),
+            )
+            .with_suggestion(suggestion.map(|name| format!("{}", name))),
+            typecheck::Error::MissingField { pos, typ, label } => {
+                let suggestion = match typ {
+                    Type::Record(ref fields) => util::similar_name(
+                        &format!("{}", label),
+                        fields.keys().map(|label| format!("{}", label)),
+                    ),
+                    _ => None,
+                };
+                Diagnostic::error(
+                    code::MISSING_FIELD,
+                    format!("`{}` does not have field `{}`", typ, label),
+                    Some(Span::new(file, src, pos)),
+                )
+                .with_suggestion(suggestion)
+            }
+            typecheck::Error::NotRecord { pos, typ, label } => Diagnostic::error(
+                code::NOT_A_RECORD,
+                format!("`{}` cannot be indexed with label `{}`", typ, label),
+                Some(Span::new(file, src, pos)),
+            ),
+            typecheck::Error::NotArray { pos, typ } => Diagnostic::error(
+                code::NOT_AN_ARRAY,
+                format!("`{}` cannot be indexed with integer", typ),
+                Some(Span::new(file, src, pos)),
+            ),
+            typecheck::Error::Ambiguous { pos } => Diagnostic::error(
+                code::TYPE_ANNOTATION_NEEDED,
+                "cannot infer the type of this expression".to_string(),
+                Some(Span::new(file, src, pos)),
+            )
+            .with_note("help: add a type annotation".to_string()),
+        }
+    }
+
+    fn render_human(&self) -> String {
+        let header = match self.severity {
+            Severity::Error => util::alert(&format!("[error {}]", self.code)),
+            Severity::Warning => util::warn(&format!("[warning {}]", self.code)),
+        };
+        let mut msg = format!("{} {}", header, self.message);
+        if let Some(ref span) = self.span {
+            msg += &render_span(span);
+        }
+        for related in self.related.iter() {
+            msg += &format!("\n{}", related.message);
+            msg += &render_span(&related.span);
+        }
+        for note in self.notes.iter() {
+            msg += &format!("\n{}", note);
+        }
+        msg
+    }
+
+    // to stderr in either format, so that the output of the program is kept apart
+    pub fn emit(&self, format: MessageFormat) {
+        match format {
+            MessageFormat::Human => eprintln!("{}", self.render_human()),
+            MessageFormat::Json => eprintln!("{}", serde_json::to_string(self).unwrap()),
+        }
+    }
+}
+
+pub fn has_error(diags: &Vec<Diagnostic>) -> bool {
+    diags.iter().any(|diag| diag.severity == Severity::Error)
+}
+
+fn render_span(span: &Span) -> String {
+    let mut msg = format!(
+        "\n  --> {}:{}:{}",
+        span.file, span.line_start, span.column_start
+    );
+    for line in span.lines.iter() {
+        msg += &format!("\n> {}", line);
+    }
+    if span.line_start == span.line_end {
+        let width = std::cmp::max(span.column_end.saturating_sub(span.column_start), 1);
+        msg += &format!(
+            "\n  {}{}",
+            " ".repeat(span.column_start - 1),
+            "^".repeat(width)
+        );
+    }
+    msg
+}
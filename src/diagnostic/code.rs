@@ -0,0 +1,437 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Code {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub explanation: &'static str,
+}
+
+pub const SYNTAX_ERROR: Code = Code {
+    id: "E0001",
+    title: "syntax error",
+    explanation: r#"The source file does not follow the grammar of mumrik.
+The diagnostic points at the furthest position the parser reached and
+lists the tokens it expected there.
+
+Erroneous example:
+```
+let a = ;
+a
+```
+
+Every `let` needs an initializer expression:
+```
+let a = 42;
+a
+```"#,
+};
+
+pub const UNBOUND_VARIABLE: Code = Code {
+    id: "E0002",
+    title: "unbound variable",
+    explanation: r#"A variable is used, but no `let`, `func` or function parameter with
+that name is in scope.
+
+Erroneous example:
+```
+let a = 1;
+a + b
+```
+
+Define the variable before it is used:
+```
+let a = 1;
+let b = 2;
+a + b
+```"#,
+};
+
+pub const MISSING_FIELD: Code = Code {
+    id: "E0003",
+    title: "missing record field",
+    explanation: r#"A field is accessed on a record whose type does not have a field
+with that label.
+
+Erroneous example:
+```
+let p = {x = 1, y = 2};
+p.z
+```
+
+Only access the fields the record has:
+```
+let p = {x = 1, y = 2};
+p.x
+```"#,
+};
+
+pub const MISMATCHED_TYPES: Code = Code {
+    id: "E0004",
+    title: "mismatched types",
+    explanation: r#"Two types which must be the same could not be unified.
+This happens e.g. when a function is applied to an argument of another
+type than its parameter, or when the branches of `if` have different types.
+
+Erroneous example:
+```
+func f x: Int :Int {
+    if x { 42 } else { 12 }
+}
+f 12
+```
+
+The condition of `if` must be `Bool`:
+```
+func f x: Int :Int {
+    if x == 0 { 42 } else { 12 }
+}
+f 12
+```"#,
+};
+
+pub const RECURSIVE_TYPE: Code = Code {
+    id: "E0005",
+    title: "recursive type",
+    explanation: r#"Type inference required a type variable to be equal to a type which
+contains the variable itself, i.e. an infinitely large type.
+
+Erroneous example:
+```
+func f x: a :Int {
+    x x
+}
+f
+```
+
+`x` is applied to itself, so its type `a` would have to be `a -> Int`.
+Give the parameter a concrete, non-recursive type instead."#,
+};
+
+pub const NOT_A_RECORD: Code = Code {
+    id: "E0006",
+    title: "field access on a non-record value",
+    explanation: r#"`e.label` is only allowed when `e` is a record.
+
+Erroneous example:
+```
+let a = 42;
+a.x
+```
+
+Access fields only of records:
+```
+let a = {x = 42};
+a.x
+```"#,
+};
+
+pub const NOT_AN_ARRAY: Code = Code {
+    id: "E0007",
+    title: "indexing a non-array value",
+    explanation: r#"`e[i]` is only allowed when `e` is an array.
+
+Erroneous example:
+```
+let a = 42;
+a[0]
+```
+
+Index only arrays:
+```
+let a = [42, 32];
+a[0]
+```"#,
+};
+
+pub const UNKNOWN_IMPORT: Code = Code {
+    id: "E0008",
+    title: "unknown import",
+    explanation: r#"No module file corresponding to an `import` was found.
+`import a.b.c;` searches `a/b/c.mm` in the directory of the compiled
+source file and in the `build.dep` directory of `mumrik-conf.toml`.
+
+Erroneous example:
+```
+import no_such_module;
+42
+```
+
+Make sure the module file exists, or install the package which provides it
+with `mumrik install`."#,
+};
+
+pub const UNREADABLE_FILE: Code = Code {
+    id: "E0009",
+    title: "unreadable file",
+    explanation: r#"A source file given on the command line, or found for an `import`,
+could not be read. Check that the file exists, that it is readable and that
+its content is valid UTF-8."#,
+};
+
+pub const TYPE_ANNOTATION_NEEDED: Code = Code {
+    id: "E0010",
+    title: "type annotation needed",
+    explanation: r#"A field or an element is taken out of a value whose type cannot be
+inferred, so it is unknown whether it is a record or an array.
+
+Erroneous example:
+```
+func get_x r: t :Int { r.x }
+get_x
+```
+
+Annotate the value with a concrete type:
+```
+func get_x r: {x: Int} :Int { r.x }
+get_x
+```"#,
+};
+
+pub const ASSERT_OUTSIDE_TEST: Code = Code {
+    id: "E0011",
+    title: "assert outside of a test",
+    explanation: r#"`assert` is used in the program itself. Assertions are checked only by
+`mumrik test`, so they are allowed only in the body of a `test` declaration.
+
+Erroneous example:
+```
+func double x: Int { x * 2 }
+assert (double 2 == 4);
+double 3
+```
+
+Move the assertion into a test:
+```
+func double x: Int { x * 2 }
+test "double doubles" { assert (double 2 == 4) }
+double 3
+```"#,
+};
+
+pub const UNKNOWN_MEMBER: Code = Code {
+    id: "E0012",
+    title: "unknown module member",
+    explanation: r#"An imported module does not define the referred name. The `pub`
+top-level definitions of a module are referred to by the module name, e.g.
+`math.gcd` after `import lib.math;`, by the name given with `as`, or directly
+when they are listed in the import.
+
+Erroneous example:
+```
+import lib.math;
+math.gdc {a = 4, b = 6}
+```
+
+Check the spelling of the name and that the module defines it at the top level:
+```
+import lib.math;
+math.gcd {a = 4, b = 6}
+```"#,
+};
+
+pub const PRIVATE_DEFINITION: Code = Code {
+    id: "E0013",
+    title: "private definition",
+    explanation: r#"An imported module defines the referred name, but does not export it.
+Only the top-level definitions declared with `pub func`, `pub let` or
+`pub type` are visible to the modules importing it.
+
+Erroneous example:
+```
+// lib/math.mm
+func gcd_step {a: Int, b: Int} :Int { a - a / b * b }
+pub func gcd {a: Int, b: Int} :Int {
+    if b == 0 { a } else { gcd {a = b, b = gcd_step {a = a, b = b}} }
+}
+
+// main.mm
+import lib.math;
+math.gcd_step {a = 4, b = 6}
+```
+
+Use an exported definition, or declare the definition as `pub`:
+```
+pub func gcd_step {a: Int, b: Int} :Int { a - a / b * b }
+```"#,
+};
+
+pub const IMPORT_CYCLE: Code = Code {
+    id: "E0014",
+    title: "import cycle",
+    explanation: r#"A module imports itself through a chain of imports. The definitions
+of a module are checked after those of the modules it imports, so the
+modules of a cycle can not be ordered. The diagnostic lists the whole chain
+and points at each import of it.
+
+Erroneous example:
+```
+// lib/a.mm
+import lib.b;
+pub func f x: Int :Int { b.g x }
+
+// lib/b.mm
+import lib.a;
+pub func g x: Int :Int { a.f x }
+```
+
+Move the definitions which the modules share into a module which imports
+neither of them."#,
+};
+
+pub const UNWRITABLE_FILE: Code = Code {
+    id: "E0015",
+    title: "unwritable file",
+    explanation: r#"An output of the compiler could not be written, e.g. the executable,
+an artifact requested with `--emit`, or an object file in the build directory.
+Check that the destination directory exists and is writable and that the disk
+is not full."#,
+};
+
+pub const RUNTIME_ERROR: Code = Code {
+    id: "E0016",
+    title: "runtime error",
+    explanation: r#"Evaluating the program with the interpreter failed, e.g. in `mumrik eval`,
+`mumrik test` or the REPL. This happens on a division by zero, an index out of
+the bounds of an array, a comparison of functions, a failed `assert`, a call
+of an `extern` function, which only the compiled program can call, or a
+recursion too deep for the interpreter.
+
+Example:
+```
+let a = [1, 2];
+a[2]
+```"#,
+};
+
+pub const COMMAND_FAILED: Code = Code {
+    id: "E0017",
+    title: "failed command",
+    explanation: r#"A command which the compiler runs on the files of the project failed, e.g.
+the C compiler on `c-sources`, the linker with `link-libs` or `ar` making a
+static library. Its output follows the message.
+Check the C files, that the libraries are installed, and `linker` in the
+`[build]` section of `mumrik-conf.toml`."#,
+};
+
+pub const INVALID_LIBRARY: Code = Code {
+    id: "E0018",
+    title: "invalid library",
+    explanation: r#"A program built as a C library with `--lib` or `[lib]` can not be
+exported. A library has only definitions, whose `pub` functions C programs
+call with `Int`, `Bool`, `Char`, `Unit` and records of them. Its functions
+never refer to top-level `let`s, which no one initializes.
+
+Example:
+```
+pub let answer = 42;
+pub func twice x: Int :Int { x * 2 }
+twice 1
+```"#,
+};
+
+pub const UNUSED_VARIABLE: Code = Code {
+    id: "W0001",
+    title: "unused variable",
+    explanation: r#"A `let`, `func` or function parameter is never referenced.
+Definitions at the top level of a module without a final expression are
+exported to importers and are never reported.
+Prefix the name with `_` to silence the warning for a single binding, or set
+`allow = ["unused-variable"]` in the `[lint]` section of `mumrik-conf.toml`.
+
+Example:
+```
+func f x: Int :Int { 42 }
+let unused = 1;
+f 0
+```"#,
+};
+
+pub const UNUSED_IMPORT: Code = Code {
+    id: "W0002",
+    title: "unused import",
+    explanation: r#"None of the definitions of an imported module is referenced.
+Remove the `import`, or set `allow = ["unused-import"]` in the `[lint]`
+section of `mumrik-conf.toml`.
+
+Example:
+```
+import gcd;
+42
+```"#,
+};
+
+pub const SHADOWING: Code = Code {
+    id: "W0003",
+    title: "shadowed name",
+    explanation: r#"A definition hides another definition with the same name, which makes
+the outer one unreachable in the rest of its scope.
+Rename one of them, or set `allow = ["shadowing"]` in the `[lint]` section
+of `mumrik-conf.toml`.
+
+Example:
+```
+let a = 1;
+let a = a + 1;
+a
+```"#,
+};
+
+pub const UNUSED_VALUE: Code = Code {
+    id: "W0004",
+    title: "unused value",
+    explanation: r#"An expression in a `;` sequence produces a value other than `unit`,
+and the value is thrown away. This usually means a computation was written
+but its result was forgotten. Assignments with `<-` are never reported.
+Bind the value with `let`, or set `allow = ["unused-value"]` in the `[lint]`
+section of `mumrik-conf.toml`.
+
+Example:
+```
+let a = 1;
+a + 1;
+println a
+```"#,
+};
+
+pub const UNREACHABLE_CODE: Code = Code {
+    id: "W0005",
+    title: "unreachable code",
+    explanation: r#"The condition of an `if` is the literal `true` or `false`, so one of its
+branches is never evaluated. Remove the `if` and keep the branch which is
+taken, or set `allow = ["unreachable-code"]` in the `[lint]` section of
+`mumrik-conf.toml`.
+
+Example:
+```
+if true { 1 } else { 2 }
+```"#,
+};
+
+pub const CODES: &[Code] = &[
+    SYNTAX_ERROR,
+    UNBOUND_VARIABLE,
+    MISSING_FIELD,
+    MISMATCHED_TYPES,
+    RECURSIVE_TYPE,
+    NOT_A_RECORD,
+    NOT_AN_ARRAY,
+    UNKNOWN_IMPORT,
+    UNREADABLE_FILE,
+    TYPE_ANNOTATION_NEEDED,
+    ASSERT_OUTSIDE_TEST,
+    UNKNOWN_MEMBER,
+    PRIVATE_DEFINITION,
+    IMPORT_CYCLE,
+    UNWRITABLE_FILE,
+    RUNTIME_ERROR,
+    COMMAND_FAILED,
+    INVALID_LIBRARY,
+    UNUSED_VARIABLE,
+    UNUSED_IMPORT,
+    SHADOWING,
+    UNUSED_VALUE,
+    UNREACHABLE_CODE,
+];
+
+pub fn lookup(id: &str) -> Option<Code> {
+    CODES.iter().find(|code| code.id == id).cloned()
+}
@@ -0,0 +1,218 @@
+use ast::{Expr, Type};
+use ident::Ident;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Html,
+    Markdown,
+}
+
+impl Format {
+    pub fn parse(s: &str) -> Option<Format> {
+        match s {
+            "html" => Some(Format::Html),
+            "markdown" | "md" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Html => "html",
+            Format::Markdown => "md",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub name: Ident,
+    // e.g) `func add {x: Int, y: Int} :Int`
+    pub signature: String,
+    pub doc: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    // e.g) `lib.math`
+    pub name: String,
+    pub imports: Vec<String>,
+    pub items: Vec<Item>,
+}
+
+fn is_user_name(name: &Ident) -> bool {
+    !format!("{}", name).starts_with('<')
+}
+
+fn func_signature(name: &Ident, param_name: &Ident, param_type: &Type, ret_type: &Type) -> String {
+    if param_name.is_omitted_param_name() {
+        format!("func {} {} :{}", name, param_type, ret_type)
+    } else {
+        format!("func {} {}: {} :{}", name, param_name, param_type, ret_type)
+    }
+}
+
+// the top-level definitions of a typed program whose outermost `imported_num` definitions are
+// spliced from imported modules
+pub fn items(e: &Expr, imported_num: usize) -> Vec<Item> {
+    let mut items = vec![];
+    let mut e = e;
+    let mut skip = imported_num;
+    loop {
+        let (item, left) = match e {
+            Expr::Let(ref name, ref typ, _, box ref left, _, ref doc) => (
+                Item {
+                    name: name.clone(),
+                    signature: format!("let {}: {}", name, typ),
+                    doc: doc.clone(),
+                },
+                left,
+            ),
+            Expr::Func {
+                ref name,
+                ref param_name,
+                ref param_type,
+                ref ret_type,
+                box ref left,
+                ref doc,
+                ..
+            } => (
+                Item {
+                    name: name.clone(),
+                    signature: func_signature(name, param_name, param_type, ret_type),
+                    doc: doc.clone(),
+                },
+                left,
+            ),
+            Expr::LetType(ref name, ref typ, box ref left, ref doc) => (
+                Item {
+                    name: name.clone(),
+                    signature: format!("type {} = {}", name, typ),
+                    doc: doc.clone(),
+                },
+                left,
+            ),
+            _ => return items,
+        };
+        if skip > 0 {
+            skip -= 1;
+        } else if is_user_name(&item.name) {
+            items.push(item);
+        }
+        e = left;
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn link(module: &str, format: Format) -> String {
+    format!("{}.{}", module, format.extension())
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{} - mumrik doc</title>
+</head>
+<body>
+{}</body>
+</html>
+"#,
+        escape(title),
+        body
+    )
+}
+
+pub fn render_module(module: &Module, format: Format) -> String {
+    match format {
+        Format::Html => {
+            let mut body = format!(
+                "<nav><a href=\"index.html\">index</a></nav>\n<h1>Module <code>{}</code></h1>\n",
+                escape(&module.name)
+            );
+            if !module.imports.is_empty() {
+                let imports: Vec<String> = module
+                    .imports
+                    .iter()
+                    .map(|import| {
+                        format!(
+                            "<a href=\"{}\"><code>{}</code></a>",
+                            escape(&link(import, format)),
+                            escape(import)
+                        )
+                    })
+                    .collect();
+                body += &format!("<p>Imports: {}</p>\n", imports.join(", "));
+            }
+            for item in module.items.iter() {
+                body += &format!(
+                    "<section id=\"{}\">\n<h2><code>{}</code></h2>\n",
+                    item.name,
+                    escape(&item.signature)
+                );
+                if let Some(ref doc) = item.doc {
+                    for paragraph in doc.split("\n\n") {
+                        body += &format!("<p>{}</p>\n", escape(paragraph.trim()));
+                    }
+                }
+                body += "</section>\n";
+            }
+            html_page(&module.name, &body)
+        }
+        Format::Markdown => {
+            let mut text = format!("# Module `{}`\n\n", module.name);
+            if !module.imports.is_empty() {
+                let imports: Vec<String> = module
+                    .imports
+                    .iter()
+                    .map(|import| format!("[`{}`]({})", import, link(import, format)))
+                    .collect();
+                text += &format!("Imports: {}\n\n", imports.join(", "));
+            }
+            for item in module.items.iter() {
+                text += &format!("## `{}`\n\n", item.signature);
+                if let Some(ref doc) = item.doc {
+                    text += &format!("{}\n\n", doc);
+                }
+            }
+            text.pop();
+            text
+        }
+    }
+}
+
+pub fn render_index(modules: &[Module], format: Format) -> String {
+    match format {
+        Format::Html => {
+            let mut body = "<h1>Modules</h1>\n<ul>\n".to_string();
+            for module in modules.iter() {
+                body += &format!(
+                    "<li><a href=\"{}\"><code>{}</code></a></li>\n",
+                    escape(&link(&module.name, format)),
+                    escape(&module.name)
+                );
+            }
+            body += "</ul>\n";
+            html_page("index", &body)
+        }
+        Format::Markdown => {
+            let mut text = "# Modules\n\n".to_string();
+            for module in modules.iter() {
+                text += &format!(
+                    "- [`{}`]({})\n",
+                    module.name,
+                    link(&module.name, format)
+                );
+            }
+            text
+        }
+    }
+}
@@ -0,0 +1,447 @@
+use ast::*;
+use diagnostic::{code, Diagnostic, Span};
+use env::Env;
+use ident::Ident;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::Write;
+use std::path::Path;
+use std::rc::Rc;
+
+// the stack of the thread evaluating programs, which `MAX_DEPTH` nested evaluations fit in
+// even in a debug build
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+// deeper recursion is reported as a runtime error, instead of overflowing the stack
+const MAX_DEPTH: usize = 40_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i32),
+    Bool(bool),
+    Char(char),
+    Unit,
+    Record(BTreeMap<Ident, Value>),
+    Array(Vec<Value>),
+    Func(Rc<Closure>),
+}
+
+#[derive(Debug)]
+pub struct Closure {
+    name: Ident,
+    param_name: Ident,
+    param_type: Type,
+    body: Expr,
+    env: Env<Cell>,
+}
+
+// functions are equal only to themselves
+impl PartialEq for Closure {
+    fn eq(&self, other: &Closure) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// variables are mutable through `<-`, and copied when they are read
+type Cell = Rc<RefCell<Value>>;
+
+fn cell(v: Value) -> Cell {
+    Rc::new(RefCell::new(v))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuntimeError {
+    DivisionByZero { pos: Position },
+    IndexOutOfBounds { pos: Position, index: i32, len: usize },
+    NotComparable { pos: Position },
+    AssertionFailed { pos: Position },
+    // only the compiled program can call C functions
+    Extern { pos: Position, name: Ident },
+    StackOverflow { pos: Position },
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuntimeError::DivisionByZero { .. } => write!(f, "division by zero"),
+            RuntimeError::IndexOutOfBounds { index, len, .. } => write!(
+                f,
+                "index out of bounds: the length is {} but the index is {}",
+                len, index
+            ),
+            RuntimeError::NotComparable { .. } => {
+                write!(f, "only integers, booleans and characters can be compared")
+            }
+            RuntimeError::AssertionFailed { .. } => write!(f, "assertion failed"),
+            RuntimeError::Extern { ref name, .. } => write!(
+                f,
+                "the C function `{}` can not be called without compiling the program",
+                name
+            ),
+            RuntimeError::StackOverflow { .. } => {
+                write!(f, "stack overflow: the recursion is too deep")
+            }
+        }
+    }
+}
+
+impl RuntimeError {
+    pub fn pos(&self) -> Position {
+        match self {
+            RuntimeError::DivisionByZero { pos }
+            | RuntimeError::IndexOutOfBounds { pos, .. }
+            | RuntimeError::NotComparable { pos }
+            | RuntimeError::AssertionFailed { pos }
+            | RuntimeError::Extern { pos, .. }
+            | RuntimeError::StackOverflow { pos } => *pos,
+        }
+    }
+
+    // the diagnostic of the error which occurred in `file` whose source text is `src`
+    pub fn to_diagnostic(&self, file: &Path, src: &str) -> Diagnostic {
+        let msg = match self {
+            // shows the failing expression
+            RuntimeError::AssertionFailed { pos } => format!(
+                "{}: `{}`",
+                self,
+                src.get(pos.start..pos.end).unwrap_or("").trim()
+            ),
+            _ => format!("{}", self),
+        };
+        Diagnostic::error(
+            code::RUNTIME_ERROR,
+            msg,
+            Some(Span::new(file, src, self.pos())),
+        )
+    }
+}
+
+// runs a typed program like its compiled executable, which returns the value of the program
+// as the exit status and prints to `out`
+pub fn run<W: Write + Send + 'static>(e: Expr, out: W) -> (Result<i32, RuntimeError>, W) {
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || {
+            let mut interp = Interpreter::new(out);
+            let status = interp.eval_toplevel(&e).map(|v| match v {
+                Some(Value::Int(n)) => n & 0xff,
+                Some(Value::Bool(b)) => b as i32,
+                Some(Value::Char(c)) => c as i32 & 0xff,
+                _ => 0,
+            });
+            (status, interp.out)
+        })
+        .expect("failed to spawn the interpreter")
+        .join()
+        .expect("interpreter panicked")
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Char(c) => write!(f, "'{}'", c),
+            Value::Unit => write!(f, "unit"),
+            Value::Record(ref fields) => {
+                let fields: Vec<_> = fields
+                    .iter()
+                    .map(|(label, v)| format!("{} = {}", label, v))
+                    .collect();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+            Value::Array(ref elems) => {
+                let elems: Vec<_> = elems.iter().map(|v| format!("{}", v)).collect();
+                write!(f, "[{}]", elems.join(", "))
+            }
+            Value::Func(_) => write!(f, "<func>"),
+        }
+    }
+}
+
+// a step from a variable to the part of it updated by `<-`
+enum Accessor {
+    Label(Ident),
+    Index(i32, Position),
+}
+
+pub struct Interpreter<W: Write> {
+    env: Env<Cell>,
+    pub out: W,
+    // the number of the expressions being evaluated
+    depth: usize,
+}
+
+impl<W: Write> Interpreter<W> {
+    pub fn new(out: W) -> Interpreter<W> {
+        Interpreter {
+            env: Env::new(),
+            out: out,
+            depth: 0,
+        }
+    }
+
+    // evaluates the top-level definitions of `e` and keeps them for later calls,
+    // returns the value of the final expression, or `None` when `e` has definitions only.
+    // nothing is kept when evaluation fails.
+    pub fn eval_toplevel(&mut self, e: &Expr) -> Result<Option<Value>, RuntimeError> {
+        let mut env = self.env.clone();
+        let mut e = e;
+        let result = loop {
+            e = match e {
+                Expr::Let(ref name, _, box ref e1, box ref left, _, _) => {
+                    let v = self.eval(e1, &env)?;
+                    if name != &Ident::new("<dummy-sequence>") {
+                        env = env.add(name.clone(), cell(v));
+                    }
+                    left
+                }
+                Expr::Func { ref name, .. } => {
+                    let (closure, left) = make_closure(e, &env);
+                    env = env.add(name.clone(), cell(closure));
+                    left
+                }
+                Expr::LetType(_, _, box ref left, _) => left,
+                Expr::EmptyMark => break None,
+                e => break Some(self.eval(e, &env)?),
+            };
+        };
+        self.env = env;
+        Ok(result)
+    }
+
+    pub fn eval(&mut self, e: &Expr, env: &Env<Cell>) -> Result<Value, RuntimeError> {
+        self.depth += 1;
+        let result = self.eval_expr(e, env);
+        self.depth -= 1;
+        result
+    }
+
+    fn eval_expr(&mut self, e: &Expr, env: &Env<Cell>) -> Result<Value, RuntimeError> {
+        match e {
+            Expr::Const(ref lit) => self.eval_lit(lit, env),
+            Expr::Var(ref name, _, _) => match env.lookup(name) {
+                Some(v) => Ok(v.borrow().clone()),
+                None => unreachable!(),
+            },
+            Expr::Func { ref name, .. } => {
+                let (closure, left) = make_closure(e, env);
+                self.eval(left, &env.add(name.clone(), cell(closure)))
+            }
+            Expr::Apply(box ref e1, box ref e2, ref pos) => {
+                let f = self.eval(e1, env)?;
+                let arg = self.eval(e2, env)?;
+                // only calls can nest without a limit
+                if self.depth >= MAX_DEPTH {
+                    return Err(RuntimeError::StackOverflow { pos: *pos });
+                }
+                self.apply(f, arg)
+            }
+            Expr::Let(ref name, _, box ref e1, box ref e2, _, _) => {
+                let v = self.eval(e1, env)?;
+                self.eval(e2, &env.add(name.clone(), cell(v)))
+            }
+            Expr::LetType(_, _, box ref e, _) => self.eval(e, env),
+            Expr::If(box ref cond, box ref e1, box ref e2, _) => match self.eval(cond, env)? {
+                Value::Bool(true) => self.eval(e1, env),
+                Value::Bool(false) => self.eval(e2, env),
+                _ => unreachable!(),
+            },
+            Expr::BinOp(op, box ref e1, box ref e2, ref pos) => {
+                let v1 = self.eval(e1, env)?;
+                let v2 = self.eval(e2, env)?;
+                eval_binop(*op, v1, v2, *pos)
+            }
+            Expr::RecordGet(box ref e, _, ref label, _) => match self.eval(e, env)? {
+                Value::Record(mut fields) => Ok(fields.remove(label).unwrap()),
+                _ => unreachable!(),
+            },
+            Expr::ArrayGet(box ref e1, box ref e2, ref pos) => {
+                let arr = self.eval(e1, env)?;
+                let idx = self.eval(e2, env)?;
+                match (arr, idx) {
+                    (Value::Array(mut elems), Value::Int(idx)) => {
+                        let len = elems.len();
+                        if idx < 0 || idx as usize >= len {
+                            Err(RuntimeError::IndexOutOfBounds {
+                                pos: *pos,
+                                index: idx,
+                                len: len,
+                            })
+                        } else {
+                            Ok(elems.swap_remove(idx as usize))
+                        }
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            Expr::Assign(box ref e1, box ref e2, _) => {
+                let (target, accessors) = self.place(e1, env)?;
+                let v = self.eval(e2, env)?;
+                let mut target = target.borrow_mut();
+                let mut place = &mut *target;
+                for accessor in accessors.iter() {
+                    place = match (place, accessor) {
+                        (Value::Record(ref mut fields), Accessor::Label(ref label)) => {
+                            fields.get_mut(label).unwrap()
+                        }
+                        (Value::Array(ref mut elems), Accessor::Index(idx, pos)) => {
+                            let len = elems.len();
+                            if *idx < 0 || *idx as usize >= len {
+                                return Err(RuntimeError::IndexOutOfBounds {
+                                    pos: *pos,
+                                    index: *idx,
+                                    len: len,
+                                });
+                            }
+                            &mut elems[*idx as usize]
+                        }
+                        _ => unreachable!(),
+                    };
+                }
+                *place = v.clone();
+                Ok(v)
+            }
+            Expr::Println(box ref e) => {
+                let v = self.eval(e, env)?;
+                // the compiled program prints every value as an integer
+                let text = match v {
+                    Value::Bool(b) => format!("{}", b as i32),
+                    Value::Char(c) => format!("{}", c as u32),
+                    Value::Unit => "0".to_string(),
+                    v => format!("{}", v),
+                };
+                writeln!(self.out, "{}", text).expect("failed to write output");
+                Ok(Value::Unit)
+            }
+            Expr::Extern(ref name, _, _, ref pos) => Err(RuntimeError::Extern {
+                pos: *pos,
+                name: name.clone(),
+            }),
+            Expr::Assert(box ref e, ref pos) => match self.eval(e, env)? {
+                Value::Bool(true) => Ok(Value::Unit),
+                _ => Err(RuntimeError::AssertionFailed { pos: *pos }),
+            },
+            Expr::EmptyMark => Ok(Value::Unit),
+        }
+    }
+
+    fn eval_lit(&mut self, lit: &Literal, env: &Env<Cell>) -> Result<Value, RuntimeError> {
+        match lit {
+            Literal::Number(n) => Ok(Value::Int(*n)),
+            Literal::Bool(b) => Ok(Value::Bool(*b)),
+            Literal::Char(c) => Ok(Value::Char(*c)),
+            Literal::Unit => Ok(Value::Unit),
+            Literal::Record(ref fields) => {
+                let mut values = BTreeMap::new();
+                for (label, e) in fields.iter() {
+                    values.insert(label.clone(), self.eval(e, env)?);
+                }
+                Ok(Value::Record(values))
+            }
+            Literal::Array(ref elems, _) => {
+                let mut values = vec![];
+                for e in elems.iter() {
+                    values.push(self.eval(e, env)?);
+                }
+                Ok(Value::Array(values))
+            }
+        }
+    }
+
+    pub fn apply(&mut self, f: Value, arg: Value) -> Result<Value, RuntimeError> {
+        let closure = match f {
+            Value::Func(closure) => closure,
+            _ => unreachable!(),
+        };
+        let env = closure
+            .env
+            .add(closure.name.clone(), cell(Value::Func(closure.clone())));
+        let env = if closure.param_name.is_omitted_param_name() {
+            match (&closure.param_type, arg) {
+                (Type::Record(_), Value::Record(fields)) => fields
+                    .into_iter()
+                    .fold(env, |acc, (label, v)| acc.add(label, cell(v))),
+                _ => unreachable!(),
+            }
+        } else {
+            env.add(closure.param_name.clone(), cell(arg))
+        };
+        self.eval(&closure.body, &env)
+    }
+
+    // the variable updated by assigning to `e`, and the path to the updated part.
+    // other expressions are evaluated into a temporary.
+    fn place(&mut self, e: &Expr, env: &Env<Cell>) -> Result<(Cell, Vec<Accessor>), RuntimeError> {
+        match e {
+            Expr::Var(ref name, _, _) => match env.lookup(name) {
+                Some(v) => Ok((v, vec![])),
+                None => unreachable!(),
+            },
+            Expr::RecordGet(box ref e, _, ref label, _) => {
+                let (target, mut accessors) = self.place(e, env)?;
+                accessors.push(Accessor::Label(label.clone()));
+                Ok((target, accessors))
+            }
+            Expr::ArrayGet(box ref e1, box ref e2, ref pos) => {
+                let (target, mut accessors) = self.place(e1, env)?;
+                match self.eval(e2, env)? {
+                    Value::Int(idx) => accessors.push(Accessor::Index(idx, *pos)),
+                    _ => unreachable!(),
+                }
+                Ok((target, accessors))
+            }
+            e => Ok((cell(self.eval(e, env)?), vec![])),
+        }
+    }
+}
+
+// a closure of the function `e` and the expression in its scope
+fn make_closure<'a>(e: &'a Expr, env: &Env<Cell>) -> (Value, &'a Expr) {
+    match e {
+        Expr::Func {
+            ref name,
+            ref param_name,
+            ref param_type,
+            box ref body,
+            box ref left,
+            ..
+        } => (
+            Value::Func(Rc::new(Closure {
+                name: name.clone(),
+                param_name: param_name.clone(),
+                param_type: param_type.clone(),
+                body: body.clone(),
+                env: env.clone(),
+            })),
+            left,
+        ),
+        _ => unreachable!(),
+    }
+}
+
+fn eval_binop(op: BinOp, v1: Value, v2: Value, pos: Position) -> Result<Value, RuntimeError> {
+    match (op, v1, v2) {
+        // integers wrap around on overflow as in the compiled program
+        (BinOp::Add, Value::Int(n1), Value::Int(n2)) => Ok(Value::Int(n1.wrapping_add(n2))),
+        (BinOp::Sub, Value::Int(n1), Value::Int(n2)) => Ok(Value::Int(n1.wrapping_sub(n2))),
+        (BinOp::Mult, Value::Int(n1), Value::Int(n2)) => Ok(Value::Int(n1.wrapping_mul(n2))),
+        (BinOp::Div, Value::Int(_), Value::Int(0)) => Err(RuntimeError::DivisionByZero { pos: pos }),
+        (BinOp::Div, Value::Int(n1), Value::Int(n2)) => Ok(Value::Int(n1.wrapping_div(n2))),
+        (BinOp::Eq, v1, v2) => Ok(Value::Bool(v1 == v2)),
+        (BinOp::Neq, v1, v2) => Ok(Value::Bool(v1 != v2)),
+        (op, v1, v2) => {
+            let ordering = match (v1, v2) {
+                (Value::Int(n1), Value::Int(n2)) => n1.cmp(&n2),
+                (Value::Bool(b1), Value::Bool(b2)) => b1.cmp(&b2),
+                (Value::Char(c1), Value::Char(c2)) => c1.cmp(&c2),
+                _ => return Err(RuntimeError::NotComparable { pos: pos }),
+            };
+            match op {
+                BinOp::Lt => Ok(Value::Bool(ordering == std::cmp::Ordering::Less)),
+                BinOp::Gt => Ok(Value::Bool(ordering == std::cmp::Ordering::Greater)),
+                _ => unreachable!(),
+            }
+        }
+    }
+}
@@ -0,0 +1,575 @@
+use lexer::{self, Token, TokenKind, Trivia};
+use parser;
+
+const INDENT: &str = "    ";
+
+// formats a program which is already known to be syntactically correct
+pub fn format(src: &str) -> Result<String, String> {
+    let tokens =
+        lexer::tokenize(src).map_err(|pos| format!("unknown character at byte {}", pos))?;
+    let expected = essence(&tokens);
+    let mut formatter = Formatter {
+        src: src,
+        tokens: tokens,
+        idx: 0,
+        indent: 0,
+        out: String::new(),
+    };
+    formatter.program();
+    let out = formatter.out;
+
+    // formatting must only change whitespaces, commas and the placement of comments
+    let formatted_tokens =
+        lexer::tokenize(&out).map_err(|pos| format!("unknown character at byte {}", pos))?;
+    if expected != essence(&formatted_tokens) || parser::program(&out).is_err() {
+        return Err("formatting changed the meaning of the program".to_string());
+    }
+    Ok(out)
+}
+
+fn essence(tokens: &Vec<Token>) -> Vec<String> {
+    tokens
+        .iter()
+        .flat_map(|token| {
+            token
+                .leading
+                .iter()
+                .filter(|trivia| trivia.is_comment())
+                .map(|trivia| trivia.text().trim_end().to_string())
+                .chain(std::iter::once(token.text.clone()))
+        })
+        .filter(|text| text.as_str() != ",")
+        .collect()
+}
+
+struct Formatter<'a> {
+    src: &'a str,
+    tokens: Vec<Token>,
+    idx: usize,
+    indent: usize,
+    out: String,
+}
+
+impl<'a> Formatter<'a> {
+    fn peek(&self) -> &str {
+        self.peek_nth(0)
+    }
+
+    fn peek_nth(&self, n: usize) -> &str {
+        self.tokens
+            .get(self.idx + n)
+            .map_or("", |token| token.text.as_str())
+    }
+
+    fn peek_kind(&self, n: usize) -> TokenKind {
+        self.tokens
+            .get(self.idx + n)
+            .map_or(TokenKind::Eof, |token| token.kind)
+    }
+
+    fn at_line_start(&self) -> bool {
+        self.out.is_empty() || self.out.ends_with('\n')
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.at_line_start() && !text.is_empty() {
+            for _ in 0..self.indent {
+                self.out += INDENT;
+            }
+        }
+        self.out += text;
+    }
+
+    fn space(&mut self) {
+        if !self.at_line_start() && !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+    }
+
+    fn newline(&mut self) {
+        // comments on the same line as the previous token stay there
+        let token = &mut self.tokens[self.idx];
+        let mut trailings = vec![];
+        while let Some(trivia) = token.leading.first().cloned() {
+            match trivia {
+                Trivia::Whitespace(ref text) if text.contains('\n') => break,
+                Trivia::Whitespace(_) => (),
+                comment => trailings.push(comment),
+            }
+            token.leading.remove(0);
+        }
+        for comment in trailings {
+            self.space();
+            self.write(comment.text());
+        }
+        while self.out.ends_with(' ') {
+            self.out.pop();
+        }
+        if !self.at_line_start() {
+            self.out.push('\n');
+        }
+    }
+
+    fn blank_line(&mut self) {
+        let after_open =
+            self.out.ends_with("{\n") || self.out.ends_with("[\n") || self.out.ends_with("(\n");
+        if self.at_line_start()
+            && !self.out.is_empty()
+            && !self.out.ends_with("\n\n")
+            && !after_open
+        {
+            self.out.push('\n');
+        }
+    }
+
+    // emits the next token with the comments in front of it
+    fn token(&mut self) {
+        let token = self.tokens[self.idx].clone();
+        self.idx += 1;
+        let mut newlines = 0;
+        for trivia in token.leading.iter() {
+            match trivia {
+                Trivia::Whitespace(ref text) => newlines += text.matches('\n').count(),
+                Trivia::LineComment(ref text) => {
+                    if self.at_line_start() && newlines >= 2 {
+                        self.blank_line();
+                    }
+                    self.space();
+                    self.write(text);
+                    self.out.push('\n');
+                    newlines = 0;
+                }
+                Trivia::BlockComment(ref text) => {
+                    if self.at_line_start() && newlines >= 2 {
+                        self.blank_line();
+                    }
+                    let own_line = self.at_line_start();
+                    self.space();
+                    self.write(text.trim_end());
+                    if own_line {
+                        self.out.push('\n');
+                    } else {
+                        self.out.push(' ');
+                    }
+                    newlines = 0;
+                }
+            }
+        }
+        let is_close = token.text == "}" || token.text == "]" || token.text == ")";
+        if self.at_line_start() && newlines >= 2 && !is_close {
+            self.blank_line();
+        }
+        self.write(&token.text);
+    }
+
+    fn expect(&mut self, text: &str) {
+        if self.peek() == text {
+            self.token();
+        }
+    }
+
+    fn matching_close(&self, open_idx: usize) -> usize {
+        let mut depth = 0;
+        for (idx, token) in self.tokens.iter().enumerate().skip(open_idx) {
+            match token.text.as_str() {
+                "{" | "[" | "(" => depth += 1,
+                "}" | "]" | ")" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return idx;
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.tokens.len() - 1
+    }
+
+    // the source text between the bracket at `open_idx` and its pair has a line break or a comment
+    fn is_multiline(&self, open_idx: usize) -> bool {
+        let close_idx = self.matching_close(open_idx);
+        let has_comment = self.tokens[open_idx + 1..close_idx + 1]
+            .iter()
+            .any(|token| token.leading.iter().any(|trivia| trivia.is_comment()));
+        let start = self.tokens[open_idx].pos.start;
+        has_comment || self.src[start..self.tokens[close_idx].pos.end].contains('\n')
+    }
+
+    fn program(&mut self) {
+        let mut has_import = false;
+        while self.peek() == "import" {
+            self.token();
+            self.space();
+            self.token();
+            while self.peek() == "." && self.peek_nth(1) != "{" {
+                self.token();
+                self.token();
+            }
+            if self.peek() == "as" {
+                self.space();
+                self.token();
+                self.space();
+                self.token();
+            } else if self.peek() == "." {
+                // e.g) import a.b.{x, y};
+                self.token();
+                self.token();
+                while self.peek_kind(0) == TokenKind::Ident {
+                    self.token();
+                    if self.peek() == "," {
+                        self.token();
+                        if self.peek() != "}" {
+                            self.space();
+                        } else {
+                            self.out.pop();
+                        }
+                    }
+                }
+                self.expect("}");
+            }
+            self.expect(";");
+            self.newline();
+            has_import = true;
+        }
+        if has_import && self.peek_kind(0) != TokenKind::Eof {
+            self.blank_line();
+        }
+        self.statements(true, true);
+        self.newline();
+        self.token(); // EOF
+        while self.out.ends_with("\n\n") {
+            self.out.pop();
+        }
+        if !self.at_line_start() {
+            self.out.push('\n');
+        }
+    }
+
+    fn is_func_decl(&self) -> bool {
+        self.peek() == "func"
+            && self.peek_kind(1) == TokenKind::Ident
+            && (self.peek_kind(2) == TokenKind::Ident || self.peek_nth(2) == "{")
+    }
+
+    fn is_sequence_end(&self) -> bool {
+        match self.peek() {
+            "}" | "]" | ")" | "," => true,
+            _ => self.peek_kind(0) == TokenKind::Eof,
+        }
+    }
+
+    // a sequence of `let`, `type` and `func` definitions and expressions separated by `;`
+    fn statements(&mut self, multiline: bool, toplevel: bool) {
+        let mut first = true;
+        let mut after_func_decl = false;
+        while !self.is_sequence_end() {
+            if !first {
+                if multiline {
+                    self.newline();
+                    if after_func_decl {
+                        self.blank_line();
+                    }
+                } else {
+                    self.space();
+                }
+            }
+            first = false;
+            after_func_decl = false;
+            if toplevel && self.peek() == "pub" {
+                self.token();
+                self.space();
+            }
+            if toplevel && self.is_func_decl() {
+                self.func_decl();
+                after_func_decl = true;
+                continue;
+            }
+            if toplevel && self.peek() == "test" {
+                self.test_decl();
+                after_func_decl = true;
+                continue;
+            }
+            match self.peek() {
+                "let" => {
+                    self.token();
+                    self.space();
+                    self.token();
+                    if self.peek() == ":" {
+                        self.token();
+                        self.space();
+                        self.type_();
+                    }
+                    self.space();
+                    self.token(); // =
+                    self.space();
+                    if self.peek() == "let" || self.peek() == "type" {
+                        // e.g) let a = let b = 1; b; a
+                        first = true;
+                        continue;
+                    }
+                    self.inner_expr();
+                }
+                "extern" => {
+                    // e.g) extern func putchar c: Int :Int;
+                    self.token();
+                    self.space();
+                    self.token(); // func
+                    self.space();
+                    self.token(); // name
+                    self.space();
+                    self.func_param();
+                }
+                "type" => {
+                    self.token();
+                    self.space();
+                    self.token();
+                    self.space();
+                    self.token(); // =
+                    self.space();
+                    self.type_();
+                }
+                _ => self.inner_expr(),
+            }
+            if self.peek() == ";" {
+                self.token();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn block(&mut self, multiline: bool) {
+        self.token(); // {
+        if self.peek() == "}" {
+            self.token();
+        } else if multiline {
+            self.indent += 1;
+            self.newline();
+            self.statements(true, false);
+            self.indent -= 1;
+            self.newline();
+            self.token(); // }
+        } else {
+            self.space();
+            self.statements(false, false);
+            self.space();
+            self.token(); // }
+        }
+    }
+
+    fn func_decl(&mut self) {
+        self.token(); // func
+        self.space();
+        self.token(); // name
+        self.space();
+        self.func_param();
+        self.space();
+        self.block(true);
+    }
+
+    fn test_decl(&mut self) {
+        self.token(); // test
+        self.space();
+        self.token(); // name
+        self.space();
+        self.block(true);
+    }
+
+    // e.g) `x: Int :Int` or `{x: Int, y: Int} :Int`
+    fn func_param(&mut self) {
+        if self.peek() == "{" {
+            self.record_type();
+        } else {
+            self.token();
+            self.token(); // :
+            self.space();
+            self.type_();
+        }
+        if self.peek() == ":" {
+            self.space();
+            self.token();
+            self.type_();
+        }
+    }
+
+    fn inner_expr(&mut self) {
+        if self.peek() == "if" {
+            self.token();
+            self.space();
+            self.statements(false, false);
+            self.space();
+            // `{ e1 } else { e2 }`
+            let else_idx = self.matching_close(self.idx) + 2;
+            let multiline = self.is_multiline(self.idx)
+                || (else_idx < self.tokens.len() && self.is_multiline(else_idx));
+            self.block(multiline);
+            self.space();
+            self.expect("else");
+            self.space();
+            self.block(multiline);
+        } else {
+            self.binop_expr();
+        }
+    }
+
+    fn binop_expr(&mut self) {
+        self.apply_expr();
+        while let "==" | "/=" | "<" | ">" | "+" | "-" | "*" | "/" | "<-" = self.peek() {
+            self.space();
+            self.token();
+            self.space();
+            self.apply_expr();
+        }
+    }
+
+    fn is_record_start(&self) -> bool {
+        self.peek() == "{"
+            && (self.peek_nth(1) == "}"
+                || (self.peek_kind(1) == TokenKind::Ident && self.peek_nth(2) == "="))
+    }
+
+    fn is_factor_start(&self) -> bool {
+        match self.peek_kind(0) {
+            TokenKind::Ident | TokenKind::Number | TokenKind::Char => true,
+            TokenKind::Keyword => match self.peek() {
+                "func" | "true" | "false" | "unit" | "println" | "assert" => true,
+                _ => false,
+            },
+            TokenKind::Symbol => self.peek() == "(" || self.peek() == "[" || self.is_record_start(),
+            TokenKind::Str | TokenKind::Eof => false,
+        }
+    }
+
+    fn apply_expr(&mut self) {
+        self.postfix_expr();
+        while self.is_factor_start() {
+            self.space();
+            self.postfix_expr();
+        }
+    }
+
+    fn postfix_expr(&mut self) {
+        self.factor_expr();
+        loop {
+            match self.peek() {
+                "." => {
+                    self.token();
+                    self.token();
+                }
+                "[" => {
+                    self.token();
+                    self.statements(false, false);
+                    self.expect("]");
+                }
+                _ => break,
+            }
+        }
+    }
+
+    fn factor_expr(&mut self) {
+        match self.peek() {
+            "func" => {
+                self.token();
+                self.space();
+                self.func_param();
+                self.space();
+                self.expect("=>");
+                self.space();
+                self.statements(false, false);
+            }
+            "{" => self.elements("}", |formatter| {
+                formatter.token(); // label
+                formatter.space();
+                formatter.token(); // =
+                formatter.space();
+                formatter.statements(false, false);
+            }),
+            "[" => self.elements("]", |formatter| formatter.statements(false, false)),
+            "(" => {
+                self.token();
+                self.statements(false, false);
+                while self.peek() == "," {
+                    self.token();
+                    self.space();
+                    self.statements(false, false);
+                }
+                self.expect(")");
+            }
+            "println" | "assert" => {
+                self.token();
+                self.space();
+                self.inner_expr();
+            }
+            _ => self.token(),
+        }
+    }
+
+    // records and arrays, whose elements are separated by optional commas
+    fn elements<F: Fn(&mut Formatter<'a>)>(&mut self, close: &str, element: F) {
+        let multiline = self.is_multiline(self.idx);
+        self.token();
+        if multiline {
+            self.indent += 1;
+        }
+        while self.peek() != close && self.peek_kind(0) != TokenKind::Eof {
+            if multiline {
+                self.newline();
+            }
+            element(self);
+            if self.peek() == "," {
+                self.token();
+            } else if multiline || self.peek() != close {
+                self.write(",");
+            }
+            if !multiline && self.peek() != close {
+                self.space();
+            }
+        }
+        if !multiline && self.out.ends_with(',') {
+            self.out.pop();
+        }
+        if multiline {
+            self.indent -= 1;
+            self.newline();
+        }
+        self.token();
+    }
+
+    fn type_(&mut self) {
+        self.primitive_type();
+        while self.peek() == "->" {
+            self.space();
+            self.token();
+            self.space();
+            self.primitive_type();
+        }
+    }
+
+    fn primitive_type(&mut self) {
+        match self.peek() {
+            "{" => self.record_type(),
+            "(" => {
+                self.token();
+                self.type_();
+                self.expect(")");
+            }
+            _ => {
+                self.token();
+                // e.g) geometry.Point
+                while self.peek() == "." {
+                    self.token();
+                    self.token();
+                }
+            }
+        }
+    }
+
+    fn record_type(&mut self) {
+        self.elements("}", |formatter| {
+            formatter.token(); // label
+            formatter.token(); // :
+            formatter.space();
+            formatter.type_();
+        })
+    }
+}
@@ -1,12 +1,13 @@
+use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 use std::sync::atomic::{AtomicUsize, Ordering::SeqCst};
 
 #[cfg(not(test))]
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Ident(String);
 
 #[cfg(test)]
-#[derive(Debug, Clone, PartialOrd, Ord, Hash)] // redefine `eq` for test
+#[derive(Debug, Clone, PartialOrd, Ord, Hash, Serialize, Deserialize)] // redefine `eq` for test
 pub struct Ident(String);
 
 #[cfg(test)]
@@ -34,6 +35,10 @@ impl Ident {
     pub fn fresh() -> Ident {
         Ident(format!("<fresh-{}>", COUNTER.fetch_add(1, SeqCst)))
     }
+    // whether `self` is made by `fresh`, e.g) the type of an unannotated variable
+    pub fn is_fresh(&self) -> bool {
+        self.0.starts_with("<fresh-")
+    }
     pub fn omitted_param_name() -> Ident {
         Ident::new("<omitted-param-name>")
     }
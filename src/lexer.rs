@@ -0,0 +1,158 @@
+use ast::Position;
+
+pub const KEYWORDS: &[&str] = &[
+    "type", "enum", "match", "let", "func", "if", "else", "Int", "Bool", "Char", "Unit", "true",
+    "false", "unit", "println", "import", "with", "test", "assert", "as", "pub", "extern",
+];
+
+// longer symbols first, so that e.g. `==` is not split into two `=`
+const SYMBOLS: &[&str] = &[
+    "::", "==", "/=", "->", "=>", "<-", "=", ",", ".", ":", ";", "+", "-", "*", "/", "(", ")", "{",
+    "}", "[", "]", "<", ">",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trivia {
+    Whitespace(String),
+    LineComment(String),
+    BlockComment(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Ident,
+    Keyword,
+    Number,
+    Char,
+    Str,
+    Symbol,
+    Eof,
+}
+
+// a token with the whitespaces and comments in front of it,
+// concatenating all of them restores the source text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub pos: Position,
+    pub leading: Vec<Trivia>,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_continue(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// byte length of the prefix of `s` whose chars satisfy `f`
+fn prefix_len<F: Fn(char) -> bool>(s: &str, f: F) -> usize {
+    s.find(|c| !f(c)).unwrap_or(s.len())
+}
+
+fn lex_trivia(rest: &str) -> Option<Trivia> {
+    if rest.starts_with("//") {
+        let len = rest.find('\n').unwrap_or(rest.len());
+        Some(Trivia::LineComment(rest[..len].to_string()))
+    } else if rest.starts_with("/*") {
+        let len = rest[2..].find("*/").map_or(rest.len(), |idx| idx + 4);
+        Some(Trivia::BlockComment(rest[..len].to_string()))
+    } else {
+        let len = prefix_len(rest, |c| c == ' ' || c == '\t' || c == '\r' || c == '\n');
+        if len == 0 {
+            None
+        } else {
+            Some(Trivia::Whitespace(rest[..len].to_string()))
+        }
+    }
+}
+
+fn lex_token(rest: &str) -> Option<(TokenKind, usize)> {
+    let c = rest.chars().next()?;
+    if is_ident_start(c) {
+        let len = prefix_len(rest, is_ident_continue);
+        let kind = if KEYWORDS.contains(&&rest[..len]) {
+            TokenKind::Keyword
+        } else {
+            TokenKind::Ident
+        };
+        Some((kind, len))
+    } else if c.is_ascii_digit() {
+        Some((TokenKind::Number, prefix_len(rest, |c| c.is_ascii_digit())))
+    } else if c == '\'' {
+        let mut chars = rest.char_indices().skip(1);
+        match (chars.next(), chars.next()) {
+            (Some(_), Some((idx, '\''))) => Some((TokenKind::Char, idx + 1)),
+            _ => None,
+        }
+    } else if c == '"' {
+        rest[1..]
+            .find(|c| c == '"' || c == '\n')
+            .filter(|idx| rest[1 + idx..].starts_with('"'))
+            .map(|idx| (TokenKind::Str, idx + 2))
+    } else {
+        SYMBOLS
+            .iter()
+            .find(|symbol| rest.starts_with(*symbol))
+            .map(|symbol| (TokenKind::Symbol, symbol.len()))
+    }
+}
+
+impl Trivia {
+    pub fn text(&self) -> &str {
+        match self {
+            Trivia::Whitespace(ref text)
+            | Trivia::LineComment(ref text)
+            | Trivia::BlockComment(ref text) => text,
+        }
+    }
+
+    pub fn is_comment(&self) -> bool {
+        match self {
+            Trivia::Whitespace(_) => false,
+            _ => true,
+        }
+    }
+}
+
+// the last token is always `TokenKind::Eof`, which holds the trivia at the end of the file.
+// returns the position of the unknown character on error.
+pub fn tokenize(src: &str) -> Result<Vec<Token>, usize> {
+    let mut tokens = vec![];
+    let mut leading = vec![];
+    let mut pos = 0;
+    loop {
+        let rest = &src[pos..];
+        if let Some(trivia) = lex_trivia(rest) {
+            pos += trivia.text().len();
+            leading.push(trivia);
+        } else if rest.is_empty() {
+            tokens.push(Token {
+                kind: TokenKind::Eof,
+                text: String::new(),
+                pos: Position {
+                    start: pos,
+                    end: pos,
+                },
+                leading: leading,
+            });
+            return Ok(tokens);
+        } else if let Some((kind, len)) = lex_token(rest) {
+            tokens.push(Token {
+                kind: kind,
+                text: rest[..len].to_string(),
+                pos: Position {
+                    start: pos,
+                    end: pos + len,
+                },
+                leading: leading,
+            });
+            leading = vec![];
+            pos += len;
+        } else {
+            return Err(pos);
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use ast::*;
+use config::LintConfig;
+use diagnostic::{code, code::Code, Diagnostic, Span};
+use env::Env;
+use ident::Ident;
+use std::collections::HashSet;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lint {
+    UnusedVariable,
+    UnusedImport,
+    Shadowing,
+    UnusedValue,
+    UnreachableCode,
+}
+
+pub const LINTS: &[Lint] = &[
+    Lint::UnusedVariable,
+    Lint::UnusedImport,
+    Lint::Shadowing,
+    Lint::UnusedValue,
+    Lint::UnreachableCode,
+];
+
+impl Lint {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused-variable",
+            Lint::UnusedImport => "unused-import",
+            Lint::Shadowing => "shadowing",
+            Lint::UnusedValue => "unused-value",
+            Lint::UnreachableCode => "unreachable-code",
+        }
+    }
+
+    pub fn code(&self) -> Code {
+        match self {
+            Lint::UnusedVariable => code::UNUSED_VARIABLE,
+            Lint::UnusedImport => code::UNUSED_IMPORT,
+            Lint::Shadowing => code::SHADOWING,
+            Lint::UnusedValue => code::UNUSED_VALUE,
+            Lint::UnreachableCode => code::UNREACHABLE_CODE,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        LINTS.iter().find(|lint| lint.name() == name).cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl LintConfig {
+    pub fn level(&self, lint: Lint) -> Level {
+        if self.deny.iter().any(|name| name == lint.name()) {
+            Level::Deny
+        } else if self.allow.iter().any(|name| name == lint.name()) {
+            Level::Allow
+        } else {
+            Level::Warn
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub lint: Lint,
+    pub pos: Position,
+    pub message: String,
+    pub previous: Option<Position>,
+}
+
+impl Warning {
+    fn new(lint: Lint, pos: Position, message: String) -> Warning {
+        Warning {
+            lint: lint,
+            pos: pos,
+            message: message,
+            previous: None,
+        }
+    }
+}
+
+pub fn to_diagnostics(
+    file: &Path,
+    src: &str,
+    warnings: Vec<Warning>,
+    config: &LintConfig,
+) -> Vec<Diagnostic> {
+    warnings
+        .into_iter()
+        .filter_map(|warning| {
+            let span = Some(Span::new(file, src, warning.pos));
+            let diag = match config.level(warning.lint) {
+                Level::Allow => return None,
+                Level::Warn => Diagnostic::warning(warning.lint.code(), warning.message, span),
+                Level::Deny => Diagnostic::error(warning.lint.code(), warning.message, span),
+            };
+            let diag = match warning.previous {
+                Some(pos) => diag.with_related(
+                    "previous definition is here".to_string(),
+                    Span::new(file, src, pos),
+                ),
+                None => diag,
+            };
+            Some(diag.with_note(format!(
+                "lint `{}` can be allowed or denied in `[lint]` of mumrik-conf.toml",
+                warning.lint.name()
+            )))
+        })
+        .collect()
+}
+
+// names given by the compiler or starting with `_` are never reported
+fn is_user_name(name: &Ident) -> bool {
+    let name = format!("{}", name);
+    !name.starts_with('_') && !name.starts_with('<')
+}
+
+pub fn check_import(import: &Import, defined_names: &Vec<Ident>, e: &Expr) -> Option<Warning> {
+    let used = e.free_term_vars();
+    if defined_names.iter().any(|name| used.contains_key(name)) {
+        None
+    } else {
+        Some(Warning::new(
+            Lint::UnusedImport,
+            import.pos,
+            format!("unused import: {}", import.module_name),
+        ))
+    }
+}
+
+// `e` is a typed program whose outermost `imported_num` definitions are spliced from modules
+pub fn check_program(e: &Expr, imported_num: usize) -> Vec<Warning> {
+    let mut warnings = vec![];
+    let mut env = Env::new();
+    let mut e = e;
+    for _ in 0..imported_num {
+        e = match e {
+            Expr::Let(ref name, _, _, box ref left, _, _) => {
+                env = env.add(name.clone(), None);
+                left
+            }
+            Expr::Func {
+                ref name,
+                box ref left,
+                ..
+            } => {
+                env = env.add(name.clone(), None);
+                left
+            }
+            Expr::LetType(_, _, box ref left, _) => left,
+            _ => unreachable!(),
+        };
+    }
+    let exported = is_library(e);
+    lint_expr(e, &env, exported, &mut warnings);
+    // a binding is reported after its scope is walked, so they are put back in the order of the source
+    warnings.sort_by_key(|warning| warning.pos.start);
+    warnings
+}
+
+// top-level definitions of a module without a final expression are exported
+fn is_library(e: &Expr) -> bool {
+    match e {
+        Expr::Func { box ref left, .. }
+        | Expr::Let(_, _, _, box ref left, _, _)
+        | Expr::LetType(_, _, box ref left, _) => is_library(left),
+        Expr::EmptyMark => true,
+        _ => false,
+    }
+}
+
+fn check_shadowing(
+    name: &Ident,
+    pos: Position,
+    env: &Env<Option<Position>>,
+    warnings: &mut Vec<Warning>,
+) {
+    if !is_user_name(name) {
+        return;
+    }
+    if let Some(previous) = env.lookup(name) {
+        warnings.push(Warning {
+            previous: previous,
+            ..Warning::new(
+                Lint::Shadowing,
+                pos,
+                format!("`{}` shadows a previous definition", name),
+            )
+        });
+    }
+}
+
+fn check_unused(
+    name: &Ident,
+    pos: Position,
+    used: &HashSet<Ident>,
+    kind: &str,
+    warnings: &mut Vec<Warning>,
+) {
+    if is_user_name(name) && !used.contains(name) {
+        warnings.push(Warning::new(
+            Lint::UnusedVariable,
+            pos,
+            format!("unused {}: `{}`", kind, name),
+        ));
+    }
+}
+
+// lints `e` and returns its free variables, so that each scope is walked once
+fn lint_expr(
+    e: &Expr,
+    env: &Env<Option<Position>>,
+    exported: bool,
+    warnings: &mut Vec<Warning>,
+) -> HashSet<Ident> {
+    match e {
+        Expr::Const(Literal::Record(ref fields)) => {
+            let mut used = HashSet::new();
+            for (_, ref e) in fields.iter() {
+                used.extend(lint_expr(e, env, false, warnings));
+            }
+            used
+        }
+        Expr::Const(Literal::Array(ref elems, _)) | Expr::Extern(_, ref elems, _, _) => {
+            let mut used = HashSet::new();
+            for e in elems.iter() {
+                used.extend(lint_expr(e, env, false, warnings));
+            }
+            used
+        }
+        Expr::Var(ref name, _, _) => {
+            let mut used = HashSet::new();
+            used.insert(name.clone());
+            used
+        }
+        Expr::Const(_) | Expr::EmptyMark => HashSet::new(),
+        Expr::Func {
+            ref name,
+            ref param_name,
+            ref param_type,
+            box ref body,
+            box ref left,
+            ref pos,
+            ..
+        } => {
+            check_shadowing(name, *pos, env, warnings);
+            let env = env.add(name.clone(), Some(*pos));
+            let params = if param_name.is_omitted_param_name() {
+                if let Type::Record(ref fields) = param_type {
+                    fields.keys().cloned().collect()
+                } else {
+                    unreachable!()
+                }
+            } else {
+                vec![param_name.clone()]
+            };
+            let body_env = params.iter().fold(env.clone(), |acc, param| {
+                check_shadowing(param, *pos, &acc, warnings);
+                acc.add(param.clone(), Some(*pos))
+            });
+            let mut used = lint_expr(body, &body_env, false, warnings);
+            for param in params.iter() {
+                check_unused(param, *pos, &used, "parameter", warnings);
+                used.remove(param);
+            }
+            let used_in_left = lint_expr(left, &env, exported, warnings);
+            if !exported {
+                check_unused(name, *pos, &used_in_left, "function", warnings);
+            }
+            used.extend(used_in_left);
+            used.remove(name);
+            used
+        }
+        Expr::Apply(box ref e1, box ref e2, _)
+        | Expr::BinOp(_, box ref e1, box ref e2, _)
+        | Expr::ArrayGet(box ref e1, box ref e2, _)
+        | Expr::Assign(box ref e1, box ref e2, _) => {
+            let mut used = lint_expr(e1, env, false, warnings);
+            used.extend(lint_expr(e2, env, false, warnings));
+            used
+        }
+        Expr::Let(ref name, ref typ, box ref e1, box ref e2, ref pos, _) => {
+            let mut used = lint_expr(e1, env, false, warnings);
+            if name == &Ident::new("<dummy-sequence>") {
+                match (typ, e1) {
+                    (Type::Unit, _) | (Type::Var(_), _) | (_, Expr::Assign(_, _, _)) => (),
+                    _ => warnings.push(Warning::new(
+                        Lint::UnusedValue,
+                        *pos,
+                        format!("unused value of type `{}`", typ),
+                    )),
+                }
+                used.extend(lint_expr(e2, env, exported, warnings));
+            } else {
+                check_shadowing(name, *pos, env, warnings);
+                let mut used_in_e2 =
+                    lint_expr(e2, &env.add(name.clone(), Some(*pos)), exported, warnings);
+                if !exported {
+                    check_unused(name, *pos, &used_in_e2, "variable", warnings);
+                }
+                used_in_e2.remove(name);
+                used.extend(used_in_e2);
+            }
+            used
+        }
+        Expr::LetType(_, _, box ref e, _) => lint_expr(e, env, exported, warnings),
+        Expr::If(box ref cond, box ref e1, box ref e2, ref pos) => {
+            // e.g) `if true { a } else { b }`, whose `else` branch is never evaluated
+            if let Expr::Const(Literal::Bool(cond)) = cond {
+                warnings.push(Warning::new(
+                    Lint::UnreachableCode,
+                    *pos,
+                    format!(
+                        "unreachable `{}` branch, the condition is always `{}`",
+                        if *cond { "else" } else { "then" },
+                        cond
+                    ),
+                ));
+            }
+            let mut used = lint_expr(cond, env, false, warnings);
+            used.extend(lint_expr(e1, env, false, warnings));
+            used.extend(lint_expr(e2, env, false, warnings));
+            used
+        }
+        Expr::RecordGet(box ref e, _, _, _)
+        | Expr::Println(box ref e)
+        | Expr::Assert(box ref e, _) => lint_expr(e, env, false, warnings),
+    }
+}
@@ -0,0 +1,463 @@
+use ast::Type;
+use command::build;
+use diagnostic::{Diagnostic, Severity};
+use lexer;
+use parser;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+pub mod analysis;
+use self::analysis::{Definition, Index};
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+
+struct Document {
+    src: String,
+    // the index of the last version which could be parsed,
+    // stale when the current version has a syntax error
+    index: Option<Index>,
+    stale: bool,
+}
+
+pub struct Server {
+    documents: HashMap<String, Document>,
+    shutdown: bool,
+}
+
+// the next message, `None` at the end of the input. a message with a malformed header is
+// skipped with an error, so that the server keeps running
+pub fn read_message<R: BufRead>(reader: &mut R) -> Option<Result<Value, String>> {
+    let mut content_length = None;
+    let mut malformed = None;
+    loop {
+        let mut line = vec![];
+        if reader.read_until(b'\n', &mut line).ok()? == 0 {
+            return None;
+        }
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if line.to_ascii_lowercase().starts_with("content-length:") {
+            match line[15..].trim().parse() {
+                Ok(len) => content_length = Some(len),
+                Err(_) => malformed = Some(format!("invalid header `{}`", line)),
+            }
+        } else if !line.contains(':') {
+            malformed = Some(format!("invalid header `{}`", line));
+        }
+    }
+    if let Some(err) = malformed {
+        return Some(Err(err));
+    }
+    let content_length = match content_length {
+        Some(len) => len,
+        None => return Some(Err("missing `Content-Length` header".to_string())),
+    };
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).ok()?;
+    Some(serde_json::from_slice(&body).map_err(|err| format!("{}", err)))
+}
+
+fn write_message(message: Value) {
+    let body = message.to_string();
+    let stdout = std::io::stdout();
+    let mut stdout = stdout.lock();
+    write!(stdout, "Content-Length: {}\r\n\r\n{}", body.len(), body)
+        .and_then(|_| stdout.flush())
+        .unwrap_or_else(|err| panic!("lsp: can not write to stdout: {}", err));
+}
+
+fn respond(id: Value, result: Value) {
+    write_message(json!({"jsonrpc": "2.0", "id": id, "result": result}));
+}
+
+fn respond_error(id: Value, code: i64, message: String) {
+    write_message(json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {"code": code, "message": message},
+    }));
+}
+
+fn notify(method: &str, params: Value) {
+    write_message(json!({"jsonrpc": "2.0", "method": method, "params": params}));
+}
+
+fn uri_to_path(uri: &str) -> PathBuf {
+    let path = if uri.starts_with("file://") {
+        &uri[7..]
+    } else {
+        uri
+    };
+    let mut bytes = vec![];
+    let mut iter = path.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex: Vec<u8> = iter.by_ref().take(2).collect();
+            match std::str::from_utf8(&hex)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(b) => bytes.push(b),
+                None => {
+                    bytes.push(b'%');
+                    bytes.extend(hex);
+                }
+            }
+        } else {
+            bytes.push(b);
+        }
+    }
+    PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn path_to_uri(path: &Path) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut uri = "file://".to_string();
+    for b in path.to_string_lossy().bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri += &format!("%{:02X}", b);
+        }
+    }
+    uri
+}
+
+// LSP positions count lines from 0 and columns in UTF-16 code units
+fn to_lsp_position(src: &str, offset: usize) -> Value {
+    let offset = std::cmp::min(offset, src.len());
+    let before = src.get(..offset).unwrap_or(src);
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+fn to_lsp_range(src: &str, start: usize, end: usize) -> Value {
+    json!({"start": to_lsp_position(src, start), "end": to_lsp_position(src, end)})
+}
+
+fn from_lsp_position(src: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+    let line_start = if line == 0 {
+        0
+    } else {
+        src.match_indices('\n')
+            .nth(line - 1)
+            .map_or(src.len(), |(idx, _)| idx + 1)
+    };
+    let mut units = 0;
+    for (idx, c) in src[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return line_start + idx;
+        }
+        units += c.len_utf16();
+    }
+    src.len()
+}
+
+fn read_source(path: &Path) -> String {
+    build::read_text(&path.to_path_buf()).unwrap_or_default()
+}
+
+fn analyze(path: &PathBuf, src: &str) -> (Vec<Diagnostic>, Option<Index>) {
+    let program = match parser::program(src) {
+        Ok(program) => program,
+        Err(err) => return (vec![Diagnostic::from_parse_error(path, src, err)], None),
+    };
+    let entry_modules = build::entry_modules(path);
+    let imports: Vec<_> = program
+        .imports
+        .iter()
+        .filter_map(|import| {
+            build::find_import(&entry_modules, import).map(|file| (import.clone(), file))
+        })
+        .collect();
+
+    let mut diags = vec![];
+    let index = match build::read_source(path, src, &entry_modules, &mut diags) {
+        Ok((expr, _, module)) => {
+            let (definitions, expr) = analysis::peel_imports(&expr, &module.files);
+            // only the definitions which the program can refer to, by the names it uses
+            let imported = module
+                .exports
+                .iter()
+                .filter_map(|(name, member)| {
+                    definitions
+                        .iter()
+                        .rev()
+                        .find(|definition| &definition.name == member)
+                        .map(|definition| (name.clone(), definition.clone()))
+                })
+                .collect();
+            Index::new(path, src, expr, true, imports, imported)
+        }
+        Err(diag) => {
+            diags.push(diag);
+            Index::new(path, src, &program.expr, false, imports, vec![])
+        }
+    };
+    (diags, Some(index))
+}
+
+fn to_lsp_diagnostic(path: &Path, src: &str, diag: &Diagnostic) -> Option<Value> {
+    let file = path.to_string_lossy();
+    let (range, message) = match diag.span {
+        Some(ref span) if span.file == file => (
+            to_lsp_range(src, span.byte_start, span.byte_end),
+            diag.message.clone(),
+        ),
+        // warnings of imported modules are reported when they are opened
+        _ if diag.severity == Severity::Warning => return None,
+        Some(ref span) => (
+            to_lsp_range(src, 0, 0),
+            format!(
+                "{}:{}:{}: {}",
+                span.file, span.line_start, span.column_start, diag.message
+            ),
+        ),
+        None => (to_lsp_range(src, 0, 0), diag.message.clone()),
+    };
+    let message = diag
+        .notes
+        .iter()
+        .fold(message, |acc, note| format!("{}\n{}", acc, note));
+    let related: Vec<_> = diag
+        .related
+        .iter()
+        .map(|related| {
+            let related_src = if related.span.file == file {
+                src.to_string()
+            } else {
+                read_source(Path::new(&related.span.file))
+            };
+            json!({
+                "location": {
+                    "uri": path_to_uri(Path::new(&related.span.file)),
+                    "range": to_lsp_range(&related_src, related.span.byte_start, related.span.byte_end),
+                },
+                "message": related.message,
+            })
+        })
+        .collect();
+    Some(json!({
+        "range": range,
+        "severity": match diag.severity {
+            Severity::Error => 1,
+            Severity::Warning => 2,
+        },
+        "code": diag.code,
+        "source": "mumrik",
+        "message": message,
+        "relatedInformation": related,
+    }))
+}
+
+fn definition_location(definition: &Definition, documents: &HashMap<String, Document>) -> Value {
+    let uri = path_to_uri(&definition.file);
+    let src = match documents.get(&uri) {
+        Some(document) => document.src.clone(),
+        None => read_source(&definition.file),
+    };
+    json!({
+        "uri": uri,
+        "range": to_lsp_range(&src, definition.pos.start, definition.pos.start),
+    })
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server {
+            documents: HashMap::new(),
+            shutdown: false,
+        }
+    }
+
+    pub fn serve(&mut self) {
+        let stdin = std::io::stdin();
+        let mut reader = stdin.lock();
+        while let Some(message) = read_message(&mut reader) {
+            match message {
+                Ok(message) => self.handle(message),
+                Err(err) => respond_error(Value::Null, PARSE_ERROR, err),
+            }
+        }
+    }
+
+    fn handle(&mut self, message: Value) {
+        let method = message["method"].as_str().unwrap_or("").to_string();
+        let params = &message["params"];
+        let result = match method.as_str() {
+            "initialize" => Some(json!({
+                "capabilities": {
+                    // the whole text is sent on every change
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "completionProvider": {"triggerCharacters": ["."]},
+                },
+                "serverInfo": {"name": "mumrik"},
+            })),
+            "shutdown" => {
+                self.shutdown = true;
+                Some(Value::Null)
+            }
+            "exit" => std::process::exit(if self.shutdown { 0 } else { 1 }),
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                self.update(
+                    document["uri"].as_str().unwrap_or(""),
+                    document["text"].as_str().unwrap_or("").to_string(),
+                );
+                None
+            }
+            "textDocument/didChange" => {
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+                if let Some(text) = text {
+                    self.update(
+                        params["textDocument"]["uri"].as_str().unwrap_or(""),
+                        text.to_string(),
+                    );
+                }
+                None
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                self.documents.remove(uri);
+                notify(
+                    "textDocument/publishDiagnostics",
+                    json!({"uri": uri, "diagnostics": []}),
+                );
+                None
+            }
+            "textDocument/hover" => Some(self.hover(params)),
+            "textDocument/definition" => Some(self.definition(params)),
+            "textDocument/completion" => Some(self.completion(params)),
+            _ => None,
+        };
+
+        // notifications do not have `id` and are never answered
+        let id = message["id"].clone();
+        if id.is_null() {
+            return;
+        }
+        match result {
+            Some(result) => respond(id, result),
+            None => respond_error(
+                id,
+                METHOD_NOT_FOUND,
+                format!("unsupported method: {}", method),
+            ),
+        }
+    }
+
+    fn update(&mut self, uri: &str, src: String) {
+        let path = uri_to_path(uri);
+        let (diags, index) = analyze(&path, &src);
+        let diagnostics: Vec<_> = diags
+            .iter()
+            .filter_map(|diag| to_lsp_diagnostic(&path, &src, diag))
+            .collect();
+        notify(
+            "textDocument/publishDiagnostics",
+            json!({"uri": uri, "diagnostics": diagnostics}),
+        );
+
+        let previous = self.documents.remove(uri).and_then(|document| document.index);
+        let stale = index.is_none();
+        self.documents.insert(
+            uri.to_string(),
+            Document {
+                src: src,
+                index: index.or(previous),
+                stale: stale,
+            },
+        );
+    }
+
+    // the document and the offset of the cursor in it
+    fn cursor(&self, params: &Value) -> Option<(&Document, usize)> {
+        let document = self
+            .documents
+            .get(params["textDocument"]["uri"].as_str()?)?;
+        let offset = from_lsp_position(&document.src, &params["position"]);
+        Some((document, offset))
+    }
+
+    fn hover(&self, params: &Value) -> Value {
+        let (document, offset) = match self.cursor(params) {
+            Some((document, offset)) if !document.stale => (document, offset),
+            _ => return Value::Null,
+        };
+        let symbol = document
+            .index
+            .as_ref()
+            .and_then(|index| index.symbol_at(offset));
+        match symbol {
+            Some(symbol) => match symbol.typ {
+                Some(ref typ) => json!({
+                    "contents": {
+                        "kind": "markdown",
+                        "value": format!("```mumrik\n{}: {}\n```", symbol.name, typ),
+                    },
+                    "range": to_lsp_range(&document.src, symbol.pos.start, symbol.pos.end),
+                }),
+                None => Value::Null,
+            },
+            None => Value::Null,
+        }
+    }
+
+    fn definition(&self, params: &Value) -> Value {
+        let (document, offset) = match self.cursor(params) {
+            Some((document, offset)) if !document.stale => (document, offset),
+            _ => return Value::Null,
+        };
+        let definition = document
+            .index
+            .as_ref()
+            .and_then(|index| index.symbol_at(offset))
+            .and_then(|symbol| symbol.definition.as_ref());
+        match definition {
+            Some(definition) => definition_location(definition, &self.documents),
+            None => Value::Null,
+        }
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let (document, offset) = match self.cursor(params) {
+            Some(cursor) => cursor,
+            None => return json!([]),
+        };
+        let mut items = vec![];
+        if let Some(ref index) = document.index {
+            for definition in index.visible_at(offset) {
+                let kind = match definition.typ {
+                    Some(Type::Func(_, _)) => 3,
+                    _ => 6,
+                };
+                items.push(json!({
+                    "label": format!("{}", definition.name),
+                    "kind": kind,
+                    "detail": definition.typ.as_ref().map(|typ| format!("{}", typ)),
+                }));
+            }
+        }
+        for keyword in lexer::KEYWORDS.iter() {
+            items.push(json!({"label": keyword, "kind": 14}));
+        }
+        json!(items)
+    }
+}
@@ -0,0 +1,382 @@
+use ast::*;
+use env::Env;
+use ident::Ident;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub name: Ident,
+    pub typ: Option<Type>,
+    pub file: PathBuf,
+    // position of the defined name, or of the whole definition in imported modules
+    pub pos: Position,
+}
+
+// an occurrence of a name in the analyzed file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: Ident,
+    pub pos: Position,
+    pub typ: Option<Type>,
+    pub definition: Option<Definition>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Index {
+    pub symbols: Vec<Symbol>,
+    // definitions with the range of the source where they are visible
+    pub scopes: Vec<(Position, Definition)>,
+}
+
+struct Indexer<'a> {
+    file: &'a Path,
+    src: &'a str,
+    // types of an untyped program are fresh variables, which are not worth showing
+    typed: bool,
+    index: Index,
+}
+
+fn contains(pos: &Position, offset: usize) -> bool {
+    pos.start <= offset && offset <= pos.end
+}
+
+fn is_user_name(name: &Ident) -> bool {
+    !format!("{}", name).starts_with('<')
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+// position of the first occurrence of the identifier `name` in `src` after `from`
+fn find_name(src: &str, from: usize, name: &Ident) -> Option<Position> {
+    let name = format!("{}", name);
+    let mut from = std::cmp::min(from, src.len());
+    while let Some(idx) = src[from..].find(name.as_str()) {
+        let start = from + idx;
+        let end = start + name.len();
+        let before = src[..start].chars().next_back();
+        let after = src[end..].chars().next();
+        if !before.map_or(false, is_ident_char) && !after.map_or(false, is_ident_char) {
+            return Some(Position {
+                start: start,
+                end: end,
+            });
+        }
+        from = end;
+    }
+    None
+}
+
+fn union(pos1: Option<Position>, pos2: Option<Position>) -> Option<Position> {
+    match (pos1, pos2) {
+        (Some(pos1), Some(pos2)) => Some(Position {
+            start: std::cmp::min(pos1.start, pos2.start),
+            end: std::cmp::max(pos1.end, pos2.end),
+        }),
+        (pos1, None) => pos1,
+        (None, pos2) => pos2,
+    }
+}
+
+// the range of the source covered by `e`
+fn extent(e: &Expr) -> Option<Position> {
+    match e {
+        Expr::Const(Literal::Record(ref fields)) => fields
+            .values()
+            .fold(None, |acc, e| union(acc, extent(e))),
+        Expr::Const(Literal::Array(ref elems, _)) => {
+            elems.iter().fold(None, |acc, e| union(acc, extent(e)))
+        }
+        Expr::Const(_) | Expr::EmptyMark => None,
+        Expr::Var(_, _, pos) => Some(*pos),
+        Expr::Func {
+            box ref body,
+            box ref left,
+            ref pos,
+            ..
+        } => union(Some(*pos), union(extent(body), extent(left))),
+        Expr::Let(_, _, box ref e1, box ref e2, ref pos, _)
+        | Expr::Apply(box ref e1, box ref e2, ref pos)
+        | Expr::BinOp(_, box ref e1, box ref e2, ref pos)
+        | Expr::ArrayGet(box ref e1, box ref e2, ref pos)
+        | Expr::Assign(box ref e1, box ref e2, ref pos) => {
+            union(Some(*pos), union(extent(e1), extent(e2)))
+        }
+        Expr::If(box ref cond, box ref e1, box ref e2, ref pos) => union(
+            Some(*pos),
+            union(extent(cond), union(extent(e1), extent(e2))),
+        ),
+        Expr::RecordGet(box ref e, _, _, ref pos) => union(Some(*pos), extent(e)),
+        Expr::LetType(_, _, box ref e, _) | Expr::Println(box ref e) => extent(e),
+        Expr::Assert(box ref e, ref pos) => union(Some(*pos), extent(e)),
+        Expr::Extern(_, _, _, ref pos) => Some(*pos),
+    }
+}
+
+const WHOLE_FILE: Position = Position {
+    start: 0,
+    end: std::usize::MAX,
+};
+
+// strips the outermost definitions, which `build::read_source` splices from the `imported` file
+// of each, and returns them with the program of the file itself
+pub fn peel_imports<'a>(e: &'a Expr, imported: &Vec<PathBuf>) -> (Vec<Definition>, &'a Expr) {
+    let mut definitions = vec![];
+    let mut e = e;
+    for file in imported.iter() {
+        e = match e {
+            Expr::Let(ref name, ref typ, _, box ref left, ref pos, _) => {
+                definitions.push(Definition {
+                    name: name.clone(),
+                    typ: Some(typ.clone()),
+                    file: file.clone(),
+                    pos: *pos,
+                });
+                left
+            }
+            Expr::Func {
+                ref name,
+                ref param_type,
+                ref ret_type,
+                box ref left,
+                ref pos,
+                ..
+            } => {
+                definitions.push(Definition {
+                    name: name.clone(),
+                    typ: Some(Type::Func(box param_type.clone(), box ret_type.clone())),
+                    file: file.clone(),
+                    pos: *pos,
+                });
+                left
+            }
+            Expr::LetType(_, _, box ref left, _) => left,
+            _ => unreachable!(),
+        };
+    }
+    (definitions, e)
+}
+
+impl Index {
+    // `e` is the program of `file` without imported definitions, which are given by `imported`
+    // with the names the program refers to them by.
+    // `imports` are the positions of the import declarations and the files they refer to.
+    pub fn new(
+        file: &Path,
+        src: &str,
+        e: &Expr,
+        typed: bool,
+        imports: Vec<(Import, PathBuf)>,
+        imported: Vec<(Ident, Definition)>,
+    ) -> Index {
+        let mut indexer = Indexer {
+            file: file,
+            src: src,
+            typed: typed,
+            index: Index {
+                symbols: vec![],
+                scopes: vec![],
+            },
+        };
+        for (import, path) in imports {
+            indexer.index.symbols.push(Symbol {
+                name: import.module_name.clone(),
+                pos: import.pos,
+                typ: None,
+                definition: Some(Definition {
+                    name: import.module_name,
+                    typ: None,
+                    file: path,
+                    pos: Position::dummy(),
+                }),
+            });
+        }
+        let env = imported.into_iter().fold(Env::new(), |env, (name, definition)| {
+            let visible = Definition {
+                name: name,
+                ..definition.clone()
+            };
+            indexer.index.scopes.push((WHOLE_FILE, visible));
+            env.add(definition.name.clone(), definition)
+        });
+        indexer.walk(e, &env);
+        indexer.index
+    }
+
+    // the innermost symbol at `offset`
+    pub fn symbol_at(&self, offset: usize) -> Option<&Symbol> {
+        self.symbols
+            .iter()
+            .filter(|symbol| contains(&symbol.pos, offset))
+            .min_by_key(|symbol| symbol.pos.end - symbol.pos.start)
+    }
+
+    // definitions visible at `offset`, the innermost one first for each name
+    pub fn visible_at(&self, offset: usize) -> Vec<&Definition> {
+        let mut visible: Vec<&Definition> = vec![];
+        for (_, definition) in self
+            .scopes
+            .iter()
+            .rev()
+            .filter(|(scope, _)| contains(scope, offset))
+        {
+            if visible.iter().all(|def| def.name != definition.name) {
+                visible.push(definition);
+            }
+        }
+        visible
+    }
+}
+
+impl<'a> Indexer<'a> {
+    fn typ(&self, typ: &Type) -> Option<Type> {
+        if self.typed {
+            Some(typ.clone())
+        } else {
+            None
+        }
+    }
+
+    fn define(
+        &mut self,
+        name: &Ident,
+        typ: Type,
+        from: usize,
+        scope: Option<Position>,
+        env: &Env<Definition>,
+    ) -> Env<Definition> {
+        let pos = match find_name(self.src, from, name) {
+            Some(pos) if is_user_name(name) => pos,
+            _ => return env.clone(),
+        };
+        let definition = Definition {
+            name: name.clone(),
+            typ: self.typ(&typ),
+            file: self.file.to_path_buf(),
+            pos: pos,
+        };
+        self.index.symbols.push(Symbol {
+            name: name.clone(),
+            pos: pos,
+            typ: definition.typ.clone(),
+            definition: Some(definition.clone()),
+        });
+        if let Some(scope) = scope {
+            self.index.scopes.push((scope, definition.clone()));
+        }
+        env.add(name.clone(), definition)
+    }
+
+    fn walk(&mut self, e: &Expr, env: &Env<Definition>) {
+        match e {
+            Expr::Const(Literal::Record(ref fields)) => {
+                for e in fields.values() {
+                    self.walk(e, env);
+                }
+            }
+            Expr::Const(Literal::Array(ref elems, _)) => {
+                for e in elems.iter() {
+                    self.walk(e, env);
+                }
+            }
+            // the arguments of a C function are the parameters of its declaration
+            Expr::Const(_) | Expr::Extern(_, _, _, _) | Expr::EmptyMark => (),
+            Expr::Var(ref name, ref typ, ref pos) => {
+                if is_user_name(name) {
+                    let symbol = Symbol {
+                        name: name.clone(),
+                        pos: Position {
+                            start: pos.start,
+                            end: pos.start + format!("{}", name).len(),
+                        },
+                        typ: self.typ(typ),
+                        definition: env.lookup(name),
+                    };
+                    self.index.symbols.push(symbol);
+                }
+            }
+            Expr::Func {
+                ref name,
+                ref param_name,
+                ref param_type,
+                ref ret_type,
+                box ref body,
+                box ref left,
+                ref pos,
+                doc: _,
+            } => {
+                let typ = Type::Func(box param_type.clone(), box ret_type.clone());
+                // the name of a function is visible in its body for recursion
+                let scope = union(Some(*pos), extent(left));
+                let env = self.define(name, typ, pos.start, scope, env);
+                let params = if param_name.is_omitted_param_name() {
+                    match param_type {
+                        Type::Record(ref fields) => fields
+                            .iter()
+                            .map(|(label, typ)| (label.clone(), typ.clone()))
+                            .collect(),
+                        _ => vec![],
+                    }
+                } else {
+                    vec![(param_name.clone(), param_type.clone())]
+                };
+                let name_end = find_name(self.src, pos.start, name).map_or(pos.start, |pos| pos.end);
+                let body_env = params.into_iter().fold(env.clone(), |acc, (param, typ)| {
+                    self.define(&param, typ, name_end, Some(*pos), &acc)
+                });
+                self.walk(body, &body_env);
+                self.walk(left, &env);
+            }
+            Expr::Let(ref name, ref typ, box ref e1, box ref e2, ref pos, _) => {
+                self.walk(e1, env);
+                if is_user_name(name) {
+                    let scope = Position {
+                        start: pos.end,
+                        end: union(Some(*pos), extent(e2)).map_or(pos.end, |pos| pos.end),
+                    };
+                    let env = self.define(name, typ.clone(), pos.start, Some(scope), env);
+                    self.walk(e2, &env);
+                } else {
+                    self.walk(e2, env);
+                }
+            }
+            Expr::LetType(_, _, box ref e, _)
+            | Expr::Println(box ref e)
+            | Expr::Assert(box ref e, _) => self.walk(e, env),
+            Expr::Apply(box ref e1, box ref e2, _)
+            | Expr::BinOp(_, box ref e1, box ref e2, _)
+            | Expr::ArrayGet(box ref e1, box ref e2, _)
+            | Expr::Assign(box ref e1, box ref e2, _) => {
+                self.walk(e1, env);
+                self.walk(e2, env);
+            }
+            Expr::If(box ref cond, box ref e1, box ref e2, _) => {
+                self.walk(cond, env);
+                self.walk(e1, env);
+                self.walk(e2, env);
+            }
+            Expr::RecordGet(box ref e, ref typ, ref label, ref pos) => {
+                self.walk(e, env);
+                // the label is at the end of the expression
+                let end = self
+                    .src
+                    .get(pos.start..pos.end)
+                    .map_or(pos.end, |s| pos.start + s.trim_end().len());
+                let typ = match typ {
+                    Type::Record(ref fields) => fields.get(label).and_then(|typ| self.typ(typ)),
+                    _ => None,
+                };
+                self.index.symbols.push(Symbol {
+                    name: label.clone(),
+                    pos: Position {
+                        start: end.saturating_sub(format!("{}", label).len()),
+                        end: end,
+                    },
+                    typ: typ,
+                    definition: None,
+                });
+            }
+        }
+    }
+}
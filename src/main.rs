@@ -5,7 +5,9 @@
 extern crate lazy_static;
 extern crate nf2llvmir as nf;
 extern crate peg;
+extern crate serde;
 extern crate serde_derive;
+extern crate serde_json;
 extern crate tempfile;
 extern crate toml;
 
@@ -13,9 +15,19 @@ mod ast;
 mod codegen;
 mod command;
 mod config;
+mod diagnostic;
+mod doc;
 mod env;
+mod eval;
+mod formatter;
 mod ident;
+mod lexer;
+mod lint;
+mod lsp;
+mod package;
 mod parser;
+mod repl;
+mod stdlib;
 mod typecheck;
 mod util;
 
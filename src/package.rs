@@ -0,0 +1,620 @@
+use config::{self, Dependency, Manifest};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, Once};
+use util;
+
+mod registry;
+mod resolve;
+mod semver;
+pub mod sha256;
+mod workspace;
+
+pub use self::registry::{publish, unpack, RegistryIndex};
+pub use self::resolve::{resolve, Candidate, Dep, Index, Resolution, Source};
+pub use self::semver::{Version, VersionReq};
+pub use self::workspace::{find as find_workspace, Member, Workspace};
+
+pub const LOCKFILE: &str = "mumrik.lock";
+
+const BUILD_DIR: &str = "build";
+
+// the exact sources of the dependencies of a project, written into `mumrik.lock`
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lock {
+    #[serde(default)]
+    pub package: Vec<LockedPackage>,
+}
+
+// a package from the registry has neither `git` nor `path`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockedPackage {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    // the revision requested in `[dependencies]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rev: Option<String>,
+    // the commit which the revision was resolved to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    // SHA-256 of the tarball of a package from the registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+}
+
+impl Lock {
+    pub fn parse(src: &str) -> Result<Lock, String> {
+        toml::from_str(src).map_err(|err| format!("{}", err))
+    }
+
+    pub fn to_toml(&self) -> String {
+        format!(
+            "# pins the dependencies of the project, generated by mumrik\n\n{}",
+            toml::to_string(self).unwrap()
+        )
+    }
+
+    // the commit pinned for the revision `rev` of the git dependency `name`, while its source
+    // is unchanged
+    pub fn commit(&self, name: &str, url: &str, rev: &Option<String>) -> Option<&str> {
+        self.package
+            .iter()
+            .find(|locked| {
+                locked.name == name && locked.git.as_deref() == Some(url) && locked.rev == *rev
+            })
+            .and_then(|locked| locked.commit.as_deref())
+    }
+
+    // the version pinned for each package
+    pub fn versions(&self) -> BTreeMap<String, Version> {
+        self.package
+            .iter()
+            .filter_map(|locked| {
+                let version = Version::parse(locked.version.as_ref()?).ok()?;
+                Some((locked.name.clone(), version))
+            })
+            .collect()
+    }
+
+    fn from_resolution(resolution: &Resolution, project_dir: &Path) -> Lock {
+        let package = resolution
+            .packages
+            .values()
+            .map(|package| {
+                let (git, rev, path) = match package.source {
+                    Source::Path(ref path) => (
+                        None,
+                        None,
+                        Some(path.strip_prefix(project_dir).unwrap_or(path).to_path_buf()),
+                    ),
+                    Source::Git { ref url, ref rev } => (Some(url.clone()), rev.clone(), None),
+                    Source::TaggedGit(ref url) => (Some(url.clone()), None, None),
+                    Source::Registry => (None, None, None),
+                };
+                LockedPackage {
+                    name: package.name.clone(),
+                    version: Some(format!("{}", package.candidate.version)),
+                    git: git,
+                    rev: rev,
+                    commit: package.candidate.commit.clone(),
+                    path: path,
+                    checksum: package.candidate.checksum.clone(),
+                }
+            })
+            .collect();
+        Lock { package: package }
+    }
+}
+
+// the directory of the project, which has the config file
+pub fn project_dir() -> Option<PathBuf> {
+    let mut dir = config::config_path()?;
+    dir.pop();
+    Some(dir)
+}
+
+lazy_static! {
+    static ref WORKSPACE: Option<Workspace> = match project_dir() {
+        Some(dir) => find_workspace(&dir).unwrap_or_else(|err| {
+            eprintln!("{}: {}", util::alert("error"), err);
+            std::process::exit(-1)
+        }),
+        None => None,
+    };
+}
+
+// the workspace which the project belongs to
+pub fn workspace() -> Option<Workspace> {
+    WORKSPACE.clone()
+}
+
+// the directory of the build artifacts, which the members of a workspace share
+pub fn build_dir() -> PathBuf {
+    match *WORKSPACE {
+        Some(ref workspace) => workspace.root.join(BUILD_DIR),
+        None => PathBuf::from(BUILD_DIR),
+    }
+}
+
+// the directories of the fetched dependencies and the build artifacts, whose modules are not
+// sources of the project
+pub fn generated_dirs() -> Vec<PathBuf> {
+    let dep_dir = config::CONFIG.lock().unwrap().build.dep.clone();
+    vec![canonical(dep_dir), canonical(build_dir())]
+}
+
+fn canonical(path: PathBuf) -> PathBuf {
+    path.canonicalize().unwrap_or(path)
+}
+
+// the dependency `name` declared in the config file of a package in `dir`
+pub fn to_dep(name: &str, dep: &Dependency, dir: &Path) -> Result<Dep, String> {
+    dep.validate()
+        .map_err(|err| format!("dependency `{}`: {}", name, err))?;
+    let source = match (&dep.git, &dep.path) {
+        (Some(ref url), _) if dep.version.is_some() => Source::TaggedGit(url.clone()),
+        (Some(ref url), _) => Source::Git {
+            url: url.clone(),
+            rev: dep.rev.clone(),
+        },
+        (None, Some(ref path)) => Source::Path(canonical(dir.join(path))),
+        (None, None) => Source::Registry,
+    };
+    let req = match dep.version {
+        Some(ref version) => {
+            VersionReq::parse(version).map_err(|err| format!("dependency `{}`: {}", name, err))?
+        }
+        None => VersionReq::any(),
+    };
+    Ok(Dep {
+        name: name.to_string(),
+        source: source,
+        req: req,
+    })
+}
+
+fn git(dir: Option<&Path>, args: &[&str]) -> Result<String, String> {
+    let mut command = Command::new("git");
+    if let Some(dir) = dir {
+        command.arg("-C").arg(dir);
+    }
+    let output = command
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to execute `git`: {}", err))?;
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    } else {
+        Err(format!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+// the commit which `rev` names in the repository in `dir`, `HEAD` of the remote by default
+fn resolve_rev(dir: &Path, rev: &Option<String>) -> Result<String, String> {
+    let candidates = match rev {
+        // branches are resolved to the fetched ones, not to stale local ones
+        Some(ref rev) => vec![format!("origin/{}", rev), rev.clone()],
+        None => vec!["origin/HEAD".to_string(), "HEAD".to_string()],
+    };
+    candidates
+        .iter()
+        .filter_map(|rev| {
+            git(
+                Some(dir),
+                &[
+                    "rev-parse",
+                    "--verify",
+                    "--quiet",
+                    &format!("{}^{{commit}}", rev),
+                ],
+            )
+            .ok()
+        })
+        .next()
+        .ok_or_else(|| {
+            format!(
+                "revision `{}` is not found in `{}`",
+                rev.as_deref().unwrap_or("HEAD"),
+                dir.display()
+            )
+        })
+}
+
+// the manifest in the config file `src`, or that of a package without config file
+fn parse_manifest(src: Option<String>, origin: &str) -> Result<Manifest, String> {
+    match src {
+        Some(src) => toml::from_str(&src)
+            .map_err(|err| format!("invalid `{}` of {}: {}", config::FILENAME, origin, err)),
+        None => Ok(Manifest::default()),
+    }
+}
+
+// the version of a package described by `manifest`, whose dependencies are relative to `dir`.
+// `version` is given by the index, e.g) the tag of a git repository.
+fn candidate(
+    manifest: Manifest,
+    dir: &Path,
+    version: Option<Version>,
+    commit: Option<String>,
+) -> Result<Candidate, String> {
+    let version = match (version, manifest.package) {
+        (Some(version), _) => version,
+        (None, Some(package)) => Version::parse(&package.version)
+            .map_err(|err| format!("`[package] version` of {}: {}", dir.display(), err))?,
+        (None, None) => Version::new(0, 0, 0),
+    };
+    let deps = manifest
+        .dependencies
+        .iter()
+        .map(|(name, dep)| to_dep(name, dep, dir))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Candidate {
+        version: version,
+        deps: deps,
+        commit: commit,
+        dir: None,
+        checksum: None,
+    })
+}
+
+// the packages available to the project: directories, git repositories cloned into the `dep`
+// directory, and the local registry
+struct LocalIndex<'a> {
+    dep_dir: PathBuf,
+    registry: Option<PathBuf>,
+    lock: &'a Lock,
+    cache: HashMap<(String, Source), Vec<Candidate>>,
+}
+
+impl<'a> LocalIndex<'a> {
+    // clones the repository of the dependency `name`, and fetches it unless the lockfile pins it
+    // at `rev`, whose commit may not be fetched yet if it is another revision
+    fn prepare_repo(
+        &self,
+        name: &str,
+        url: &str,
+        rev: &Option<String>,
+    ) -> Result<PathBuf, String> {
+        let dir = self.dep_dir.join(name);
+        if !dir.join(".git").exists() {
+            eprintln!("fetch: {} ({})", name, url);
+            std::fs::create_dir_all(&self.dep_dir)
+                .map_err(|err| format!("can not create `{}`: {}", self.dep_dir.display(), err))?;
+            git(None, &["clone", "--quiet", url, dir.to_str().unwrap()])?;
+            return Ok(dir);
+        }
+        let pinned = self.lock.package.iter().any(|locked| {
+            locked.name == name && locked.git.as_deref() == Some(url) && locked.rev == *rev
+        });
+        if git(Some(&dir), &["remote", "get-url", "origin"])? != url {
+            git(Some(&dir), &["remote", "set-url", "origin", url])?;
+        } else if pinned {
+            return Ok(dir);
+        }
+        eprintln!("fetch: {} ({})", name, url);
+        git(Some(&dir), &["fetch", "--quiet", "--tags", "origin"])?;
+        Ok(dir)
+    }
+
+    fn git_manifest(dir: &Path, commit: &str, origin: &str) -> Result<Manifest, String> {
+        let src = git(
+            Some(dir),
+            &["show", &format!("{}:{}", commit, config::FILENAME)],
+        )
+        .ok();
+        parse_manifest(src, origin)
+    }
+
+    fn load(&mut self, name: &str, source: &Source) -> Result<Vec<Candidate>, String> {
+        match source {
+            Source::Path(ref dir) => {
+                if !dir.is_dir() {
+                    return Err(format!(
+                        "the directory of dependency `{}` is not found: {}",
+                        name,
+                        dir.display()
+                    ));
+                }
+                let src = std::fs::read_to_string(dir.join(config::FILENAME)).ok();
+                let manifest = parse_manifest(src, &format!("`{}`", dir.display()))?;
+                let mut candidate = candidate(manifest, dir, None, None)?;
+                candidate.dir = Some(dir.clone());
+                Ok(vec![candidate])
+            }
+            Source::Git { ref url, ref rev } => {
+                let dir = self.prepare_repo(name, url, rev)?;
+                let commit = match self.lock.commit(name, url, rev) {
+                    Some(commit) => commit.to_string(),
+                    None => resolve_rev(&dir, rev)?,
+                };
+                let manifest = LocalIndex::git_manifest(&dir, &commit, &format!("`{}`", source))?;
+                Ok(vec![candidate(manifest, &dir, None, Some(commit))?])
+            }
+            Source::TaggedGit(ref url) => {
+                let dir = self.prepare_repo(name, url, &None)?;
+                let mut candidates = vec![];
+                for tag in git(Some(&dir), &["tag", "--list"])?.lines() {
+                    let version = match Version::parse(tag.trim_start_matches('v')) {
+                        Ok(version) => version,
+                        Err(_) => continue,
+                    };
+                    let commit = git(Some(&dir), &["rev-parse", &format!("{}^{{commit}}", tag)])?;
+                    let origin = format!("`{}` {}", url, tag);
+                    let manifest = LocalIndex::git_manifest(&dir, &commit, &origin)?;
+                    candidates.push(candidate(manifest, &dir, Some(version), Some(commit))?);
+                }
+                Ok(candidates)
+            }
+            Source::Registry => {
+                let registry = self.registry.as_ref().ok_or_else(|| {
+                    format!(
+                        "dependency `{}` is looked up in the registry, but `[registry]` is not configured",
+                        name
+                    )
+                })?;
+                let mut candidates = vec![];
+                for entry in RegistryIndex::read(registry)?.versions(name) {
+                    let version = Version::parse(&entry.version)
+                        .map_err(|err| format!("`{}` in the registry: {}", name, err))?;
+                    // a version is never published twice, so its tarball is never replaced
+                    let locked = self.lock.package.iter().find(|locked| {
+                        locked.name == name && locked.version.as_ref() == Some(&entry.version)
+                    });
+                    if let Some(checksum) = locked.and_then(|locked| locked.checksum.as_ref()) {
+                        if *checksum != entry.checksum {
+                            return Err(format!(
+                                "checksum of `{} v{}` in the registry does not match `{}`",
+                                name, version, LOCKFILE
+                            ));
+                        }
+                    }
+                    let manifest = Manifest {
+                        package: None,
+                        dependencies: entry.dependencies.clone(),
+                    };
+                    let mut candidate = candidate(manifest, registry, Some(version), None)?;
+                    candidate.dir = Some(self.dep_dir.join(format!("{}-{}", name, version)));
+                    candidate.checksum = Some(entry.checksum.clone());
+                    candidates.push(candidate);
+                }
+                Ok(candidates)
+            }
+        }
+    }
+}
+
+impl<'a> Index for LocalIndex<'a> {
+    fn candidates(&mut self, name: &str, source: &Source) -> Result<Vec<Candidate>, String> {
+        let key = (name.to_string(), source.clone());
+        if !self.cache.contains_key(&key) {
+            let candidates = self.load(name, source)?;
+            self.cache.insert(key.clone(), candidates);
+        }
+        Ok(self.cache[&key].clone())
+    }
+}
+
+fn read_lock(path: &Path) -> Result<Lock, String> {
+    match std::fs::read_to_string(path) {
+        Ok(src) => Lock::parse(&src).map_err(|err| format!("invalid `{}`: {}", LOCKFILE, err)),
+        Err(_) => Ok(Lock::default()),
+    }
+}
+
+// the registry in `[registry]` of the project in `project_dir`
+pub fn registry_dir(project_dir: &Path) -> Option<PathBuf> {
+    let config = config::CONFIG.lock().unwrap();
+    let registry = config.registry.as_ref()?;
+    Some(project_dir.join(&registry.path))
+}
+
+// the name of the project with its version, e.g) `app v0.1.0`, or the name of the directory of
+// its workspace
+pub fn project_label(project_dir: &Path) -> String {
+    let dir = match *WORKSPACE {
+        Some(ref workspace) => workspace.root.as_path(),
+        None => project_dir,
+    };
+    match config::CONFIG.lock().unwrap().package {
+        Some(ref package) if WORKSPACE.is_none() => {
+            format!("{} v{}", package.name, package.version)
+        }
+        _ => dir
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("main")
+            .to_string(),
+    }
+}
+
+// what the dependencies are resolved for: the project, or all members of its workspace which
+// depend on the members as directories
+struct Root {
+    // the directory of `mumrik.lock`
+    dir: PathBuf,
+    deps: Vec<Dep>,
+    dep_dir: PathBuf,
+    registry: Option<PathBuf>,
+}
+
+fn resolution_root(project_dir: &Path) -> Result<Root, String> {
+    if let Some(ref workspace) = *WORKSPACE {
+        return Ok(Root {
+            dir: workspace.root.clone(),
+            deps: workspace
+                .members
+                .iter()
+                .map(|member| Dep {
+                    name: member.name.clone(),
+                    source: Source::Path(member.dir.clone()),
+                    req: VersionReq::any(),
+                })
+                .collect(),
+            dep_dir: workspace.dep_dir.clone(),
+            registry: workspace.registry.clone(),
+        });
+    }
+    let (deps, dep_dir) = {
+        let config = config::CONFIG.lock().unwrap();
+        let deps = config
+            .dependencies
+            .iter()
+            .map(|(name, dep)| to_dep(name, dep, project_dir))
+            .collect::<Result<Vec<_>, _>>()?;
+        (deps, project_dir.join(&config.build.dep))
+    };
+    Ok(Root {
+        dir: project_dir.to_path_buf(),
+        deps: deps,
+        dep_dir: dep_dir,
+        registry: registry_dir(project_dir),
+    })
+}
+
+fn resolve_root(project_dir: &Path, root: &Root, lock: &Lock) -> Result<Resolution, String> {
+    let mut index = LocalIndex {
+        dep_dir: root.dep_dir.clone(),
+        registry: root.registry.clone(),
+        lock: lock,
+        cache: HashMap::new(),
+    };
+    resolve(
+        &project_label(project_dir),
+        &root.deps,
+        &mut index,
+        &lock.versions(),
+    )
+}
+
+// chooses the versions of the dependencies of the project in `project_dir` and of theirs,
+// keeping those pinned in `mumrik.lock` while they match
+pub fn resolve_project(project_dir: &Path) -> Result<Resolution, String> {
+    let root = resolution_root(project_dir)?;
+    let lock = read_lock(&root.dir.join(LOCKFILE))?;
+    resolve_root(project_dir, &root, &lock)
+}
+
+// the directory of each resolved package, checking out the git repositories at their commits
+// and unpacking the tarballs from the registry
+fn checkout(
+    resolution: &Resolution,
+    dep_dir: &Path,
+    registry: Option<&Path>,
+) -> Result<BTreeMap<String, PathBuf>, String> {
+    let mut roots = BTreeMap::new();
+    for (name, package) in resolution.packages.iter() {
+        let dir = match package.candidate.commit {
+            None if package.source == Source::Registry => {
+                let registry = registry.unwrap();
+                let dir = package.candidate.dir.clone().unwrap();
+                let index = RegistryIndex::read(registry)?;
+                let entry = index.find(name, &package.candidate.version).unwrap();
+                unpack(registry, entry, &dir)
+                    .map_err(|err| format!("failed to fetch `{}`: {}", name, err))?;
+                dir
+            }
+            Some(ref commit) => {
+                let dir = dep_dir.join(name);
+                if git(Some(&dir), &["rev-parse", "HEAD"]).ok().as_ref() != Some(commit) {
+                    git(Some(&dir), &["checkout", "--quiet", "--detach", commit])
+                        .map_err(|err| format!("failed to check out `{}`: {}", name, err))?;
+                }
+                dir
+            }
+            None => package.candidate.dir.clone().unwrap(),
+        };
+        roots.insert(name.clone(), dir);
+    }
+    Ok(roots)
+}
+
+// resolves and fetches the dependencies of the project, and pins them in `mumrik.lock`, which
+// is shared by the members of a workspace
+pub fn fetch() -> Result<BTreeMap<String, PathBuf>, String> {
+    let project_dir = match project_dir() {
+        Some(dir) => dir,
+        None => return Ok(BTreeMap::new()),
+    };
+    let root = resolution_root(&project_dir)?;
+    let lock_path = root.dir.join(LOCKFILE);
+    let lock = read_lock(&lock_path)?;
+    let resolution = resolve_root(&project_dir, &root, &lock)?;
+    let roots = checkout(&resolution, &root.dep_dir, root.registry.as_deref())?;
+
+    let new_lock = Lock::from_resolution(&resolution, &root.dir);
+    if new_lock != lock && !(new_lock.package.is_empty() && !lock_path.exists()) {
+        std::fs::write(&lock_path, new_lock.to_toml())
+            .map_err(|err| format!("can not write `{}`: {}", lock_path.display(), err))?;
+    }
+    Ok(roots)
+}
+
+lazy_static! {
+    // the directory of each package which the project depends on, set by `ensure_fetched`
+    static ref ROOTS: Mutex<BTreeMap<String, PathBuf>> = Mutex::new(BTreeMap::new());
+    // the directory of each package pinned in `mumrik.lock` which is already in place, for the
+    // commands which never fetch, e.g) `check` and the language server
+    static ref LOCKED_ROOTS: BTreeMap<String, PathBuf> = locked_roots().unwrap_or_default();
+}
+
+static FETCH: Once = Once::new();
+
+// fetches the dependencies of the commands which build or run the program, at most once
+pub fn ensure_fetched() {
+    FETCH.call_once(|| match fetch() {
+        Ok(roots) => *ROOTS.lock().unwrap() = roots,
+        Err(err) => {
+            eprintln!("{}: {}", util::alert("error"), err);
+            std::process::exit(-1);
+        }
+    });
+}
+
+// the directories of the packages pinned in `mumrik.lock` and of the directories which the
+// project depends on, without resolving nor fetching them. the git repositories are not checked
+// out at their commits, so they may be at the others.
+fn locked_roots() -> Result<BTreeMap<String, PathBuf>, String> {
+    let project_dir = match project_dir() {
+        Some(dir) => dir,
+        None => return Ok(BTreeMap::new()),
+    };
+    let root = resolution_root(&project_dir)?;
+    let lock = read_lock(&root.dir.join(LOCKFILE))?;
+    let locked = lock.package.iter().map(|locked| {
+        let dir = match (&locked.path, &locked.git, &locked.version) {
+            (Some(ref path), _, _) => root.dir.join(path),
+            // a package from the registry is unpacked with its version, see `LocalIndex`
+            (None, None, Some(ref version)) => {
+                root.dep_dir.join(format!("{}-{}", locked.name, version))
+            }
+            _ => root.dep_dir.join(&locked.name),
+        };
+        (locked.name.clone(), dir)
+    });
+    let dirs = root.deps.iter().filter_map(|dep| match dep.source {
+        Source::Path(ref dir) => Some((dep.name.clone(), dir.clone())),
+        _ => None,
+    });
+    Ok(locked.chain(dirs).filter(|(_, dir)| dir.is_dir()).collect())
+}
+
+// the directory which the modules of the package `name` are imported from,
+// e.g) `import foo.util;` reads `util.mm` in the directory of `foo`
+pub fn root(name: &str) -> Option<PathBuf> {
+    if FETCH.is_completed() {
+        ROOTS.lock().unwrap().get(name).cloned()
+    } else {
+        LOCKED_ROOTS.get(name).cloned()
+    }
+}
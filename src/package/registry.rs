@@ -0,0 +1,213 @@
+use super::semver::Version;
+use super::sha256::sha256;
+use config::{self, Dependency, Manifest, PackageConfig};
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile;
+use util;
+
+// the file listing the packages of a registry
+pub const INDEX: &str = "index.toml";
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegistryIndex {
+    #[serde(default)]
+    pub package: Vec<Entry>,
+}
+
+// a version of a package in a registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    pub version: String,
+    // SHA-256 of the tarball
+    pub checksum: String,
+    // relative to the registry, e.g) `json/json-0.4.0.tar.gz`
+    pub tarball: PathBuf,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, Dependency>,
+}
+
+impl RegistryIndex {
+    pub fn parse(src: &str) -> Result<RegistryIndex, String> {
+        toml::from_str(src).map_err(|err| format!("{}", err))
+    }
+
+    // the index of the registry in `dir`, which is empty before the first package is published
+    pub fn read(dir: &Path) -> Result<RegistryIndex, String> {
+        let path = dir.join(INDEX);
+        match std::fs::read_to_string(&path) {
+            Ok(src) => RegistryIndex::parse(&src)
+                .map_err(|err| format!("invalid registry index `{}`: {}", path.display(), err)),
+            Err(_) => Ok(RegistryIndex::default()),
+        }
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), String> {
+        let path = dir.join(INDEX);
+        let src = format!(
+            "# the packages of the registry, generated by `mumrik publish`\n\n{}",
+            toml::to_string(self).unwrap()
+        );
+        std::fs::write(&path, src)
+            .map_err(|err| format!("can not write `{}`: {}", path.display(), err))
+    }
+
+    pub fn versions<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Entry> {
+        self.package.iter().filter(move |entry| entry.name == name)
+    }
+
+    pub fn find(&self, name: &str, version: &Version) -> Option<&Entry> {
+        self.package.iter().find(|entry| {
+            entry.name == name && Version::parse(&entry.version).as_ref() == Ok(version)
+        })
+    }
+
+    // adds `entry` keeping the packages sorted by their names and versions
+    pub fn add(&mut self, entry: Entry) -> Result<(), String> {
+        let version = Version::parse(&entry.version)?;
+        if self.find(&entry.name, &version).is_some() {
+            return Err(format!(
+                "`{} v{}` is already published",
+                entry.name, entry.version
+            ));
+        }
+        self.package.push(entry);
+        self.package.sort_by_key(|entry| {
+            (
+                entry.name.clone(),
+                Version::parse(&entry.version).unwrap_or_else(|_| Version::new(0, 0, 0)),
+            )
+        });
+        Ok(())
+    }
+}
+
+fn tar(args: &[&std::ffi::OsStr]) -> Result<(), String> {
+    let output = Command::new("tar")
+        .args(args)
+        .output()
+        .map_err(|err| format!("failed to execute `tar`: {}", err))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "`tar` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+// packs the modules under `src_dir` with the manifest of the package into `registry`, so that
+// `import <name>.<module>;` reads them as the modules in the same directory as `build.src` do
+pub fn publish(
+    registry: &Path,
+    package: &PackageConfig,
+    dependencies: &BTreeMap<String, Dependency>,
+    src_dir: &Path,
+    skip: &[PathBuf],
+) -> Result<Entry, String> {
+    let version =
+        Version::parse(&package.version).map_err(|err| format!("`[package] version`: {}", err))?;
+    if let Some((name, _)) = dependencies.iter().find(|(_, dep)| dep.path.is_some()) {
+        return Err(format!(
+            "dependency `{}` is given by `path`, which is not available to the users of the registry",
+            name
+        ));
+    }
+    let mut index = RegistryIndex::read(registry)?;
+    if index.find(&package.name, &version).is_some() {
+        return Err(format!(
+            "`{} v{}` is already published to `{}`",
+            package.name,
+            version,
+            registry.display()
+        ));
+    }
+
+    let files: Vec<PathBuf> = util::source_files(src_dir, skip)
+        .map_err(|err| format!("can not read `{}`: {}", src_dir.display(), err))?
+        .into_iter()
+        .map(|file| file.strip_prefix(src_dir).unwrap().to_path_buf())
+        .collect();
+    if files.is_empty() {
+        return Err(format!("no module is found in `{}`", src_dir.display()));
+    }
+    let staging =
+        tempfile::tempdir().map_err(|err| format!("can not create a directory: {}", err))?;
+    for file in files.iter() {
+        let dest = staging.path().join(file);
+        std::fs::create_dir_all(dest.parent().unwrap())
+            .and_then(|_| std::fs::copy(src_dir.join(file), &dest))
+            .map_err(|err| format!("can not copy `{}`: {}", file.display(), err))?;
+    }
+    let manifest = Manifest {
+        package: Some(package.clone()),
+        dependencies: dependencies.clone(),
+    };
+    std::fs::write(
+        staging.path().join(config::FILENAME),
+        toml::to_string(&manifest).unwrap(),
+    )
+    .map_err(|err| format!("can not write `{}`: {}", config::FILENAME, err))?;
+
+    let tarball = Path::new(&package.name).join(format!("{}-{}.tar.gz", package.name, version));
+    let tarball_path = registry.join(&tarball);
+    std::fs::create_dir_all(tarball_path.parent().unwrap())
+        .map_err(|err| format!("can not create `{}`: {}", registry.display(), err))?;
+    tar(&[
+        "-czf".as_ref(),
+        tarball_path.as_os_str(),
+        "-C".as_ref(),
+        staging.path().as_os_str(),
+        ".".as_ref(),
+    ])?;
+    let data = std::fs::read(&tarball_path)
+        .map_err(|err| format!("can not read `{}`: {}", tarball_path.display(), err))?;
+
+    let entry = Entry {
+        name: package.name.clone(),
+        version: format!("{}", version),
+        checksum: sha256(&data),
+        tarball: tarball,
+        dependencies: dependencies.clone(),
+    };
+    index.add(entry.clone())?;
+    index.write(registry)?;
+    Ok(entry)
+}
+
+// unpacks the tarball of `entry` into `dest` after verifying its checksum,
+// unless it has been unpacked
+pub fn unpack(registry: &Path, entry: &Entry, dest: &Path) -> Result<(), String> {
+    if dest.is_dir() {
+        return Ok(());
+    }
+    let tarball = registry.join(&entry.tarball);
+    let data = std::fs::read(&tarball)
+        .map_err(|err| format!("can not read `{}`: {}", tarball.display(), err))?;
+    if sha256(&data) != entry.checksum {
+        return Err(format!(
+            "checksum of `{}` does not match the registry index, the tarball may be corrupted",
+            tarball.display()
+        ));
+    }
+    eprintln!("fetch: {} v{} (registry)", entry.name, entry.version);
+    // unpacked next to `dest` not to leave a partial package in `dest` on failure
+    let mut partial = dest.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let _ = std::fs::remove_dir_all(&partial);
+    std::fs::create_dir_all(&partial)
+        .map_err(|err| format!("can not create `{}`: {}", partial.display(), err))?;
+    tar(&[
+        "-xzf".as_ref(),
+        tarball.as_os_str(),
+        "-C".as_ref(),
+        partial.as_os_str(),
+    ])?;
+    std::fs::rename(&partial, dest)
+        .map_err(|err| format!("can not create `{}`: {}", dest.display(), err))
+}
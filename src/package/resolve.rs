@@ -0,0 +1,268 @@
+use super::semver::{Version, VersionReq};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fmt;
+use std::path::PathBuf;
+
+// a limit of the rounds to choose versions, which only matters when choices keep changing
+const MAX_ROUNDS: usize = 64;
+
+// where the versions of a package come from
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Source {
+    // a directory, which has a single version
+    Path(PathBuf),
+    // a revision of a git repository, the default branch when omitted
+    Git { url: String, rev: Option<String> },
+    // a git repository whose tags `v<version>` are its versions
+    TaggedGit(String),
+    // the local registry of the project
+    Registry,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Path(ref path) => write!(f, "{}", path.display()),
+            Source::Git { ref url, rev: None } | Source::TaggedGit(ref url) => write!(f, "{}", url),
+            Source::Git {
+                ref url,
+                rev: Some(ref rev),
+            } => write!(f, "{}#{}", url, rev),
+            Source::Registry => write!(f, "registry"),
+        }
+    }
+}
+
+// a dependency of a package on the versions of `name` in `source` which match `req`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dep {
+    pub name: String,
+    pub source: Source,
+    pub req: VersionReq,
+}
+
+// a version of a package with its own dependencies
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub version: Version,
+    pub deps: Vec<Dep>,
+    // the commit of the version in a git repository
+    pub commit: Option<String>,
+    // the directory of the version in a directory, or where the registry unpacks it
+    pub dir: Option<PathBuf>,
+    // SHA-256 of the tarball of the version in the registry
+    pub checksum: Option<String>,
+}
+
+pub trait Index {
+    // the versions of the package `name` in `source`
+    fn candidates(&mut self, name: &str, source: &Source) -> Result<Vec<Candidate>, String>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Package {
+    pub name: String,
+    pub source: Source,
+    pub candidate: Candidate,
+}
+
+impl Package {
+    fn label(&self) -> String {
+        format!("{} v{}", self.name, self.candidate.version)
+    }
+}
+
+// the version chosen for each package which the root depends on directly or indirectly
+#[derive(Debug, Clone, PartialEq)]
+pub struct Resolution {
+    pub deps: Vec<Dep>,
+    pub packages: BTreeMap<String, Package>,
+}
+
+// the requirements of the root and the packages it reaches through `selected`,
+// with the package requiring each of them
+fn requirements(
+    root: &str,
+    deps: &[Dep],
+    selected: &BTreeMap<String, Package>,
+) -> BTreeMap<String, Vec<(String, Dep)>> {
+    let mut requirements: BTreeMap<String, Vec<(String, Dep)>> = BTreeMap::new();
+    let mut visited = BTreeSet::new();
+    let mut queue: VecDeque<(String, &Dep)> =
+        deps.iter().map(|dep| (root.to_string(), dep)).collect();
+    while let Some((by, dep)) = queue.pop_front() {
+        requirements
+            .entry(dep.name.clone())
+            .or_insert_with(Vec::new)
+            .push((by, dep.clone()));
+        if !visited.insert(dep.name.clone()) {
+            continue;
+        }
+        if let Some(package) = selected.get(&dep.name) {
+            queue.extend(
+                package
+                    .candidate
+                    .deps
+                    .iter()
+                    .map(|dep| (package.label(), dep)),
+            );
+        }
+    }
+    requirements
+}
+
+fn unsatisfied_error(
+    name: &str,
+    source: &Source,
+    requirements: &[(String, Dep)],
+    candidates: &[Candidate],
+) -> String {
+    let mut versions: Vec<Version> = candidates
+        .iter()
+        .map(|candidate| candidate.version)
+        .collect();
+    versions.sort();
+    let available = if versions.is_empty() {
+        format!("no version of `{}` is found in {}", name, source)
+    } else {
+        let versions: Vec<String> = versions
+            .iter()
+            .map(|version| format!("{}", version))
+            .collect();
+        format!("available versions in {}: {}", source, versions.join(", "))
+    };
+    if requirements.len() == 1 {
+        let (ref by, ref dep) = requirements[0];
+        format!(
+            "no version of `{}` matches `{}` required by `{}`, {}",
+            name, dep.req, by, available
+        )
+    } else {
+        let lines: Vec<String> = requirements
+            .iter()
+            .map(|(by, dep)| format!("    `{}` required by `{}`", dep.req, by))
+            .collect();
+        format!(
+            "conflicting requirements for `{}`:\n{}\n{}",
+            name,
+            lines.join("\n"),
+            available
+        )
+    }
+}
+
+// chooses a version of each package which the root named `root` depends on through `deps`,
+// so that each package has a single version matching all of its requirements. the versions
+// in `preferred`, e.g) those in the lockfile, are kept while they match, otherwise the
+// highest matching versions are chosen.
+pub fn resolve<I: Index>(
+    root: &str,
+    deps: &[Dep],
+    index: &mut I,
+    preferred: &BTreeMap<String, Version>,
+) -> Result<Resolution, String> {
+    let mut selected: BTreeMap<String, Package> = BTreeMap::new();
+    for _ in 0..MAX_ROUNDS {
+        let mut next = BTreeMap::new();
+        for (name, requirements) in requirements(root, deps, &selected) {
+            let (ref first_by, ref first) = requirements[0];
+            if let Some((by, dep)) = requirements
+                .iter()
+                .find(|(_, dep)| dep.source != first.source)
+            {
+                return Err(format!(
+                    "`{}` is required from different sources, {} by `{}` and {} by `{}`",
+                    name, first.source, first_by, dep.source, by
+                ));
+            }
+            let candidates = index.candidates(&name, &first.source)?;
+            let matching: Vec<&Candidate> = candidates
+                .iter()
+                .filter(|candidate| {
+                    requirements
+                        .iter()
+                        .all(|(_, dep)| dep.req.matches(&candidate.version))
+                })
+                .collect();
+            let kept = selected
+                .get(&name)
+                .map(|package| package.candidate.version)
+                .into_iter()
+                .chain(preferred.get(&name).cloned())
+                .filter_map(|version| {
+                    matching
+                        .iter()
+                        .find(|candidate| candidate.version == version)
+                })
+                .next();
+            let candidate = match kept.or_else(|| matching.iter().max_by_key(|c| c.version)) {
+                Some(candidate) => (*candidate).clone(),
+                None => {
+                    return Err(unsatisfied_error(
+                        &name,
+                        &first.source,
+                        &requirements,
+                        &candidates,
+                    ))
+                }
+            };
+            next.insert(
+                name.clone(),
+                Package {
+                    name: name,
+                    source: first.source.clone(),
+                    candidate: candidate,
+                },
+            );
+        }
+        if next == selected {
+            return Ok(Resolution {
+                deps: deps.to_vec(),
+                packages: selected,
+            });
+        }
+        selected = next;
+    }
+    Err("the versions of the dependencies keep changing, can not resolve them".to_string())
+}
+
+impl Resolution {
+    // the resolved graph as a tree under `root`, where the packages already shown are marked
+    // with `(*)` instead of showing their dependencies again
+    pub fn render_tree(&self, root: &str) -> String {
+        let mut out = format!("{}\n", root);
+        let mut shown = BTreeSet::new();
+        self.render_deps(&self.deps, "", &mut shown, &mut out);
+        out
+    }
+
+    fn render_deps(
+        &self,
+        deps: &[Dep],
+        prefix: &str,
+        shown: &mut BTreeSet<String>,
+        out: &mut String,
+    ) {
+        for (idx, dep) in deps.iter().enumerate() {
+            let last = idx + 1 == deps.len();
+            let package = &self.packages[&dep.name];
+            let again = !shown.insert(dep.name.clone());
+            *out += &format!(
+                "{}{} {} ({}){}\n",
+                prefix,
+                if last { "└──" } else { "├──" },
+                package.label(),
+                package.source,
+                if again && !package.candidate.deps.is_empty() {
+                    " (*)"
+                } else {
+                    ""
+                }
+            );
+            if !again {
+                let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                self.render_deps(&package.candidate.deps, &prefix, shown, out);
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt;
+
+// e.g) `1.2.3`, versions with pre-release or build metadata are not supported
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    // `^1.2`, also written as `1.2`
+    Caret,
+    // `~1.2`
+    Tilde,
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+}
+
+// a comparator whose version may be partial, e.g) `>=1.2` or `~1`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Comparator {
+    op: Op,
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+}
+
+// e.g) `^1.2`, `>=1.0, <2.0` or `*`, matched by the versions which match all of the comparators
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReq {
+    comparators: Vec<Comparator>,
+}
+
+fn parse_number(s: &str) -> Result<u64, String> {
+    if s.is_empty() || !s.chars().all(|c| c.is_ascii_digit()) || (s.len() > 1 && s.starts_with('0'))
+    {
+        return Err(format!("invalid version number `{}`", s));
+    }
+    s.parse()
+        .map_err(|_| format!("too large version number `{}`", s))
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Version {
+        Version {
+            major: major,
+            minor: minor,
+            patch: patch,
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<Version, String> {
+        let numbers: Vec<&str> = s.trim().split('.').collect();
+        if numbers.len() != 3 {
+            return Err(format!(
+                "invalid version `{}`, expected `<major>.<minor>.<patch>`",
+                s
+            ));
+        }
+        Ok(Version::new(
+            parse_number(numbers[0])?,
+            parse_number(numbers[1])?,
+            parse_number(numbers[2])?,
+        ))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl Comparator {
+    fn parse(s: &str) -> Result<Comparator, String> {
+        let s = s.trim();
+        let ops = [
+            (">=", Op::GreaterEq),
+            ("<=", Op::LessEq),
+            (">", Op::Greater),
+            ("<", Op::Less),
+            ("=", Op::Exact),
+            ("^", Op::Caret),
+            ("~", Op::Tilde),
+        ];
+        let (op, version) = ops
+            .iter()
+            .find(|(prefix, _)| s.starts_with(prefix))
+            .map_or((Op::Caret, s), |(prefix, op)| {
+                (*op, s[prefix.len()..].trim())
+            });
+        let numbers: Vec<&str> = version.split('.').collect();
+        if numbers.len() > 3 {
+            return Err(format!("invalid version requirement `{}`", s));
+        }
+        let number = |idx: usize| -> Result<Option<u64>, String> {
+            match numbers.get(idx) {
+                Some(number) => parse_number(number).map(Some),
+                None => Ok(None),
+            }
+        };
+        Ok(Comparator {
+            op: op,
+            major: parse_number(numbers[0])?,
+            minor: number(1)?,
+            patch: number(2)?,
+        })
+    }
+
+    // the lowest version matching the comparator with the omitted numbers as 0
+    fn lower(&self) -> Version {
+        Version::new(self.major, self.minor.unwrap_or(0), self.patch.unwrap_or(0))
+    }
+
+    // the lowest version above the range which the given numbers of the comparator allow,
+    // e.g) `2.0.0` for `1` and `1.3.0` for `1.2`
+    fn upper(&self) -> Version {
+        match (self.minor, self.patch) {
+            (None, _) => Version::new(self.major + 1, 0, 0),
+            (Some(minor), None) => Version::new(self.major, minor + 1, 0),
+            (Some(minor), Some(patch)) => Version::new(self.major, minor, patch + 1),
+        }
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        match self.op {
+            Op::Exact => self.lower() <= *version && *version < self.upper(),
+            Op::Greater => *version >= self.upper(),
+            Op::GreaterEq => *version >= self.lower(),
+            Op::Less => *version < self.lower(),
+            Op::LessEq => *version < self.upper(),
+            Op::Tilde => {
+                let upper = match self.minor {
+                    Some(minor) => Version::new(self.major, minor + 1, 0),
+                    None => Version::new(self.major + 1, 0, 0),
+                };
+                self.lower() <= *version && *version < upper
+            }
+            // compatible updates keep the leftmost non-zero number
+            Op::Caret => {
+                let upper = match (self.major, self.minor, self.patch) {
+                    (0, Some(0), Some(patch)) => Version::new(0, 0, patch + 1),
+                    (0, Some(minor), _) => Version::new(0, minor + 1, 0),
+                    (major, _, _) => Version::new(major + 1, 0, 0),
+                };
+                self.lower() <= *version && *version < upper
+            }
+        }
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match self.op {
+            Op::Caret => "^",
+            Op::Tilde => "~",
+            Op::Exact => "=",
+            Op::Greater => ">",
+            Op::GreaterEq => ">=",
+            Op::Less => "<",
+            Op::LessEq => "<=",
+        };
+        write!(f, "{}{}", op, self.major)?;
+        if let Some(minor) = self.minor {
+            write!(f, ".{}", minor)?;
+        }
+        if let Some(patch) = self.patch {
+            write!(f, ".{}", patch)?;
+        }
+        Ok(())
+    }
+}
+
+impl VersionReq {
+    // `*`, matched by every version
+    pub fn any() -> VersionReq {
+        VersionReq {
+            comparators: vec![],
+        }
+    }
+
+    pub fn parse(s: &str) -> Result<VersionReq, String> {
+        if s.trim() == "*" {
+            return Ok(VersionReq::any());
+        }
+        let comparators = s
+            .split(',')
+            .map(Comparator::parse)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("{} in `{}`", err, s))?;
+        Ok(VersionReq {
+            comparators: comparators,
+        })
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.comparators
+            .iter()
+            .all(|comparator| comparator.matches(version))
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.comparators.is_empty() {
+            return write!(f, "*");
+        }
+        let comparators: Vec<String> = self
+            .comparators
+            .iter()
+            .map(|comparator| format!("{}", comparator))
+            .collect();
+        write!(f, "{}", comparators.join(", "))
+    }
+}
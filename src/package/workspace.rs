@@ -0,0 +1,108 @@
+use config::{self, Config};
+use std::path::{Path, PathBuf};
+
+// several packages sharing the lockfile and the `build` directory, declared by
+// `[workspace] members = ["app", "util"]` in the config file of their parent directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Workspace {
+    // the directory of the config file declaring `[workspace]`
+    pub root: PathBuf,
+    pub members: Vec<Member>,
+    // `[build] dep` of the root, where the git dependencies of all members are cloned
+    pub dep_dir: PathBuf,
+    // `[registry]` of the root
+    pub registry: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    // the name in `[package]` of the member, the name of its directory by default
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+impl Workspace {
+    pub fn member(&self, name: &str) -> Option<&Member> {
+        self.members.iter().find(|member| member.name == name)
+    }
+
+    // the member whose directory is `dir`
+    pub fn member_at(&self, dir: &Path) -> Option<&Member> {
+        let dir = canonical(dir.to_path_buf());
+        self.members.iter().find(|member| member.dir == dir)
+    }
+}
+
+fn canonical(path: PathBuf) -> PathBuf {
+    path.canonicalize().unwrap_or(path)
+}
+
+fn read_config(path: &Path) -> Result<Config, String> {
+    let src = std::fs::read_to_string(path)
+        .map_err(|err| format!("can not read `{}`: {}", path.display(), err))?;
+    toml::from_str(&src).map_err(|err| format!("invalid `{}`: {}", path.display(), err))
+}
+
+fn load(root: &Path, config: &Config) -> Result<Workspace, String> {
+    let workspace = config.workspace.as_ref().unwrap();
+    let mut members: Vec<Member> = vec![];
+    for path in workspace.members.iter() {
+        let dir = canonical(root.join(path));
+        let member_config = dir.join(config::FILENAME);
+        if !member_config.is_file() {
+            return Err(format!(
+                "workspace member `{}` has no `{}`",
+                path.display(),
+                config::FILENAME
+            ));
+        }
+        if dir == root {
+            return Err("the root of a workspace can not be its own member".to_string());
+        }
+        let name = match read_config(&member_config)?.package {
+            Some(package) => package.name,
+            None => dir
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("main")
+                .to_string(),
+        };
+        if members.iter().any(|member| member.name == name) {
+            return Err(format!("workspace members share the name `{}`", name));
+        }
+        members.push(Member {
+            name: name,
+            dir: dir,
+        });
+    }
+    Ok(Workspace {
+        root: root.to_path_buf(),
+        members: members,
+        dep_dir: root.join(&config.build.dep),
+        registry: config
+            .registry
+            .as_ref()
+            .map(|registry| root.join(&registry.path)),
+    })
+}
+
+// the workspace of the project in `project_dir`, which is its root or one of its members
+pub fn find(project_dir: &Path) -> Result<Option<Workspace>, String> {
+    let project_dir = canonical(project_dir.to_path_buf());
+    let mut dir = project_dir.clone();
+    loop {
+        let path = dir.join(config::FILENAME);
+        if path.is_file() {
+            let config = read_config(&path)?;
+            if config.workspace.is_some() {
+                let workspace = load(&dir, &config)?;
+                if dir == project_dir || workspace.member_at(&project_dir).is_some() {
+                    return Ok(Some(workspace));
+                }
+            }
+        }
+        if !dir.pop() {
+            return Ok(None);
+        }
+    }
+}
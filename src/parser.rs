@@ -7,7 +7,7 @@ enum RecordArrayGetExprAux {
     ArrayGet(Expr, usize),
 }
 
-peg::parser!(grammar rules() for str {
+peg::parser!(grammar rules(src: &str) for str {
 
 pub rule type_() -> Type
     = __ ty:func_type() { ty }
@@ -25,7 +25,15 @@ rule primitive_type() -> Type
     / BOOL() { Type::Bool }
     / CHAR() { Type::Char }
     / UNIT_T() { Type::Unit }
-    / name:ident() { Type::Var(name) }
+    / "_" !ident() __ {
+        // e.g) `_` of `func sum a: {arr: _, len: Int}`, a type left to the inference
+        Type::Var(Ident::fresh())
+    }
+    / names:(ident() ++ DOT()) {
+        // e.g) `Point` or `geometry.Point` of an imported module
+        let names: Vec<String> = names.iter().map(|name| format!("{}", name)).collect();
+        Type::Var(Ident::new(&names.join(".")))
+    }
     / LEFT_PAREN() ty:func_type() RIGHT_PAREN() { ty }
 
 rule record_type() -> Type
@@ -34,70 +42,154 @@ rule record_type() -> Type
     }
 
 pub rule program() -> Program
-    = __ imports:import_()* e:toplevel_expr() {
+    = __ imports:import_()* toplevel:toplevel_expr() {
+        let (tests, public, e) = toplevel;
         Program {
             imports: imports,
+            tests: tests,
+            public: public,
             expr: e,
         }
     }
 
 rule import_() -> Import
-    = IMPORT() dirs:(dir:ident() DOT() { dir })* name:ident() SEMICOLON() {
+    = start:position!() IMPORT() dirs:(dir:ident() DOT() !LEFT_BRACE() { dir })* name:ident() rest:import_rest() end:position!() SEMICOLON() {
+        // e.g) import a.b; import a.b as c; import a.b.{x, y};
+        let (items, alias) = rest;
         Import {
             dirs: dirs,
+            alias: alias.unwrap_or_else(|| name.clone()),
             module_name: name,
+            items: items,
+            pos: Position {start: start, end: end},
         }
     }
 
-rule toplevel_expr() -> Expr
-    = start:position!() FUNC()  name:ident() param_name:ident() COLON() param_type:type_() ret_type:(COLON() typ:type_() { typ })? LEFT_BRACE() body:expr() RIGHT_BRACE() end:position!() left:toplevel_expr() {
+rule import_rest() -> (Option<Vec<Ident>>, Option<Ident>)
+    = DOT() LEFT_BRACE() items:(ident() ++ COMMA()) COMMA()? RIGHT_BRACE() { (Some(items), None) }
+    / alias:(AS() alias:ident() { alias })? { (None, alias) }
+
+// test declarations and the names of `pub` definitions are collected apart from the program
+rule toplevel_expr() -> (Vec<Test>, Vec<Ident>, Expr)
+    = start:position!() public:$(PUB())? FUNC()  name:ident() param_name:ident() COLON() param_type:type_() ret_type:(COLON() typ:type_() { typ })? LEFT_BRACE() body:expr() RIGHT_BRACE() end:position!() left:toplevel_expr() {
         // e.g) func id x:Int { x } ..
         let ret_type = ret_type.unwrap_or_else(|| Type::Var(Ident::fresh()));
-        Expr::Func {
+        let (tests, public_names, left) = left;
+        let public_names = with_public(public.is_some(), &name, public_names);
+        (tests, public_names, Expr::Func {
             name: name,
             param_name: param_name,
             param_type: param_type,
             ret_type: ret_type,
             body: box body,
             left: box left,
-            pos: Position {start: start, end: end}
-        }
+            pos: Position {start: start, end: end},
+            doc: doc_comment(src, start),
+        })
     }
-    / start:position!() FUNC() name:ident() record_type:record_type() ret_type:(COLON() typ:type_() { typ })? LEFT_BRACE() body:expr() RIGHT_BRACE() end:position!() left:toplevel_expr() {
+    / start:position!() public:$(PUB())? FUNC() name:ident() record_type:record_type() ret_type:(COLON() typ:type_() { typ })? LEFT_BRACE() body:expr() RIGHT_BRACE() end:position!() left:toplevel_expr() {
         // e.g) func add {x:Int, y:Int} { x + y } ..
         let ret_type = ret_type.unwrap_or_else(|| Type::Var(Ident::fresh()));
-        Expr::Func {
+        let (tests, public_names, left) = left;
+        let public_names = with_public(public.is_some(), &name, public_names);
+        (tests, public_names, Expr::Func {
             name: name,
             param_name: Ident::omitted_param_name(),
             param_type: record_type,
             ret_type: ret_type,
             body: box body,
             left: box left,
-            pos: Position {start: start, end: end}
-        }
+            pos: Position {start: start, end: end},
+            doc: doc_comment(src, start),
+        })
     }
-    / start:position!() LET() name:ident() typ:(COLON() typ:type_() { typ })? EQUAL() init:expr() SEMICOLON() end:position!() left:toplevel_expr() {
+    / start:position!() public:$(PUB())? LET() name:ident() typ:(COLON() typ:type_() { typ })? EQUAL() init:inner_expr() SEMICOLON() end:position!() left:toplevel_expr() {
+        // the initializer ends at the first `;` as in `expr`, e.g) `pub let a = 1; a`, which
+        // `expr` alone would take as the sequence `1; a`
         let typ = typ.unwrap_or_else(|| Type::Var(Ident::fresh()));
-        Expr::Let(name, typ, box init, box left, Position {start: start, end: end})
+        let (tests, public_names, left) = left;
+        let public_names = with_public(public.is_some(), &name, public_names);
+        let doc = doc_comment(src, start);
+        (tests, public_names, Expr::Let(name, typ, box init, box left, Position {start: start, end: end}, doc))
+    }
+    / start:position!() public:$(PUB())? TYPE() name:ident() EQUAL() typ:type_() SEMICOLON() left:toplevel_expr() {
+        let (tests, public_names, left) = left;
+        let public_names = with_public(public.is_some(), &name, public_names);
+        let doc = doc_comment(src, start);
+        (tests, public_names, Expr::LetType(name, typ, box left, doc))
+    }
+    / start:position!() public:$(PUB())? EXTERN() FUNC() name:ident() params:extern_params() COLON() ret_type:c_type() end:position!() SEMICOLON() left:toplevel_expr() {
+        // e.g) extern func putchar c: Int :Int; ..
+        let (tests, public_names, left) = left;
+        let public_names = with_public(public.is_some(), &name, public_names);
+        let pos = Position {start: start, end: end};
+        let (param_name, params) = params;
+        // `Unit` is passed as no argument, e.g) `extern func getchar _: Unit :Int;`
+        let args = params
+            .iter()
+            .filter(|(_, typ)| *typ != Type::Unit)
+            .map(|(name, typ)| Expr::Var(name.clone(), typ.clone(), pos))
+            .collect();
+        let param_type = if param_name.is_omitted_param_name() {
+            Type::Record(params.into_iter().collect())
+        } else {
+            params[0].1.clone()
+        };
+        (tests, public_names, Expr::Func {
+            name: name.clone(),
+            param_name: param_name,
+            param_type: param_type,
+            ret_type: ret_type.clone(),
+            body: box Expr::Extern(name, args, ret_type, pos),
+            left: box left,
+            pos: pos,
+            doc: doc_comment(src, start),
+        })
+    }
+    / test:test_decl() left:toplevel_expr() {
+        let (mut tests, public_names, left) = left;
+        tests.insert(0, test);
+        (tests, public_names, left)
+    }
+    / e:expr() { (vec![], vec![], e) }
+
+// the parameters of a C function in order, e.g) `c: Int` or `{fd: Int, c: Char}`
+rule extern_params() -> (Ident, Vec<(Ident, Type)>)
+    = param_name:ident() COLON() typ:c_type() { (param_name.clone(), vec![(param_name, typ)]) }
+    / LEFT_BRACE() params:(label:ident() COLON() typ:c_type() COMMA()? { (label, typ) })* RIGHT_BRACE() {
+        (Ident::omitted_param_name(), params)
     }
-    / TYPE() name:ident() EQUAL() typ:type_() SEMICOLON() left:toplevel_expr() {
-        Expr::LetType(name, typ, box left)
+
+// the types which C functions take and return
+rule c_type() -> Type
+    = INT() { Type::Int }
+    / BOOL() { Type::Bool }
+    / CHAR() { Type::Char }
+    / UNIT_T() { Type::Unit }
+
+rule test_decl() -> Test
+    = start:position!() TEST() name:string() LEFT_BRACE() body:expr() RIGHT_BRACE() end:position!() {
+        // e.g) test "id returns its argument" { assert (id 1 == 1) }
+        Test {
+            name: name,
+            body: body,
+            pos: Position {start: start, end: end},
+        }
     }
-    / expr()
 
 rule expr() -> Expr
     = start:position!() LET() name:ident() typ:(COLON() typ:type_() { typ })? EQUAL() e1:inner_expr() SEMICOLON() end:position!() e2:expr() {
         let typ = typ.unwrap_or_else(|| Type::Var(Ident::fresh()));
-        Expr::Let(name, typ, box e1, box e2, Position {start: start, end: end})
+        Expr::Let(name, typ, box e1, box e2, Position {start: start, end: end}, None)
     }
     / TYPE() name:ident() EQUAL() typ:type_() SEMICOLON() e:expr() {
-        Expr::LetType(name, typ, box e)
+        Expr::LetType(name, typ, box e, None)
     }
     / es:((start:position!() e:inner_expr() end:position!() { (e, Position {start: start, end: end}) }) ** SEMICOLON()) {?
         let mut es = es;
         if let Some((head, _)) = es.pop() {
             Ok(es.into_iter().rev().fold(head, |acc, (e, pos)| {
-                Expr::Let(Ident::new("<dummy-sequence>"), Type::Var(Ident::fresh()), box e, box acc, pos)
+                Expr::Let(Ident::new("<dummy-sequence>"), Type::Var(Ident::fresh()), box e, box acc, pos, None)
             }))
         } else {
             Err("no expr found")
@@ -168,6 +260,7 @@ rule factor_expr() -> Expr
     / unit_expr()
     / char_expr()
     / println_expr()
+    / assert_expr()
     / var_expr()
     / LEFT_PAREN() e:expr() RIGHT_PAREN() { e }
 
@@ -185,6 +278,7 @@ rule func_expr() -> Expr
             body: box body,
             left: box Expr::Var(func_name, func_type, pos),
             pos: pos,
+            doc: None,
         }
     }
     / start:position!() FUNC() record_type:record_type() ret_type:(COLON() typ:type_() { typ })? FAT_ARROW() body:expr() end:position!() {
@@ -200,6 +294,7 @@ rule func_expr() -> Expr
             body: box body,
             left: box Expr::Var(func_name, func_type, pos),
             pos: pos,
+            doc: None,
         }
     }
 
@@ -248,6 +343,11 @@ rule char_expr() -> Expr
 rule println_expr() -> Expr
     = PRINTLN() e:inner_expr() { Expr::Println(box e) }
 
+rule assert_expr() -> Expr
+    = ASSERT() start:position!() e:inner_expr() end:position!() {
+        Expr::Assert(box e, Position {start: start, end: end})
+    }
+
 rule var_expr() -> Expr
     = start:position!() name:ident() end:position!() {
         Expr::Var(name, Type::Var(Ident::fresh()), Position{start: start, end: end})
@@ -256,6 +356,9 @@ rule var_expr() -> Expr
 rule number() -> i32
     = n:$(['0'..='9']+) __ { n.parse().unwrap() }
 
+rule string() -> String
+    = "\"" s:$((!['"'|'\n'] [_])*) "\"" __ { s.to_string() }
+
 rule ident() -> Ident
     = !IS_KEYWORD() s:$(quiet!{['a'..='z'|'A'..='Z'|'_']['a'..='z'|'A'..='Z'|'0'..='9'|'_']*}) __ { Ident::new(s) }
     / expected!("<identifier>")
@@ -268,7 +371,7 @@ rule comment()
     / "/*" (!"*/" [_])* "*/"  // block comment
 
 rule IS_KEYWORD()
-    = TYPE() / ENUM() / MATCH() / LET() / FUNC() / IF() / ELSE() / INT() / BOOL() / TRUE() / FALSE() / UNIT_V() / PRINTLN() / IMPORT() / WITH()
+    = TYPE() / ENUM() / MATCH() / LET() / FUNC() / IF() / ELSE() / INT() / BOOL() / TRUE() / FALSE() / UNIT_V() / PRINTLN() / IMPORT() / WITH() / TEST() / ASSERT() / AS() / PUB() / EXTERN()
 
 rule TYPE() = "type" !ident() __
 rule ENUM() = "enum" !ident() __
@@ -287,6 +390,11 @@ rule UNIT_V() = "unit" !ident() __
 rule PRINTLN() = "println" !ident() __
 rule IMPORT() = "import" !ident() __
 rule WITH() = "with" !ident() __
+rule TEST() = "test" !ident() __
+rule ASSERT() = "assert" !ident() __
+rule AS() = "as" !ident() __
+rule PUB() = "pub" !ident() __
+rule EXTERN() = "extern" !ident() __
 
 rule WHITE_SPACE() = [' '|'\t'|'\r'|'\n']
 rule EQUAL() = "=" __
@@ -317,7 +425,44 @@ rule RIGHT_ANGLE_BRACKET() = ">" __
 
 });
 
-pub use self::rules::*;
+// `names` with `name` in front of them, if it is declared `pub`
+fn with_public(public: bool, name: &Ident, mut names: Vec<Ident>) -> Vec<Ident> {
+    if public {
+        names.insert(0, name.clone());
+    }
+    names
+}
+
+pub fn program(src: &str) -> Result<Program, peg::error::ParseError<peg::str::LineCol>> {
+    rules::program(src, src)
+}
+
+// the `///` lines right above the declaration at `start`, which the grammar skips as comments
+fn doc_comment(src: &str, start: usize) -> Option<String> {
+    let mut lines = src[..start].rsplit('\n');
+    // e.g) `pub` is preceded by the indentation only
+    if !lines.next().map_or(true, |line| line.trim().is_empty()) {
+        return None;
+    }
+    let mut docs = vec![];
+    for line in lines {
+        let line = line.trim_start();
+        if line.is_empty() {
+            continue;
+        }
+        if !line.starts_with("///") || line.starts_with("////") {
+            break;
+        }
+        let line = &line[3..];
+        docs.push(line.strip_prefix(' ').unwrap_or(line));
+    }
+    if docs.is_empty() {
+        None
+    } else {
+        docs.reverse();
+        Some(docs.join("\n"))
+    }
+}
 
 pub struct Expected(Vec<String>);
 
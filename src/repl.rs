@@ -0,0 +1,197 @@
+use ast::{Expr, Type};
+use command::build;
+use diagnostic::{Diagnostic, MessageFormat};
+use env::Env;
+use eval::{Interpreter, RuntimeError};
+use ident::Ident;
+use lexer::{self, TokenKind};
+use parser;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use typecheck::{self, Subst};
+use util;
+
+pub const HELP: &str = r#"enter top-level `let`, `func` and `type` definitions or expressions.
+commands:
+    :type <expr>    print the type of <expr> without evaluating it
+    :load <file>    evaluate the definitions in <file>
+    :reset          forget all definitions
+    :help           print this message
+    :quit           exit the REPL"#;
+
+#[derive(Debug)]
+pub enum Error {
+    Diagnostic(Diagnostic),
+    // with the file and the source text it occurred in
+    Runtime(RuntimeError, PathBuf, String),
+    Command(String),
+}
+
+impl Error {
+    pub fn emit(&self) {
+        match self {
+            Error::Diagnostic(ref diag) => diag.emit(MessageFormat::Human),
+            Error::Runtime(ref err, ref file, ref src) => {
+                err.to_diagnostic(file, src).emit(MessageFormat::Human)
+            }
+            Error::Command(ref msg) => eprintln!("{}: {}", util::alert("error"), msg),
+        }
+    }
+}
+
+// definitions typed in so far, with their types and values
+pub struct Session<W: Write> {
+    types: Env<Type>,
+    subst: Subst,
+    interp: Interpreter<W>,
+}
+
+// whether `input` has unclosed brackets, so that the next line should be appended to it
+pub fn is_incomplete(input: &str) -> bool {
+    let tokens = match lexer::tokenize(input) {
+        Ok(tokens) => tokens,
+        Err(_) => return false,
+    };
+    let depth = tokens
+        .iter()
+        .filter(|token| token.kind == TokenKind::Symbol)
+        .fold(0, |depth, token| match token.text.as_str() {
+            "{" | "(" | "[" => depth + 1,
+            "}" | ")" | "]" => depth - 1,
+            _ => depth,
+        });
+    depth > 0
+}
+
+// names defined at the top level of `e`, with their types
+fn definitions(e: &Expr) -> Vec<(Ident, Type)> {
+    match e {
+        Expr::Let(ref name, ref typ, _, box ref left, _, _) => {
+            let mut defs = definitions(left);
+            if name != &Ident::new("<dummy-sequence>") {
+                defs.insert(0, (name.clone(), typ.clone()));
+            }
+            defs
+        }
+        Expr::Func {
+            ref name,
+            ref param_type,
+            ref ret_type,
+            box ref left,
+            ..
+        } => {
+            let mut defs = definitions(left);
+            defs.insert(
+                0,
+                (
+                    name.clone(),
+                    Type::Func(box param_type.clone(), box ret_type.clone()),
+                ),
+            );
+            defs
+        }
+        Expr::LetType(_, _, box ref left, _) => definitions(left),
+        _ => vec![],
+    }
+}
+
+impl<W: Write> Session<W> {
+    pub fn new(out: W) -> Session<W> {
+        Session {
+            types: Env::new(),
+            subst: Subst::new(),
+            interp: Interpreter::new(out),
+        }
+    }
+
+    pub fn reset(self) -> Session<W> {
+        Session::new(self.interp.out)
+    }
+
+    pub fn output(&self) -> &W {
+        &self.interp.out
+    }
+
+    // handles one input other than `:reset` and `:quit`, returns the lines to print
+    pub fn input(&mut self, input: &str) -> Result<Vec<String>, Error> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        if !input.starts_with(':') {
+            return self.eval_input(input);
+        }
+        let (command, arg) = match input.find(char::is_whitespace) {
+            Some(idx) => (&input[..idx], input[idx..].trim()),
+            None => (input, ""),
+        };
+        match command {
+            ":type" | ":t" => self.type_of(arg),
+            ":load" | ":l" => self.load(&PathBuf::from(arg)),
+            ":help" | ":h" => Ok(HELP.lines().map(String::from).collect()),
+            _ => Err(Error::Command(format!(
+                "unknown command `{}`, see `:help`",
+                command
+            ))),
+        }
+    }
+
+    fn check(&self, src: &str) -> Result<(Expr, Type, Subst), Error> {
+        let file = Path::new("<repl>");
+        let program = parser::program(src)
+            .map_err(|err| Error::Diagnostic(Diagnostic::from_parse_error(file, src, err)))?;
+        if !program.imports.is_empty() {
+            return Err(Error::Command(
+                "`import` is not available in the REPL, use `:load` instead".to_string(),
+            ));
+        }
+        typecheck::check_incremental(program.expr, &self.types, &self.subst)
+            .map_err(|err| Error::Diagnostic(Diagnostic::from_type_error(file, src, err)))
+    }
+
+    fn type_of(&self, src: &str) -> Result<Vec<String>, Error> {
+        let (_, typ, _) = self.check(src)?;
+        Ok(vec![format!("{} : {}", src, typ)])
+    }
+
+    fn eval_input(&mut self, src: &str) -> Result<Vec<String>, Error> {
+        let (e, typ, subst) = self.check(src)?;
+        self.eval(&e, typ)
+            .map_err(|err| Error::Runtime(err, PathBuf::from("<repl>"), src.to_string()))
+            .map(|lines| {
+                self.subst = subst;
+                lines
+            })
+    }
+
+    fn load(&mut self, path: &PathBuf) -> Result<Vec<String>, Error> {
+        let mut lints = vec![];
+        let result = build::read_file(path, &build::entry_modules(path), &mut lints);
+        for diag in lints.iter() {
+            diag.emit(MessageFormat::Human);
+        }
+        let (e, typ) = result.map_err(Error::Diagnostic)?;
+        let src = std::fs::read_to_string(path).unwrap_or_default();
+        let mut lines = vec![format!("loaded {}", path.display())];
+        lines.append(
+            &mut self
+                .eval(&e, typ)
+                .map_err(|err| Error::Runtime(err, path.clone(), src))?,
+        );
+        Ok(lines)
+    }
+
+    // evaluates a typed input and keeps its definitions
+    fn eval(&mut self, e: &Expr, typ: Type) -> Result<Vec<String>, RuntimeError> {
+        let value = self.interp.eval_toplevel(e)?;
+        let mut lines = vec![];
+        for (name, typ) in definitions(e) {
+            lines.push(format!("{} : {}", name, typ));
+            self.types = self.types.add(name, typ);
+        }
+        if let Some(value) = value {
+            lines.push(format!("{} : {}", value, typ));
+        }
+        Ok(lines)
+    }
+}
@@ -0,0 +1,79 @@
+use ast::{Import, Position};
+use config;
+use parser;
+use std::path::{Path, PathBuf};
+
+// the modules of `std`, bundled into the compiler
+const MODULES: &[(&str, &str)] = &[
+    ("array", include_str!("../lib/std/array.mm")),
+    ("char", include_str!("../lib/std/char.mm")),
+    ("math", include_str!("../lib/std/math.mm")),
+    ("option", include_str!("../lib/std/option.mm")),
+    ("prelude", include_str!("../lib/std/prelude.mm")),
+    ("result", include_str!("../lib/std/result.mm")),
+    ("string", include_str!("../lib/std/string.mm")),
+];
+
+// the directory which the files of the modules are named in, which never exists, so that they are
+// read from the compiler instead of the file system
+const DIR: &str = "<std>";
+
+// the source text of the module `std.<name>`
+pub fn source(name: &str) -> Option<&'static str> {
+    MODULES
+        .iter()
+        .find(|(name_, _)| *name_ == name)
+        .map(|(_, src)| *src)
+}
+
+// the file of `import`, if it is a module of `std`, e.g) `<std>/math.mm` of `std.math`
+pub fn find(import: &Import) -> Option<PathBuf> {
+    match import.dirs.as_slice() {
+        [dir] if format!("{}", dir) == "std" => {
+            let name = format!("{}", import.module_name);
+            source(&name).map(|_| Path::new(DIR).join(format!("{}.mm", name)))
+        }
+        _ => None,
+    }
+}
+
+// the source text of the module in `file`, if it is a module of `std`
+pub fn read(file: &Path) -> Option<&'static str> {
+    if file.parent() != Some(Path::new(DIR)) {
+        return None;
+    }
+    source(file.file_stem()?.to_str()?)
+}
+
+// the names of the modules of `std`
+pub fn names() -> impl Iterator<Item = &'static str> {
+    MODULES.iter().map(|(name, _)| *name)
+}
+
+// whether `file` is a module of `std`, which the prelude is never imported into
+pub fn is_std_module(file: &Path) -> bool {
+    read(file).is_some()
+}
+
+// the imports of `std.prelude`, which every module in `file` has implicitly unless
+// `[build] prelude = false`
+pub fn prelude(file: &Path) -> Vec<Import> {
+    if !config::CONFIG.lock().unwrap().build.prelude || is_std_module(file) {
+        return vec![];
+    }
+    // the prelude is bundled, so it is parsable
+    let program = parser::program(source("prelude").unwrap()).unwrap();
+    program
+        .imports
+        .into_iter()
+        .map(|import| Import {
+            pos: Position::dummy(),
+            ..import
+        })
+        .collect()
+}
+
+// whether `import` is one of the prelude, which is not written in the module
+pub fn is_implicit(import: &Import) -> bool {
+    import.pos == Position::dummy()
+}
@@ -1,4 +1,19 @@
 // mod codegen_test;
-// mod eval_test;
+mod diagnostic_test;
+mod doc_test;
+mod eval_test;
+mod export_test;
+mod fmt_test;
+mod graph_test;
+mod lint_test;
+mod lsp_test;
+mod object_test;
+mod package_test;
 mod parse_test;
+mod repl_test;
+mod resolve_test;
+mod semver_test;
+mod stdlib_test;
+mod subst_test;
 mod typecheck_test;
+mod util_test;
@@ -0,0 +1,148 @@
+use ast::Position;
+use diagnostic::{code, Diagnostic, Span};
+use parser;
+use serde_json::{self, json};
+use std::path::Path;
+use typecheck;
+
+fn type_error(src: &str) -> Diagnostic {
+    let program = parser::program(src).unwrap();
+    let err = typecheck::check(program.expr).unwrap_err();
+    Diagnostic::from_type_error(Path::new("main.mm"), src, err)
+}
+
+#[test]
+fn json_shape() {
+    let diag = type_error("let x = 1;\nx + true");
+    assert_eq!(
+        serde_json::to_value(&diag).unwrap(),
+        json!({
+            "severity": "error",
+            "code": "E0004",
+            "message": "unification failed with `Int` and `Bool`",
+            "span": {
+                "file": "main.mm",
+                "byte_start": 13,
+                "byte_end": 14,
+                "line_start": 2,
+                "column_start": 3,
+                "line_end": 2,
+                "column_end": 4,
+            },
+            "related": [],
+            "notes": [],
+        })
+    );
+}
+
+#[test]
+fn json_related_and_notes() {
+    let src = "let a = 1;\nlet b = 2;\n";
+    let diag = Diagnostic::warning(
+        code::SHADOWING,
+        "`a` shadows".to_string(),
+        Some(Span::new(
+            Path::new("main.mm"),
+            src,
+            Position { start: 15, end: 16 },
+        )),
+    )
+    .with_related(
+        "previous definition".to_string(),
+        Span::new(Path::new("main.mm"), src, Position { start: 4, end: 5 }),
+    )
+    .with_suggestion(Some("c".to_string()));
+    let value = serde_json::to_value(&diag).unwrap();
+    assert_eq!(value["severity"], "warning");
+    assert_eq!(value["related"][0]["message"], "previous definition");
+    assert_eq!(value["related"][0]["span"]["column_start"], 5);
+    assert_eq!(value["notes"], json!(["help: did you mean `c`?"]));
+}
+
+#[test]
+fn span_trims_trailing_whitespace() {
+    // positions of the parser include the whitespaces after the expression
+    let src = "let x = 1   \n;x";
+    let span = Span::new(Path::new("main.mm"), src, Position { start: 8, end: 12 });
+    assert_eq!((span.byte_start, span.byte_end), (8, 9));
+    assert_eq!((span.column_start, span.column_end), (9, 10));
+}
+
+#[test]
+fn span_across_lines() {
+    let src = "if true {\n  1\n} else {\n  2\n}";
+    let span = Span::new(Path::new("main.mm"), src, Position { start: 0, end: 28 });
+    assert_eq!((span.line_start, span.column_start), (1, 1));
+    assert_eq!((span.line_end, span.column_end), (5, 2));
+    assert_eq!(span.lines.len(), 5);
+}
+
+#[test]
+fn span_counts_columns_in_chars() {
+    let src = "let s = 'あ'; s + 1";
+    let span = Span::new(Path::new("main.mm"), src, Position { start: 15, end: 20 });
+    assert_eq!(span.column_start, 14);
+}
+
+#[test]
+fn span_out_of_source() {
+    // e.g) a syntax error at the end of the file
+    let span = Span::new(Path::new("main.mm"), "1 +", Position { start: 3, end: 4 });
+    assert_eq!((span.byte_start, span.byte_end), (3, 3));
+    assert_eq!((span.line_start, span.column_start), (1, 4));
+}
+
+#[test]
+fn parse_error_span() {
+    let src = "let x = ;";
+    let err = parser::program(src).unwrap_err();
+    let diag = Diagnostic::from_parse_error(Path::new("main.mm"), src, err);
+    assert_eq!(diag.code, code::SYNTAX_ERROR.id);
+    let span = diag.span.unwrap();
+    assert_eq!((span.byte_start, span.column_start), (8, 9));
+}
+
+#[test]
+fn field_suggestion() {
+    let diag = type_error("let p = {count = 1, total = 2};\np.cuont");
+    assert_eq!(diag.code, code::MISSING_FIELD.id);
+    assert_eq!(diag.notes, vec!["help: did you mean `count`?"]);
+}
+
+#[test]
+fn unbound_var_suggestion() {
+    let diag = type_error("let length = 1;\nlenght + 1");
+    assert_eq!(diag.notes, vec!["help: did you mean `length`?"]);
+}
+
+#[test]
+fn explanation_examples_parse() {
+    for code in code::CODES
+        .iter()
+        .filter(|code| *code != &code::SYNTAX_ERROR)
+    {
+        for (idx, block) in code.explanation.split("```").enumerate() {
+            // the blocks are between the fences
+            if idx % 2 == 0 {
+                continue;
+            }
+            // e.g) `// lib/math.mm` starts a file of the example
+            let mut files = vec![String::new()];
+            for line in block.lines() {
+                if line.starts_with("// ") && line.ends_with(".mm") {
+                    files.push(String::new());
+                } else {
+                    files.last_mut().unwrap().push_str(&format!("{}\n", line));
+                }
+            }
+            for file in files.iter() {
+                assert!(
+                    parser::program(file).is_ok(),
+                    "an example of {} is not a program:\n{}",
+                    code.id,
+                    file
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,44 @@
+use doc::{self, Format, Module};
+use parser;
+use typecheck;
+
+fn module(src: &str) -> Module {
+    let program = parser::program(src).unwrap();
+    let (e, _) = typecheck::check(program.expr).unwrap();
+    Module {
+        name: "main".to_string(),
+        imports: vec!["lib.math".to_string()],
+        items: doc::items(&e, 0),
+    }
+}
+
+#[test]
+fn inferred_signatures() {
+    let module = module("/// one\nlet a = 1;\nfunc inc x: Int { x + a }\ntype t = Bool;\ninc 2");
+    let signatures: Vec<&str> = module
+        .items
+        .iter()
+        .map(|item| item.signature.as_str())
+        .collect();
+    assert_eq!(
+        signatures,
+        vec!["let a: Int", "func inc x: Int :Int", "type t = Bool"]
+    );
+    assert_eq!(module.items[0].doc, Some("one".to_string()));
+    assert_eq!(module.items[1].doc, None);
+}
+
+#[test]
+fn render_markdown() {
+    assert_eq!(
+        doc::render_module(&module("/// adds one\nfunc inc x: Int { x + 1 }\n"), Format::Markdown),
+        "# Module `main`\n\nImports: [`lib.math`](lib.math.md)\n\n## `func inc x: Int :Int`\n\nadds one\n"
+    );
+}
+
+#[test]
+fn render_html_escapes() {
+    let html = doc::render_module(&module("/// a < b\nlet a = 1;\n"), Format::Html);
+    assert!(html.contains("<a href=\"lib.math.html\"><code>lib.math</code></a>"));
+    assert!(html.contains("<p>a &lt; b</p>"));
+}
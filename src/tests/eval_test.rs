@@ -0,0 +1,122 @@
+use ast::Position;
+use eval::{self, RuntimeError};
+use parser;
+use typecheck;
+
+fn run(src: &str) -> (Result<i32, RuntimeError>, String) {
+    let program = parser::program(src).unwrap();
+    let (expr, _) = typecheck::check(program.expr).unwrap();
+    let (status, out) = eval::run(expr, vec![]);
+    (status, String::from_utf8(out).unwrap())
+}
+
+fn status(src: &str) -> i32 {
+    run(src).0.unwrap()
+}
+
+#[test]
+fn arithmetic() {
+    assert_eq!(status("1+2+3*4"), 15);
+    assert_eq!(status("(10 - 4) / 4"), 1);
+    assert_eq!(status("if 1 < 2 { 3 } else { 4 }"), 3);
+    // the exit status of a process is truncated to a byte
+    assert_eq!(status("256 + 42"), 42);
+}
+
+#[test]
+fn func() {
+    assert_eq!(
+        status(
+            r#"
+func fib x: Int :Int {
+    if x < 2 { 1 }
+    else { fib (x-1) + fib (x-2) }
+}
+
+fib 8
+"#
+        ),
+        34
+    );
+    assert_eq!(
+        status("func add {x: Int, y: Int}: Int { x + y } add {x = 12, y = 30}"),
+        42
+    );
+}
+
+#[test]
+fn closure() {
+    assert_eq!(
+        status(
+            r#"
+func hoge {x: Int, y: Int}: Int {
+    (func x_: Int => x_ + y)(x)
+}
+
+hoge {x = 1, y = 11}
+"#
+        ),
+        12
+    );
+}
+
+#[test]
+fn record_and_array() {
+    assert_eq!(status("let a = {hoge = 114, fuga = 514}; a.hoge"), 114);
+    assert_eq!(status("let arr = [42, 32]; arr[1]"), 32);
+    assert_eq!(
+        status("let a = {xs = [1, 2], n = 3}; a.xs[1] <- a.n; a.xs[1] + a.xs[0]"),
+        4
+    );
+    // values are copied, assigning to a copy does not change the original
+    assert_eq!(status("let a = [1]; let b = a; b[0] <- 2; a[0]"), 1);
+}
+
+#[test]
+fn println() {
+    let (status, out) = run("println 1; println (1 < 2); 0");
+    assert_eq!(status, Ok(0));
+    assert_eq!(out, "1\n1\n");
+}
+
+#[test]
+fn runtime_error() {
+    match run("let a = [1, 2]; a[2]").0 {
+        Err(RuntimeError::IndexOutOfBounds { index: 2, len: 2, .. }) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    match run("1 / (1 - 1)").0 {
+        Err(RuntimeError::DivisionByZero { .. }) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    match run("extern func putchar c: Int :Int;\nputchar 65").0 {
+        Err(RuntimeError::Extern { name, .. }) => assert_eq!(format!("{}", name), "putchar"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn assert() {
+    assert_eq!(status("assert (1 < 2); 0"), 0);
+    match run("let a = 1;\nassert a == 2;\na").0 {
+        Err(RuntimeError::AssertionFailed { pos }) => assert_eq!((pos.start, pos.end), (18, 24)),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn deep_recursion() {
+    let src = "func count n: Int :Int { if n == 0 { 0 } else { 1 + count (n - 1) } }\n";
+    assert_eq!(run(&format!("{}count 5000", src)).0, Ok(5000 & 0xff));
+    // reported at the call nested too deeply, instead of overflowing the stack
+    let call = src.rfind("count").unwrap();
+    assert_eq!(
+        run(&format!("{}count 1000000", src)).0,
+        Err(RuntimeError::StackOverflow {
+            pos: Position {
+                start: call,
+                end: call + 14,
+            }
+        })
+    );
+}
@@ -0,0 +1,63 @@
+use codegen;
+use parser;
+use typecheck;
+
+fn exports(src: &str) -> Result<Vec<codegen::Export>, String> {
+    let program = parser::program(src).unwrap();
+    let (e, _) = typecheck::check_module(program.expr).unwrap();
+    codegen::exports(
+        &e,
+        &codegen::lift(e.clone(), &[]),
+        &program.public,
+        "calc",
+    )
+}
+
+#[test]
+fn header() {
+    let exports = exports(
+        "/// adds two numbers\npub func add {b: Int, a: Int} :Int { a + b }\nfunc twice x: Int :Int { x * 2 }\npub func is_even x: Int :Bool { twice x / 2 == x }\npub func reset _: Unit :Unit { unit }\npub type Pair = {x: Int, y: Int};",
+    )
+    .unwrap();
+    assert_eq!(
+        codegen::header("calc", &exports),
+        r#"// the functions of `calc`, generated by `mumrik build --lib`
+#ifndef CALC_H
+#define CALC_H
+
+#include <stdbool.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// adds two numbers
+int32_t calc_add(int32_t a, int32_t b);
+bool calc_is_even(int32_t x);
+void calc_reset(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
+"#
+    );
+}
+
+#[test]
+fn unexportable() {
+    assert_eq!(
+        exports("let k = 1;\npub func f x: Int :Int { x + k }").err(),
+        Some("`f` refers to top-level `let`s, which a library never initializes".to_string())
+    );
+    assert_eq!(
+        exports("pub func f x: Int { {a = x} }").err(),
+        Some("`f` of type `Int -> ({a: Int,})` can not be called from C, which passes only `Int`, `Bool`, `Char`, `Unit` and records of them".to_string())
+    );
+    assert_eq!(
+        exports("pub let k = 1;\npub func f x: Int :Int { x }").err(),
+        Some("`k` is not a function, which a library can not export, declare it without `pub`".to_string())
+    );
+}
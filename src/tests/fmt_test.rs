@@ -0,0 +1,65 @@
+use formatter::format;
+
+#[test]
+fn canonical_layout() {
+    assert_eq!(
+        format("func fib x:Int :Int {if x<2 {1} else {fib (x-1)+fib (x-2)}} fib 8"),
+        Ok("func fib x: Int :Int {\n    if x < 2 { 1 } else { fib (x - 1) + fib (x - 2) }\n}\n\nfib 8\n".to_string())
+    );
+    assert_eq!(
+        format("let a = {x = 1, y = [1,2,]};\na.x"),
+        Ok("let a = {x = 1, y = [1, 2]};\na.x\n".to_string())
+    );
+    assert_eq!(
+        format("let a = {x = 1,\n  y = 2};\na.x"),
+        Ok("let a = {\n    x = 1,\n    y = 2,\n};\na.x\n".to_string())
+    );
+    assert_eq!(
+        format("import  foo . bar;\nbar 1"),
+        Ok("import foo.bar;\n\nbar 1\n".to_string())
+    );
+    assert_eq!(
+        format("import a.b  as  c;\nimport a . b.{x ,y,};\nc.x"),
+        Ok("import a.b as c;\nimport a.b.{x, y};\n\nc.x\n".to_string())
+    );
+    assert_eq!(
+        format("pub  func f x: Int {x}\npub let a=1;\nf a"),
+        Ok("pub func f x: Int {\n    x\n}\n\npub let a = 1;\nf a\n".to_string())
+    );
+}
+
+#[test]
+fn keep_comments() {
+    assert_eq!(
+        format("// head\nlet a = 1;  // one\n\n\n/* two */ let b = 2;\na + b // end\n"),
+        Ok("// head\nlet a = 1; // one\n\n/* two */\nlet b = 2;\na + b // end\n".to_string())
+    );
+}
+
+#[test]
+fn idempotent() {
+    let src = "func f {x: Int, y: Int} :Int {\n    // comment\n    if x < y {\n        x\n    } else {\n        y\n    }\n}\n\nf {x = 1, y = 2}\n";
+    assert_eq!(format(src), Ok(src.to_string()));
+}
+
+#[test]
+fn test_decl() {
+    assert_eq!(
+        format("func id x: Int { x }\ntest  \"id\" {assert (id 1 == 1)}\nid 2"),
+        Ok(
+            "func id x: Int {\n    x\n}\n\ntest \"id\" {\n    assert (id 1 == 1)\n}\n\nid 2\n"
+                .to_string()
+        )
+    );
+}
+
+#[test]
+fn extern_decl() {
+    assert_eq!(
+        format("pub  extern func add {a:Int,b:Int}:Int;\nextern func putchar c:Int :Int;\nputchar 65"),
+        Ok(
+            "pub extern func add {a: Int, b: Int} :Int;\nextern func putchar c: Int :Int;\nputchar 65\n"
+                .to_string()
+        )
+    );
+}
@@ -0,0 +1,156 @@
+use command::build::graph::{self, Graph};
+use diagnostic::Diagnostic;
+use parser;
+use std::path::Path;
+use tempfile;
+
+// the module graph of `main.mm` among `files`, which are given as pairs of the path and the content
+fn build(files: &[(&str, &str)]) -> (tempfile::TempDir, Result<Graph, Diagnostic>) {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, content) in files.iter() {
+        let path = dir.path().join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    let main = dir.path().join("main.mm");
+    let src = std::fs::read_to_string(&main).unwrap();
+    let program = parser::program(&src).unwrap();
+    let entry_modules = vec![dir.path().to_path_buf()];
+    let graph = graph::build(&main, &src, &program, &entry_modules);
+    (dir, graph)
+}
+
+fn file_name<P: AsRef<Path>>(file: &P) -> &str {
+    file.as_ref().file_name().unwrap().to_str().unwrap()
+}
+
+fn cycle(files: &[(&str, &str)]) -> Diagnostic {
+    match build(files) {
+        (_, Ok(_)) => panic!("no import cycle is found"),
+        (_, Err(diag)) => diag,
+    }
+}
+
+#[test]
+fn diamond() {
+    let (_dir, graph) = build(&[
+        ("main.mm", "import lib.b;\nimport lib.c;\nb.f (c.g 1)\n"),
+        (
+            "lib/b.mm",
+            "import lib.d;\npub func f x: Int :Int { d.h x }\n",
+        ),
+        (
+            "lib/c.mm",
+            "import lib.d;\npub func g x: Int :Int { d.h x }\n",
+        ),
+        ("lib/d.mm", "pub func h x: Int :Int { x }\n"),
+    ]);
+    let graph = graph.unwrap();
+    let index = |name: &str| {
+        graph
+            .nodes
+            .iter()
+            .position(|node| node.name == name)
+            .unwrap()
+    };
+    // `lib.d` is read once, before both of its importers
+    let libs: Vec<(&str, &str)> = graph
+        .nodes
+        .iter()
+        .filter(|node| node.name.starts_with("lib."))
+        .map(|node| (node.name.as_str(), file_name(&node.file)))
+        .collect();
+    assert_eq!(
+        libs,
+        vec![("lib.d", "d.mm"), ("lib.b", "b.mm"), ("lib.c", "c.mm")]
+    );
+    let (b, c, d) = (index("lib.b"), index("lib.c"), index("lib.d"));
+    assert!(graph.nodes[b].imports.contains(&d));
+    assert!(graph.nodes[c].imports.contains(&d));
+    assert!(graph.imports.ends_with(&[b, c]));
+}
+
+#[test]
+fn std_from_memory() {
+    let (dir, graph) = build(&[("main.mm", "import std.math;\nmath.abs 1\n")]);
+    let graph = graph.unwrap();
+    // the bundled source is read, and nothing is written for it
+    let math = graph
+        .nodes
+        .iter()
+        .find(|node| node.name == "std.math")
+        .unwrap();
+    assert_eq!(math.file, Path::new("<std>/math.mm"));
+    assert!(math.src.contains("pub func abs"));
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn import_cycle() {
+    let diag = cycle(&[
+        ("main.mm", "import lib.a;\na.f 1\n"),
+        (
+            "lib/a.mm",
+            "import lib.b;\npub func f x: Int :Int { b.g x }\n",
+        ),
+        (
+            "lib/b.mm",
+            "import lib.c;\npub func g x: Int :Int { c.h x }\n",
+        ),
+        (
+            "lib/c.mm",
+            "import lib.a;\npub func h x: Int :Int { a.f x }\n",
+        ),
+    ]);
+    assert_eq!(diag.code, "E0014");
+    // the whole chain from the module back to itself
+    assert_eq!(
+        diag.message,
+        "import cycle: lib.a -> lib.b -> lib.c -> lib.a"
+    );
+    let span = diag.span.unwrap();
+    assert!(span.file.ends_with("c.mm"));
+    assert_eq!((span.line_start, span.column_start), (1, 1));
+    // each import on the way
+    let related: Vec<(&str, &str)> = diag
+        .related
+        .iter()
+        .map(|related| (related.message.as_str(), file_name(&related.span.file)))
+        .collect();
+    assert_eq!(
+        related,
+        vec![
+            ("`lib.b` is imported here", "a.mm"),
+            ("`lib.c` is imported here", "b.mm")
+        ]
+    );
+}
+
+#[test]
+fn import_cycle_through_program() {
+    let diag = cycle(&[
+        (
+            "main.mm",
+            "import lib.a;\npub func g x: Int :Int { x }\na.f 1\n",
+        ),
+        (
+            "lib/a.mm",
+            "import main;\npub func f x: Int :Int { main.g x }\n",
+        ),
+    ]);
+    assert_eq!(diag.code, "E0014");
+    assert_eq!(diag.message, "import cycle: main -> lib.a -> main");
+    assert!(diag.span.unwrap().file.ends_with("a.mm"));
+    assert_eq!(diag.related.len(), 1);
+    assert!(diag.related[0].span.file.ends_with("main.mm"));
+}
+
+#[test]
+fn self_import() {
+    let diag = cycle(&[
+        ("main.mm", "import lib.a;\na.f 1\n"),
+        ("lib/a.mm", "import lib.a;\npub func f x: Int :Int { x }\n"),
+    ]);
+    assert_eq!(diag.message, "import cycle: lib.a -> lib.a");
+    assert!(diag.related.is_empty());
+}
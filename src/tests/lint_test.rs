@@ -0,0 +1,69 @@
+use lint::{self, Lint};
+use parser;
+use typecheck;
+
+fn lints(src: &str) -> Vec<Lint> {
+    let program = parser::program(src).unwrap();
+    let (expr, _) = typecheck::check(program.expr).unwrap();
+    lint::check_program(&expr, 0)
+        .into_iter()
+        .map(|warning| warning.lint)
+        .collect()
+}
+
+#[test]
+fn unused_variable() {
+    assert_eq!(lints("let a = 1; 2"), vec![Lint::UnusedVariable]);
+    assert_eq!(lints("let a = 1; a"), vec![]);
+    assert_eq!(lints("let _a = 1; 2"), vec![]);
+    assert_eq!(
+        lints("func f x: Int :Int { 42 } f 1"),
+        vec![Lint::UnusedVariable]
+    );
+    assert_eq!(
+        lints("func f x: Int :Int { if x < 2 { 1 } else { f (x-1) } } 1"),
+        vec![Lint::UnusedVariable]
+    );
+    assert_eq!(
+        lints("func f {x: Int, y: Int} :Int { x + y } f {x = 1, y = 2}"),
+        vec![]
+    );
+    assert_eq!(lints("func f x: Int :Int { x }"), vec![]);
+    // the inner `a` is used, not the outer one
+    assert_eq!(
+        lints("let a = 1; let a = 2; a"),
+        vec![Lint::UnusedVariable, Lint::Shadowing]
+    );
+}
+
+#[test]
+fn shadowing() {
+    assert_eq!(lints("let a = 1; let a = a + 1; a"), vec![Lint::Shadowing]);
+    assert_eq!(
+        lints("let x = 1; func f x: Int :Int { x } f x"),
+        vec![Lint::Shadowing]
+    );
+}
+
+#[test]
+fn unused_value() {
+    assert_eq!(
+        lints("let a = 1; a + 1; println a"),
+        vec![Lint::UnusedValue]
+    );
+    assert_eq!(lints("let a = 1; println a; a"), vec![]);
+    assert_eq!(lints("let a = [1, 2]; a[0] <- 3; a[0]"), vec![]);
+}
+
+#[test]
+fn unreachable_code() {
+    assert_eq!(
+        lints("if true { 1 } else { 2 }"),
+        vec![Lint::UnreachableCode]
+    );
+    assert_eq!(
+        lints("let a = 1; if false { a } else { 2 }"),
+        vec![Lint::UnreachableCode]
+    );
+    assert_eq!(lints("let a = true; if a { 1 } else { 2 }"), vec![]);
+}
@@ -0,0 +1,79 @@
+use ast::Type;
+use lsp::{self, analysis::Index};
+use parser;
+use serde_json::json;
+use std::path::Path;
+use typecheck;
+
+fn index(src: &str) -> Index {
+    let program = parser::program(src).unwrap();
+    let (expr, _) = typecheck::check(program.expr).unwrap();
+    Index::new(Path::new("main.mm"), src, &expr, true, vec![], vec![])
+}
+
+#[test]
+fn hover() {
+    let src = "let r = {x = 1}; let a = r.x; a";
+    let index = index(src);
+    let symbol = index.symbol_at(src.rfind('a').unwrap()).unwrap();
+    assert_eq!(format!("{}", symbol.name), "a");
+    assert_eq!(symbol.typ, Some(Type::Int));
+    let symbol = index.symbol_at(src.find(".x").unwrap() + 1).unwrap();
+    assert_eq!(format!("{}", symbol.name), "x");
+    assert_eq!(symbol.typ, Some(Type::Int));
+}
+
+#[test]
+fn definition() {
+    let src = "func f x: Int :Int { x + 1 }\nlet y = 2;\nf y";
+    let index = index(src);
+    let call = index.symbol_at(src.rfind('f').unwrap()).unwrap();
+    let definition = call.definition.as_ref().unwrap();
+    assert_eq!(definition.pos.start, src.find('f').unwrap() + 5);
+    assert_eq!(
+        definition.typ,
+        Some(Type::Func(box Type::Int, box Type::Int))
+    );
+    let param = index.symbol_at(src.find("x +").unwrap()).unwrap();
+    assert_eq!(
+        param.definition.as_ref().unwrap().pos.start,
+        src.find("x:").unwrap()
+    );
+    let arg = index.symbol_at(src.rfind('y').unwrap()).unwrap();
+    assert_eq!(
+        arg.definition.as_ref().unwrap().pos.start,
+        src.find("y =").unwrap()
+    );
+}
+
+#[test]
+fn completion() {
+    let src = "let a = if true { let b = 1; b } else { 0 };\na";
+    let index = index(src);
+    let names = |offset| -> Vec<String> {
+        index
+            .visible_at(offset)
+            .into_iter()
+            .map(|definition| format!("{}", definition.name))
+            .collect()
+    };
+    assert_eq!(names(src.rfind('b').unwrap()), vec!["b"]);
+    assert_eq!(names(src.rfind('a').unwrap()), vec!["a"]);
+}
+
+#[test]
+fn read_message() {
+    let input = "Content-Length: 2\r\n\r\n{}\
+                 Content-Length: x\r\n\r\n\
+                 \r\n\
+                 garbage\r\n\r\n\
+                 Content-Length: 8\r\n\r\n{\"id\":1}";
+    let mut reader = input.as_bytes();
+    assert_eq!(lsp::read_message(&mut reader), Some(Ok(json!({}))));
+    // malformed headers are reported one by one, and the following messages are still read
+    assert!(lsp::read_message(&mut reader).unwrap().is_err());
+    assert!(lsp::read_message(&mut reader).unwrap().is_err());
+    assert!(lsp::read_message(&mut reader).unwrap().is_err());
+    assert_eq!(lsp::read_message(&mut reader), Some(Ok(json!({"id": 1}))));
+    assert_eq!(lsp::read_message(&mut reader), None);
+}
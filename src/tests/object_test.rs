@@ -0,0 +1,116 @@
+use ast::{Expr, Type};
+use codegen::{self, Unit};
+use ident::Ident;
+use parser;
+use std::collections::HashMap;
+use typecheck;
+
+// the typed definitions of a module in `src`
+fn definitions(src: &str) -> Expr {
+    let program = parser::program(src).unwrap();
+    typecheck::check_module(program.expr).unwrap().0
+}
+
+fn names(names: &[&str]) -> Vec<Ident> {
+    names.iter().map(|name| Ident::new(name)).collect()
+}
+
+fn record(fields: &[(&str, Type)]) -> Type {
+    Type::Record(
+        fields
+            .iter()
+            .map(|(label, typ)| (Ident::new(label), typ.clone()))
+            .collect::<HashMap<_, _>>(),
+    )
+}
+
+const MATH: &str = "/// adds two numbers\npub func add {x: Int, y: Int} :Int { x + y }\nfunc twice x: Int :Int { x * 2 }\npub type Pair = {x: Int, y: Int};\npub func is_even x: Int :Bool { twice x / 2 == x }\n";
+
+#[test]
+fn interface() {
+    let e = definitions(MATH);
+    let unit = codegen::unit("calc", &e, &names(&["add", "Pair", "is_even"]), &[]).unwrap();
+    assert_eq!(unit.interface.module, "calc");
+    // only the `pub` functions, with their types
+    let symbols: Vec<(String, Type)> = unit
+        .interface
+        .symbols
+        .iter()
+        .map(|symbol| (format!("{}", symbol.name), symbol.typ.clone()))
+        .collect();
+    assert_eq!(
+        symbols,
+        vec![
+            (
+                "add".to_string(),
+                Type::Func(
+                    box record(&[("x", Type::Int), ("y", Type::Int)]),
+                    box Type::Int
+                )
+            ),
+            (
+                "is_even".to_string(),
+                Type::Func(box Type::Int, box Type::Bool)
+            ),
+        ]
+    );
+    assert!(unit.imports.is_empty());
+}
+
+#[test]
+fn unit_with_imports() {
+    let math = codegen::unit("calc", &definitions(MATH), &names(&["add"]), &[]).unwrap();
+    // `add` is of the imported module
+    let e = match definitions(
+        "func add {x: Int, y: Int} :Int { x + y }\npub func inc x: Int :Int { add {x = x, y = 1} }\n",
+    ) {
+        Expr::Func { box left, .. } => left,
+        _ => unreachable!(),
+    };
+    let unit = codegen::unit("counter", &e, &names(&["inc"]), &[Some(&math)]).unwrap();
+    assert_eq!(unit.imports, vec![math.interface.clone()]);
+
+    let mut ll = vec![];
+    codegen::write_unit_llvm_ir(&unit, &mut ll);
+    let ll = String::from_utf8(ll).unwrap();
+    // defines only its own function, and declares the imported one
+    assert!(ll
+        .lines()
+        .any(|line| line.starts_with("define ") && line.contains("@inc(")));
+    assert!(ll
+        .lines()
+        .any(|line| line.starts_with("declare ") && line.contains("@add(")));
+    assert!(!ll
+        .lines()
+        .any(|line| line.starts_with("define ") && line.contains("@add(")));
+}
+
+#[test]
+fn extended_across_object_files() {
+    let e = definitions("pub func is_even x: Int :Bool { x / 2 * 2 == x }\nfunc odd x: Int :Bool { is_even x == false }\n");
+    let unit = codegen::unit("even", &e, &names(&["is_even"]), &[]).unwrap();
+    let mut ll = vec![];
+    codegen::write_unit_llvm_ir(&unit, &mut ll);
+    let ll = String::from_utf8(ll).unwrap();
+    // `Bool` is extended as C does only where other object files call it
+    assert!(ll
+        .lines()
+        .any(|line| line.starts_with("define zeroext i1 @is_even(")));
+    assert!(ll
+        .lines()
+        .any(|line| line.starts_with("define internal i1 @odd(")));
+}
+
+#[test]
+fn units_compiled_with_the_program() {
+    // the types are left to the importer
+    let e = definitions("pub func first a: {arr: _, len: Int} :Int { a.arr[0] }\n");
+    assert!(codegen::unit("first", &e, &names(&["first"]), &[]).is_none());
+    // only `main` evaluates top-level `let`s
+    let e = definitions("pub let k = 1;\npub func get _: Unit :Int { 1 }\n");
+    assert!(codegen::unit("k", &e, &names(&["k", "get"]), &[]).is_none());
+    // nor can a module importing such a module be compiled apart
+    let e = definitions("pub func one _: Unit :Int { 1 }\n");
+    let imports: &[Option<&Unit>] = &[None];
+    assert!(codegen::unit("one", &e, &names(&["one"]), imports).is_none());
+}
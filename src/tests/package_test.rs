@@ -0,0 +1,249 @@
+use config::{Dependency, Manifest};
+use package::sha256::sha256;
+use package::{
+    find_workspace, to_dep, unpack, Lock, LockedPackage, Member, RegistryIndex, Source, Version,
+    VersionReq,
+};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tempfile;
+
+const URL: &str = "file:///tmp/foo";
+
+fn rev(rev: &str) -> Option<String> {
+    Some(rev.to_string())
+}
+
+fn lock() -> Lock {
+    Lock {
+        package: vec![
+            LockedPackage {
+                name: "bar".to_string(),
+                version: Some("0.2.0".to_string()),
+                git: None,
+                rev: None,
+                commit: None,
+                path: Some(PathBuf::from("../bar")),
+                checksum: None,
+            },
+            LockedPackage {
+                name: "foo".to_string(),
+                version: None,
+                git: Some("file:///tmp/foo".to_string()),
+                rev: Some("v1".to_string()),
+                commit: Some("0123abcd".to_string()),
+                path: None,
+                checksum: None,
+            },
+        ],
+    }
+}
+
+#[test]
+fn lockfile_roundtrip() {
+    let lock = lock();
+    let src = lock.to_toml();
+    assert!(src.contains("commit = \"0123abcd\""));
+    assert!(!src.contains("rev = \"\""));
+    assert_eq!(Lock::parse(&src), Ok(lock));
+    assert_eq!(Lock::parse(""), Ok(Lock::default()));
+    assert!(Lock::parse("[[package]]\nname = 1").is_err());
+}
+
+#[test]
+fn pinned_commit() {
+    let lock = lock();
+    assert_eq!(lock.commit("foo", URL, &rev("v1")), Some("0123abcd"));
+    // the pin is dropped once the source in the config changes
+    assert_eq!(lock.commit("foo", URL, &rev("v2")), None);
+    assert_eq!(lock.commit("foo", URL, &None), None);
+    assert_eq!(lock.commit("foo", "file:///tmp/bar", &rev("v1")), None);
+    assert_eq!(lock.commit("baz", URL, &rev("v1")), None);
+}
+
+#[test]
+fn pinned_versions() {
+    let mut expected = BTreeMap::new();
+    expected.insert("bar".to_string(), Version::new(0, 2, 0));
+    assert_eq!(lock().versions(), expected);
+}
+
+fn dependency(git: Option<&str>, path: Option<&str>, version: Option<&str>) -> Dependency {
+    Dependency {
+        git: git.map(String::from),
+        rev: None,
+        path: path.map(PathBuf::from),
+        version: version.map(String::from),
+    }
+}
+
+#[test]
+fn dependency_source() {
+    let dir = Path::new("/nonexistent/app");
+    let dep = to_dep("foo", &dependency(Some(URL), None, None), dir).unwrap();
+    assert_eq!(
+        dep.source,
+        Source::Git {
+            url: URL.to_string(),
+            rev: None
+        }
+    );
+    assert_eq!(dep.req, VersionReq::any());
+
+    // the tags are the versions of a git repository with `version`
+    let dep = to_dep("foo", &dependency(Some(URL), None, Some("^1.2")), dir).unwrap();
+    assert_eq!(dep.source, Source::TaggedGit(URL.to_string()));
+    assert_eq!(dep.req, VersionReq::parse("^1.2").unwrap());
+
+    let dep = to_dep("foo", &dependency(None, Some("../foo"), None), dir).unwrap();
+    assert_eq!(dep.source, Source::Path(dir.join("../foo")));
+
+    let dep = to_dep("foo", &dependency(None, None, Some("1")), dir).unwrap();
+    assert_eq!(dep.source, Source::Registry);
+
+    assert!(to_dep("foo", &dependency(None, None, None), dir).is_err());
+    assert!(to_dep("foo", &dependency(None, None, Some("x.y")), dir).is_err());
+}
+
+#[test]
+fn version_shorthand() {
+    let manifest: Manifest =
+        toml::from_str("[dependencies]\njson = \"1.2\"\nlog = { git = \"file:///tmp/log\" }\n")
+            .unwrap();
+    assert_eq!(
+        manifest.dependencies["json"],
+        dependency(None, None, Some("1.2"))
+    );
+    assert_eq!(
+        manifest.dependencies["log"],
+        dependency(Some("file:///tmp/log"), None, None)
+    );
+    let err = toml::from_str::<Manifest>("[dependencies]\njson = { tag = \"v1\" }\n").unwrap_err();
+    assert!(
+        format!("{}", err).contains("unknown field `tag`"),
+        "{}",
+        err
+    );
+    assert!(toml::from_str::<Manifest>("[dependencies]\njson = 1\n").is_err());
+}
+
+#[test]
+fn checksum() {
+    assert_eq!(
+        sha256(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        sha256(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    // spans two blocks after padding
+    assert_eq!(
+        sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+}
+
+const INDEX: &str = r#"
+[[package]]
+name = "json"
+version = "0.4.0"
+checksum = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+tarball = "json/json-0.4.0.tar.gz"
+
+[package.dependencies]
+log = "^1.2"
+
+[[package]]
+name = "json"
+version = "0.3.0"
+checksum = "0000"
+tarball = "json/json-0.3.0.tar.gz"
+"#;
+
+#[test]
+fn registry_index() {
+    let mut index = RegistryIndex::parse(INDEX).unwrap();
+    let entry = index.find("json", &Version::new(0, 4, 0)).unwrap().clone();
+    assert_eq!(
+        entry.dependencies["log"],
+        dependency(None, None, Some("^1.2"))
+    );
+    assert_eq!(index.versions("json").count(), 2);
+    assert!(index.find("json", &Version::new(0, 5, 0)).is_none());
+    assert!(index.add(entry.clone()).is_err());
+
+    let mut newer = entry.clone();
+    newer.version = "0.10.0".to_string();
+    index.add(newer).unwrap();
+    let versions: Vec<&str> = index
+        .versions("json")
+        .map(|entry| entry.version.as_str())
+        .collect();
+    assert_eq!(versions, vec!["0.3.0", "0.4.0", "0.10.0"]);
+    assert_eq!(
+        RegistryIndex::parse(&toml::to_string(&index).unwrap()),
+        Ok(index)
+    );
+}
+
+#[test]
+fn corrupted_tarball() {
+    let registry = tempfile::tempdir().unwrap();
+    let index = RegistryIndex::parse(INDEX).unwrap();
+    std::fs::create_dir(registry.path().join("json")).unwrap();
+    std::fs::write(registry.path().join("json/json-0.3.0.tar.gz"), b"").unwrap();
+    let dest = registry.path().join("json-0.3.0");
+    let err = unpack(registry.path(), &index.package[1], &dest).unwrap_err();
+    assert!(err.contains("checksum"), "{}", err);
+    assert!(!dest.exists());
+}
+
+#[test]
+fn workspace_members() {
+    let root = tempfile::tempdir().unwrap();
+    let root = root.path().canonicalize().unwrap();
+    let write = |path: &str, src: &str| {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, src).unwrap();
+    };
+    write(
+        "mumrik-conf.toml",
+        "[workspace]\nmembers = [\"app\", \"libs/util\"]\n\n[build]\nsrc = \"main.mm\"\noutput = \"a.out\"\ndep = \"dep\"\n",
+    );
+    write(
+        "app/mumrik-conf.toml",
+        "[package]\nname = \"my-app\"\nversion = \"0.1.0\"\n",
+    );
+    write("libs/util/mumrik-conf.toml", "");
+    write("other/mumrik-conf.toml", "");
+
+    let workspace = find_workspace(&root.join("app")).unwrap().unwrap();
+    assert_eq!(workspace.root, root);
+    assert_eq!(
+        workspace.members,
+        vec![
+            Member {
+                name: "my-app".to_string(),
+                dir: root.join("app"),
+            },
+            Member {
+                name: "util".to_string(),
+                dir: root.join("libs/util"),
+            },
+        ]
+    );
+    assert_eq!(workspace.dep_dir, root.join("dep"));
+    assert_eq!(find_workspace(&root).unwrap(), Some(workspace));
+    assert_eq!(find_workspace(&root.join("other")).unwrap(), None);
+
+    write(
+        "mumrik-conf.toml",
+        "[workspace]\nmembers = [\"app\", \"lib\"]\n",
+    );
+    assert_eq!(
+        find_workspace(&root.join("app")).unwrap_err(),
+        "workspace member `lib` has no `mumrik-conf.toml`"
+    );
+}
@@ -1,4 +1,4 @@
-use ast::{self, Expr::*, Literal::*, Position, Program, Type};
+use ast::{self, Expr::*, Import, Literal::*, Position, Program, Test, Type};
 use ident::Ident;
 use parser::*;
 
@@ -20,6 +20,8 @@ fn primitive_literal() {
         program("123"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Const(Number(123)),
         })
     );
@@ -27,6 +29,8 @@ fn primitive_literal() {
         program("true"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Const(Bool(true)),
         })
     );
@@ -34,6 +38,8 @@ fn primitive_literal() {
         program("false"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Const(Bool(false)),
         })
     );
@@ -41,6 +47,8 @@ fn primitive_literal() {
         program("unit"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Const(Unit),
         })
     );
@@ -48,6 +56,8 @@ fn primitive_literal() {
         program("a"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Var(
                 Ident::new("a"),
                 Type::Var(Ident::new("<fresh-expected>")),
@@ -63,6 +73,8 @@ fn apply() {
         program("(func x:Int :Int => x) 1"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Apply(
                 box Func {
                     name: Ident::new("<fresh-expected>"),
@@ -79,7 +91,8 @@ fn apply() {
                         Type::Func(box Type::Int, box Type::Int),
                         Position { start: 1, end: 21 }
                     ),
-                    pos: Position { start: 1, end: 21 }
+                    pos: Position { start: 1, end: 21 },
+                    doc: None
                 },
                 box Const(Number(1)),
                 Position { start: 0, end: 24 }
@@ -94,6 +107,8 @@ fn sequence() {
         program("1; 2; 3"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Let(
                 Ident::new("<dummy-sequence>"),
                 Type::Var(Ident::new("<fresh-expected>")),
@@ -103,9 +118,11 @@ fn sequence() {
                     Type::Var(Ident::new("<fresh-expected>")),
                     box Const(Number(2)),
                     box Const(Number(3)),
-                    Position { start: 3, end: 4 }
+                    Position { start: 3, end: 4 },
+                    None
                 ),
-                Position { start: 0, end: 1 }
+                Position { start: 0, end: 1 },
+                None
             )
         })
     );
@@ -117,6 +134,8 @@ fn if_() {
         program("if true { 1 } else { 2 }"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: If(
                 box Const(Bool(true)),
                 box Const(Number(1)),
@@ -133,6 +152,8 @@ fn arithmetic() {
         program("1+2*5+6"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: BinOp(
                 ast::BinOp::Add,
                 box BinOp(
@@ -159,6 +180,8 @@ fn compare() {
         program("1 < 2"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: BinOp(
                 ast::BinOp::Lt,
                 box Const(Number(1)),
@@ -171,6 +194,8 @@ fn compare() {
         program("1 > 2"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: BinOp(
                 ast::BinOp::Gt,
                 box Const(Number(1)),
@@ -187,6 +212,8 @@ fn record() {
         program("{ id=42, value=123 }"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Const(Record(hashmap! {
                 Ident::new("id") => Const(Number(42)),
                 Ident::new("value") => Const(Number(123))
@@ -201,6 +228,8 @@ fn tuple() {
         program("(1, 2, 3)"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Const(Record(hashmap! {
                 Ident::new("0") => Const(Number(1)),
                 Ident::new("1") => Const(Number(2)),
@@ -216,6 +245,8 @@ fn record_get() {
         program("{id=42}.id"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: RecordGet(
                 box Const(Record(hashmap! {Ident::new("id") => Const(Number(42))})),
                 Type::Var(Ident::new("<fresh-expected>")),
@@ -232,6 +263,8 @@ fn record_assign() {
         program("let x = {hoge=12, fuga=32}; x.hoge <- 42"),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Let(
                 Ident::new("x"),
                 Type::Var(Ident::new("<fresh-expected>")),
@@ -253,7 +286,8 @@ fn record_assign() {
                     box Const(Number(42)),
                     Position { start: 35, end: 38 }
                 ),
-                Position { start: 0, end: 28 }
+                Position { start: 0, end: 28 },
+                None
             ),
         })
     );
@@ -272,6 +306,8 @@ f 13
         ),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Func {
                 name: Ident::new("f"),
                 param_name: Ident::new("a"),
@@ -296,7 +332,8 @@ f 13
                     box Const(Number(13)),
                     Position { start: 34, end: 39 }
                 ),
-                pos: Position { start: 1, end: 34 }
+                pos: Position { start: 1, end: 34 },
+                doc: None
             },
         })
     );
@@ -319,6 +356,8 @@ fib 3
         ),
         Ok(Program {
             imports: vec![],
+            tests: vec![],
+            public: vec![],
             expr: Func {
                 name: Ident::new("fib"),
                 param_name: Ident::new("x"),
@@ -393,7 +432,8 @@ fib 3
                         end: 105
                     }
                 ),
-                pos: Position { start: 1, end: 99 }
+                pos: Position { start: 1, end: 99 },
+                doc: None
             }
         })
     );
@@ -405,7 +445,195 @@ fn let_type_func() {
         program("type a = Int; 42"),
         Ok(Program {
             imports: vec![],
-            expr: LetType(Ident::new("a"), Type::Int, box Const(Number(42)))
+            tests: vec![],
+            public: vec![],
+            expr: LetType(Ident::new("a"), Type::Int, box Const(Number(42)), None)
+        })
+    );
+}
+
+#[test]
+fn test_decl() {
+    assert_eq!(
+        program("test \"one\" { assert 1 == 1 } 42"),
+        Ok(Program {
+            imports: vec![],
+            tests: vec![Test {
+                name: "one".to_string(),
+                body: Assert(
+                    box BinOp(
+                        ast::BinOp::Eq,
+                        box Const(Number(1)),
+                        box Const(Number(1)),
+                        Position { start: 22, end: 25 }
+                    ),
+                    Position { start: 20, end: 27 }
+                ),
+                pos: Position { start: 0, end: 29 }
+            }],
+            public: vec![],
+            expr: Const(Number(42))
+        })
+    );
+}
+
+// the names and the doc comments of the top-level definitions of `e`
+fn docs(e: &ast::Expr) -> Vec<(Ident, Option<String>)> {
+    match e {
+        Func {
+            ref name,
+            box ref left,
+            ref doc,
+            ..
+        }
+        | Let(ref name, _, _, box ref left, _, ref doc)
+        | LetType(ref name, _, box ref left, ref doc) => {
+            let mut docs = docs(left);
+            docs.insert(0, (name.clone(), doc.clone()));
+            docs
+        }
+        _ => vec![],
+    }
+}
+
+#[test]
+fn doc_comments() {
+    let program = program(
+        "/// adds one\n/// to `x`\nfunc inc x: Int { /// not a doc\n let a = x; a + 1 }\n// plain\nlet b = 1;\n/// answer\n\n  type t = Int;\n//// not a doc\nlet c = 2;\ninc b",
+    )
+    .unwrap();
+    assert_eq!(
+        docs(&program.expr),
+        vec![
+            (Ident::new("inc"), Some("adds one\nto `x`".to_string())),
+            (Ident::new("b"), None),
+            (Ident::new("t"), Some("answer".to_string())),
+            (Ident::new("c"), None),
+        ]
+    );
+}
+
+#[test]
+fn imports() {
+    let program = program("import a.b;\nimport a.b as c;\nimport a.b.{x, y};\n1").unwrap();
+    assert_eq!(
+        program.imports,
+        vec![
+            Import {
+                dirs: vec![Ident::new("a")],
+                module_name: Ident::new("b"),
+                alias: Ident::new("b"),
+                items: None,
+                pos: Position { start: 0, end: 10 }
+            },
+            Import {
+                dirs: vec![Ident::new("a")],
+                module_name: Ident::new("b"),
+                alias: Ident::new("c"),
+                items: None,
+                pos: Position { start: 12, end: 27 }
+            },
+            Import {
+                dirs: vec![Ident::new("a")],
+                module_name: Ident::new("b"),
+                alias: Ident::new("b"),
+                items: Some(vec![Ident::new("x"), Ident::new("y")]),
+                pos: Position { start: 29, end: 46 }
+            },
+        ]
+    );
+}
+
+#[test]
+fn qualified_type() {
+    assert_eq!(
+        program("let p: geometry.Point = 1; p").map(|program| program.expr),
+        Ok(Let(
+            Ident::new("p"),
+            Type::Var(Ident::new("geometry.Point")),
+            box Const(Number(1)),
+            box Var(
+                Ident::new("p"),
+                Type::Var(Ident::new("<fresh-expected>")),
+                Position { start: 27, end: 28 }
+            ),
+            Position { start: 0, end: 27 },
+            None
+        ))
+    );
+}
+
+#[test]
+fn public_definitions() {
+    let program = program(
+        "/// exported\npub func f x: Int { x }\nlet a = 1;\npub let b = 2;\npub type t = Int;\nf a",
+    )
+    .unwrap();
+    assert_eq!(
+        program.public,
+        vec![Ident::new("f"), Ident::new("b"), Ident::new("t")]
+    );
+    assert_eq!(docs(&program.expr)[0], (Ident::new("f"), Some("exported".to_string())));
+}
+
+#[test]
+fn toplevel_let_before_expression() {
+    // a top-level `let` directly followed by the final expression keeps its `pub` and its doc
+    let parsed = program("/// answer\npub let x = 42;\nx").unwrap();
+    assert_eq!(parsed.public, vec![Ident::new("x")]);
+    assert_eq!(
+        docs(&parsed.expr),
+        vec![(Ident::new("x"), Some("answer".to_string()))]
+    );
+    // the initializer is never a sequence, as in a nested `let`
+    match program("let x = 1; 2; x").map(|program| program.expr) {
+        Ok(Let(_, _, box Const(Number(1)), box Let(ref name, _, _, _, _, _), _, _)) => {
+            assert_eq!(name, &Ident::new("<dummy-sequence>"))
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn extern_func() {
+    let pos = Position { start: 0, end: 31 };
+    assert_eq!(
+        program("extern func putchar c: Int :Int;").map(|program| program.expr),
+        Ok(Func {
+            name: Ident::new("putchar"),
+            param_name: Ident::new("c"),
+            param_type: Type::Int,
+            ret_type: Type::Int,
+            body: box Extern(
+                Ident::new("putchar"),
+                vec![Var(Ident::new("c"), Type::Int, pos)],
+                Type::Int,
+                pos
+            ),
+            left: box EmptyMark,
+            pos: pos,
+            doc: None,
         })
     );
+    // the arguments are in the order of the parameters, except for `Unit`
+    match program("extern func f {b: Int, a: Char, u: Unit} :Unit;").map(|program| program.expr) {
+        Ok(Func {
+            param_name,
+            body: box Extern(_, args, Type::Unit, _),
+            ..
+        }) => {
+            assert!(param_name.is_omitted_param_name());
+            let names: Vec<_> = args
+                .into_iter()
+                .map(|arg| match arg {
+                    Var(name, _, _) => name,
+                    arg => panic!("unexpected argument: {:?}", arg),
+                })
+                .collect();
+            assert_eq!(names, vec![Ident::new("b"), Ident::new("a")]);
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert!(program("extern func f x: {a: Int} :Int;").is_err());
+    assert!(program("extern func f x: Int;").is_err());
 }
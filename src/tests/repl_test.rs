@@ -0,0 +1,41 @@
+use repl::Session;
+
+fn run(inputs: &[&str]) -> (Vec<String>, String) {
+    let mut session = Session::new(vec![]);
+    let mut lines = vec![];
+    for input in inputs {
+        lines.append(&mut session.input(input).unwrap());
+    }
+    let out = String::from_utf8(session.output().clone()).unwrap();
+    (lines, out)
+}
+
+#[test]
+fn definitions() {
+    let (lines, _) = run(&["let x = 1;", "func f y: Int :Int { y + x }", "f 41"]);
+    assert_eq!(lines, vec!["x : Int", "f : Int -> (Int)", "42 : Int"]);
+}
+
+#[test]
+fn accumulated_subst() {
+    // the parameter type of `id` is decided by a later input
+    let (lines, _) = run(&["func id x: t :t { x }", "id 1", ":type id"]);
+    assert_eq!(lines[1..], ["1 : Int", "id : Int -> (Int)"]);
+}
+
+#[test]
+fn assign_and_println() {
+    let (lines, out) = run(&["let r = {n = 1};", "r.n <- 2; println r.n", "r.n + 1"]);
+    assert_eq!(lines.last().unwrap(), "3 : Int");
+    assert_eq!(out, "2\n");
+}
+
+#[test]
+fn errors_keep_session() {
+    let mut session = Session::new(vec![]);
+    assert!(session.input("let x = 1 / 0;").is_err());
+    assert!(session.input("x").is_err());
+    assert!(session.input("let x = true;").is_ok());
+    assert!(session.input("x + 1").is_err());
+    assert_eq!(session.input("x").unwrap(), vec!["true : Bool"]);
+}
@@ -0,0 +1,173 @@
+use package::{resolve, Candidate, Dep, Index, Source, Version, VersionReq};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+#[derive(Default)]
+struct MemoryIndex {
+    packages: HashMap<String, Vec<Candidate>>,
+}
+
+impl MemoryIndex {
+    fn add(&mut self, name: &str, version: &str, deps: &[(&str, &str)]) {
+        let candidate = Candidate {
+            version: Version::parse(version).unwrap(),
+            deps: deps.iter().map(|(name, req)| dep(name, req)).collect(),
+            commit: None,
+            dir: None,
+            checksum: None,
+        };
+        self.packages
+            .entry(name.to_string())
+            .or_insert_with(Vec::new)
+            .push(candidate);
+    }
+}
+
+impl Index for MemoryIndex {
+    fn candidates(&mut self, name: &str, _: &Source) -> Result<Vec<Candidate>, String> {
+        Ok(self.packages.get(name).cloned().unwrap_or_default())
+    }
+}
+
+fn dep(name: &str, req: &str) -> Dep {
+    Dep {
+        name: name.to_string(),
+        source: Source::Registry,
+        req: VersionReq::parse(req).unwrap(),
+    }
+}
+
+fn versions(
+    index: &mut MemoryIndex,
+    deps: &[Dep],
+    preferred: &BTreeMap<String, Version>,
+) -> Result<Vec<String>, String> {
+    let resolution = resolve("app", deps, index, preferred)?;
+    Ok(resolution
+        .packages
+        .values()
+        .map(|package| format!("{} {}", package.name, package.candidate.version))
+        .collect())
+}
+
+fn index() -> MemoryIndex {
+    let mut index = MemoryIndex::default();
+    index.add("log", "1.0.0", &[]);
+    index.add("log", "1.4.0", &[]);
+    index.add("log", "2.0.0", &[]);
+    index.add("json", "0.3.0", &[("log", "^1.0")]);
+    index.add("json", "0.4.0", &[("log", "^1.2")]);
+    index.add("http", "1.0.0", &[("json", "^0.4"), ("log", "^1")]);
+    index
+}
+
+#[test]
+fn transitive() {
+    let mut index = index();
+    assert_eq!(
+        versions(&mut index, &[dep("http", "1")], &BTreeMap::new()),
+        Ok(vec![
+            "http 1.0.0".to_string(),
+            "json 0.4.0".to_string(),
+            "log 1.4.0".to_string()
+        ])
+    );
+}
+
+#[test]
+fn shared_requirements() {
+    let mut index = index();
+    // `log 2.0.0` is the highest, but `json` requires `^1.2`
+    assert_eq!(
+        versions(
+            &mut index,
+            &[dep("json", "*"), dep("log", "*")],
+            &BTreeMap::new()
+        ),
+        Ok(vec!["json 0.4.0".to_string(), "log 1.4.0".to_string()])
+    );
+    let err = versions(
+        &mut index,
+        &[dep("json", "0.4"), dep("log", "2")],
+        &BTreeMap::new(),
+    )
+    .unwrap_err();
+    assert!(
+        err.contains("conflicting requirements for `log`"),
+        "{}",
+        err
+    );
+    assert!(err.contains("`^2` required by `app`"), "{}", err);
+    assert!(err.contains("`^1.2` required by `json v0.4.0`"), "{}", err);
+    assert!(err.contains("1.0.0, 1.4.0, 2.0.0"), "{}", err);
+}
+
+#[test]
+fn unsatisfied() {
+    let mut index = index();
+    let err = versions(&mut index, &[dep("log", "^3")], &BTreeMap::new()).unwrap_err();
+    assert!(
+        err.contains("no version of `log` matches `^3` required by `app`"),
+        "{}",
+        err
+    );
+    let err = versions(&mut index, &[dep("xml", "*")], &BTreeMap::new()).unwrap_err();
+    assert!(err.contains("no version of `xml` is found"), "{}", err);
+}
+
+#[test]
+fn preferred_versions() {
+    let mut index = index();
+    let mut preferred = BTreeMap::new();
+    preferred.insert("log".to_string(), Version::new(1, 0, 0));
+    assert_eq!(
+        versions(&mut index, &[dep("log", "1")], &preferred),
+        Ok(vec!["log 1.0.0".to_string()])
+    );
+    // the preferred version is given up when it does not match any more
+    assert_eq!(
+        versions(&mut index, &[dep("log", "1.2")], &preferred),
+        Ok(vec!["log 1.4.0".to_string()])
+    );
+}
+
+#[test]
+fn different_sources() {
+    let mut index = index();
+    index.add("json", "0.5.0", &[]);
+    let mut path_log = dep("log", "*");
+    path_log.source = Source::Path(PathBuf::from("/tmp/log"));
+    let err = versions(
+        &mut index,
+        &[dep("json", "0.3"), path_log],
+        &BTreeMap::new(),
+    )
+    .unwrap_err();
+    assert!(
+        err.contains("`log` is required from different sources"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn tree() {
+    let mut index = index();
+    let resolution = resolve(
+        "app v0.1.0",
+        &[dep("http", "1"), dep("json", "*")],
+        &mut index,
+        &BTreeMap::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        resolution.render_tree("app v0.1.0"),
+        "app v0.1.0
+├── http v1.0.0 (registry)
+│   ├── json v0.4.0 (registry)
+│   │   └── log v1.4.0 (registry)
+│   └── log v1.4.0 (registry)
+└── json v0.4.0 (registry) (*)
+"
+    );
+}
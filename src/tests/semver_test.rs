@@ -0,0 +1,65 @@
+use package::{Version, VersionReq};
+
+fn matches(req: &str, version: &str) -> bool {
+    VersionReq::parse(req)
+        .unwrap()
+        .matches(&Version::parse(version).unwrap())
+}
+
+#[test]
+fn parse_version() {
+    assert_eq!(Version::parse("1.2.3"), Ok(Version::new(1, 2, 3)));
+    assert_eq!(format!("{}", Version::new(0, 10, 2)), "0.10.2");
+    assert!(Version::parse("1.2").is_err());
+    assert!(Version::parse("1.2.3.4").is_err());
+    assert!(Version::parse("1.02.3").is_err());
+    assert!(Version::parse("1.x.3").is_err());
+    assert!(Version::new(1, 10, 0) > Version::new(1, 9, 9));
+}
+
+#[test]
+fn caret() {
+    assert!(matches("^1.2", "1.2.0"));
+    assert!(matches("^1.2", "1.9.3"));
+    assert!(!matches("^1.2", "1.1.9"));
+    assert!(!matches("^1.2", "2.0.0"));
+    // a bare version is a caret requirement
+    assert!(matches("1.2.3", "1.4.0"));
+    assert!(!matches("1.2.3", "1.2.2"));
+    assert!(matches("^0.2.1", "0.2.5"));
+    assert!(!matches("^0.2.1", "0.3.0"));
+    assert!(matches("^0.0.3", "0.0.3"));
+    assert!(!matches("^0.0.3", "0.0.4"));
+}
+
+#[test]
+fn tilde_and_comparisons() {
+    assert!(matches("~1.2", "1.2.7"));
+    assert!(!matches("~1.2", "1.3.0"));
+    assert!(matches("~1", "1.9.0"));
+    assert!(matches("=1.2", "1.2.9"));
+    assert!(!matches("=1.2.0", "1.2.1"));
+    assert!(matches(">1.2", "1.3.0"));
+    assert!(!matches(">1.2", "1.2.5"));
+    assert!(matches("<=1.2", "1.2.5"));
+    assert!(!matches("<1.2", "1.2.0"));
+    assert!(matches(">=1.0, <2.0", "1.5.0"));
+    assert!(!matches(">=1.0, <2.0", "2.0.0"));
+    assert!(matches("*", "0.0.1"));
+}
+
+#[test]
+fn parse_requirement() {
+    assert_eq!(
+        format!("{}", VersionReq::parse(">=1.0,  <2").unwrap()),
+        ">=1.0, <2"
+    );
+    assert_eq!(format!("{}", VersionReq::parse("1.2").unwrap()), "^1.2");
+    assert_eq!(
+        format!("{}", VersionReq::parse("=1.2.3").unwrap()),
+        "=1.2.3"
+    );
+    assert!(VersionReq::parse("").is_err());
+    assert!(VersionReq::parse("^1.x").is_err());
+    assert!(VersionReq::parse("1.2.3.4").is_err());
+}
@@ -0,0 +1,93 @@
+use eval;
+use parser;
+use stdlib;
+use typecheck;
+
+// the status of the program `src` following the definitions of `std.<module>`,
+// some of which are left generic as `src` does not use them
+fn run_with(module: &str, src: &str) -> i32 {
+    let src = format!("{}\n{}", stdlib::source(module).unwrap(), src);
+    let program = parser::program(&src).unwrap();
+    let (expr, _) = typecheck::check_module(program.expr).unwrap();
+    eval::run(expr, vec![]).0.unwrap()
+}
+
+#[test]
+fn modules() {
+    for module in ["array", "char", "math", "option", "prelude", "result", "string"].iter() {
+        let program = parser::program(stdlib::source(module).unwrap()).unwrap();
+        // `std.string` refers to `std.char`, which is checked with its importers
+        if program.imports.is_empty() {
+            assert!(typecheck::check_module(program.expr).is_ok(), "{}", module);
+        }
+    }
+    assert_eq!(stdlib::source("io"), None);
+}
+
+#[test]
+fn prelude() {
+    let prelude = parser::program(stdlib::source("prelude").unwrap()).unwrap();
+    assert!(!prelude.imports.is_empty());
+    for import in prelude.imports.iter() {
+        assert_eq!(format!("{}", import.dirs[0]), "std");
+        let module = format!("{}", import.module_name);
+        let program = parser::program(stdlib::source(&module).unwrap()).unwrap();
+        for item in import.items.as_ref().unwrap() {
+            assert!(program.public.contains(item), "{}.{}", module, item);
+        }
+    }
+}
+
+#[test]
+fn math() {
+    assert_eq!(run_with("math", "abs (0 - 3)"), 3);
+    assert_eq!(run_with("math", "0 - sign (0 - 3)"), 1);
+    assert_eq!(run_with("math", "clamp {x = 12, low = 0, high = 10}"), 10);
+    assert_eq!(run_with("math", "pow {base = 3, exp = 5}"), 243);
+    assert_eq!(run_with("math", "0 - rem {a = 0 - 7, b = 2}"), 1);
+    assert_eq!(run_with("math", "gcd {a = 12, b = 0 - 18}"), 6);
+    assert_eq!(run_with("math", "lcm {a = 4, b = 6}"), 12);
+    assert_eq!(run_with("math", "if is_odd 7 { 1 } else { 0 }"), 1);
+}
+
+#[test]
+fn char() {
+    assert_eq!(run_with("char", "digit_value '7'"), 7);
+    assert_eq!(run_with("char", "0 - digit_value 'x'"), 1);
+    assert_eq!(run_with("char", "if digit_char 4 == '4' { 1 } else { 0 }"), 1);
+    assert_eq!(run_with("char", "if digit_char 10 == '?' { 1 } else { 0 }"), 1);
+    assert_eq!(run_with("char", "if digit_char (0 - 1) == '?' { 1 } else { 0 }"), 1);
+    assert_eq!(run_with("char", "if is_alpha 'Q' { 1 } else { 0 }"), 1);
+    assert_eq!(run_with("char", "if is_alnum '_' { 1 } else { 0 }"), 0);
+    assert_eq!(run_with("char", "if is_space 'a' { 1 } else { 0 }"), 0);
+}
+
+#[test]
+fn array() {
+    let xs = "let xs = {arr = [3, 5, 2, 5], len = 4};\n";
+    assert_eq!(run_with("array", &format!("{}sum xs", xs)), 15);
+    assert_eq!(run_with("array", &format!("{}product xs", xs)), 150);
+    assert_eq!(run_with("array", &format!("{}maximum xs", xs)), 5);
+    assert_eq!(run_with("array", &format!("{}minimum xs", xs)), 2);
+    assert_eq!(run_with("array", &format!("{}index_of {{a = xs, x = 5}}", xs)), 1);
+    assert_eq!(run_with("array", &format!("{}count {{a = xs, x = 5}}", xs)), 2);
+    assert_eq!(
+        run_with(
+            "array",
+            "if contains {a = {arr = ['a', 'b'], len = 2}, x = 'c'} { 1 } else { 0 }"
+        ),
+        0
+    );
+}
+
+#[test]
+fn option_and_result() {
+    assert_eq!(run_with("option", "unwrap_or {opt = some 3, default = 0}"), 3);
+    assert_eq!(run_with("option", "unwrap_or {opt = none unit, default = 7}"), 7);
+    assert_eq!(
+        run_with("option", "(or {opt = none unit, other = some 2}).value"),
+        2
+    );
+    assert_eq!(run_with("result", "error_or {res = err 4, default = 0}"), 4);
+    assert_eq!(run_with("result", "if is_ok (ok 1) { 1 } else { 0 }"), 1);
+}
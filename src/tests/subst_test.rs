@@ -0,0 +1,46 @@
+use ast::{Expr, Position};
+use ident::Ident;
+use parser;
+use std::collections::HashMap;
+
+fn parse(src: &str) -> Expr {
+    parser::program(src).unwrap().expr
+}
+
+fn var_names(e: &Expr) -> Vec<String> {
+    match e {
+        Expr::Var(ref name, _, _) => vec![format!("{}", name)],
+        _ => e.children().into_iter().flat_map(var_names).collect(),
+    }
+}
+
+#[test]
+fn rename_var() {
+    let e = parse("let a = x; func f x: Int { x } let x = x + 1; x").rename_var(
+        &Ident::new("x"),
+        &Ident::new("m.x"),
+    );
+    assert_eq!(var_names(&e), vec!["m.x", "x", "m.x", "x"]);
+}
+
+#[test]
+fn rename_var_keeps_position() {
+    match parse("x").rename_var(&Ident::new("x"), &Ident::new("m.x")) {
+        Expr::Var(name, _, pos) => {
+            assert_eq!(name, Ident::new("m.x"));
+            assert_eq!(pos, Position { start: 0, end: 1 });
+        }
+        e => panic!("unexpected {:?}", e),
+    }
+}
+
+#[test]
+fn qualify() {
+    let mut members = HashMap::new();
+    members.insert(Ident::new("gcd"), Ident::new("lib.math.gcd"));
+    let e = parse("let a = math.gcd; let b = math.lcm; let math = {gcd = a}; math.gcd").qualify(
+        &Ident::new("math"),
+        &members,
+    );
+    assert_eq!(var_names(&e), vec!["lib.math.gcd", "math.lcm", "a", "math"]);
+}
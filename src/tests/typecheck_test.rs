@@ -1,5 +1,6 @@
 use ast::*;
 use ident::Ident;
+use parser;
 use typecheck;
 
 #[test]
@@ -25,7 +26,8 @@ fn primitive_literal() {
                 Type::Func(box Type::Int, box Type::Int),
                 Position { start: 0, end: 0 }
             ),
-            pos: Position { start: 0, end: 0 }
+            pos: Position { start: 0, end: 0 },
+            doc: None
         }),
         Ok((
             Expr::Func {
@@ -39,7 +41,8 @@ fn primitive_literal() {
                     Type::Func(box Type::Int, box Type::Int),
                     Position { start: 0, end: 0 }
                 ),
-                pos: Position { start: 0, end: 0 }
+                pos: Position { start: 0, end: 0 },
+                doc: None
             },
             Type::Func(box Type::Int, box Type::Int)
         ))
@@ -66,7 +69,8 @@ fn apply() {
                     Type::Func(box Type::Int, box Type::Int),
                     Position { start: 0, end: 0 }
                 ),
-                pos: Position { start: 0, end: 0 }
+                pos: Position { start: 0, end: 0 },
+                doc: None
             },
             box Expr::Const(Literal::Number(42)),
             Position { start: 0, end: 0 }
@@ -84,7 +88,8 @@ fn apply() {
                         Type::Func(box Type::Int, box Type::Int),
                         Position { start: 0, end: 0 }
                     ),
-                    pos: Position { start: 0, end: 0 }
+                    pos: Position { start: 0, end: 0 },
+                    doc: None
                 },
                 box Expr::Const(Literal::Number(42)),
                 Position { start: 0, end: 0 }
@@ -110,7 +115,8 @@ fn apply() {
                     Type::Var(Ident::new("d")),
                     Position { start: 0, end: 0 }
                 ),
-                pos: Position { start: 0, end: 0 }
+                pos: Position { start: 0, end: 0 },
+                doc: None
             },
             box Expr::Const(Literal::Number(42)),
             Position { start: 0, end: 0 }
@@ -128,7 +134,8 @@ fn apply() {
                         Type::Func(box Type::Int, box Type::Int),
                         Position { start: 0, end: 0 }
                     ),
-                    pos: Position { start: 0, end: 0 }
+                    pos: Position { start: 0, end: 0 },
+                    doc: None
                 },
                 box Expr::Const(Literal::Number(42)),
                 Position { start: 0, end: 0 }
@@ -164,7 +171,8 @@ fn binop_expr() {
                 Type::Var(Ident::new("c")),
                 Position { start: 0, end: 0 }
             ),
-            pos: Position { start: 0, end: 0 }
+            pos: Position { start: 0, end: 0 },
+            doc: None
         }),
         Ok((
             Expr::Func {
@@ -183,7 +191,8 @@ fn binop_expr() {
                     Type::Func(box Type::Int, box Type::Int),
                     Position { start: 0, end: 0 }
                 ),
-                pos: Position { start: 0, end: 0 }
+                pos: Position { start: 0, end: 0 },
+                doc: None
             },
             Type::Func(box Type::Int, box Type::Int)
         ))
@@ -228,7 +237,8 @@ fn if_expr() {
                     Type::Var(Ident::fresh()),
                     Position { start: 0, end: 0 }
                 ),
-                pos: Position { start: 0, end: 0 }
+                pos: Position { start: 0, end: 0 },
+                doc: None
             },
             left: box Expr::Var(
                 func_name1.clone(),
@@ -236,6 +246,7 @@ fn if_expr() {
                 Position { start: 0, end: 0 }
             ),
             pos: Position { start: 0, end: 0 },
+            doc: None,
         }),
         Ok((
             Expr::Func {
@@ -259,7 +270,8 @@ fn if_expr() {
                         Type::Func(box Type::Int, box Type::Int),
                         Position { start: 0, end: 0 }
                     ),
-                    pos: Position { start: 0, end: 0 }
+                    pos: Position { start: 0, end: 0 },
+                    doc: None
                 },
                 left: box Expr::Var(
                     func_name1.clone(),
@@ -267,8 +279,159 @@ fn if_expr() {
                     Position { start: 0, end: 0 }
                 ),
                 pos: Position { start: 0, end: 0 },
+                doc: None,
             },
             Type::Func(box Type::Bool, box Type::Func(box Type::Int, box Type::Int))
         ))
     );
 }
+
+#[test]
+fn record_get_error() {
+    let mut fields = ::std::collections::HashMap::new();
+    fields.insert(Ident::new("x"), Expr::Const(Literal::Number(1)));
+    let mut field_types = ::std::collections::HashMap::new();
+    field_types.insert(Ident::new("x"), Type::Int);
+    assert_eq!(
+        typecheck::check(Expr::RecordGet(
+            box Expr::Const(Literal::Record(fields)),
+            Type::Var(Ident::fresh()),
+            Ident::new("z"),
+            Position { start: 0, end: 3 }
+        )),
+        Err(typecheck::Error::MissingField {
+            pos: Position { start: 0, end: 3 },
+            typ: Type::Record(field_types),
+            label: Ident::new("z"),
+        })
+    );
+    assert_eq!(
+        typecheck::check(Expr::RecordGet(
+            box Expr::Const(Literal::Number(42)),
+            Type::Var(Ident::fresh()),
+            Ident::new("z"),
+            Position { start: 0, end: 3 }
+        )),
+        Err(typecheck::Error::NotRecord {
+            pos: Position { start: 0, end: 3 },
+            typ: Type::Int,
+            label: Ident::new("z"),
+        })
+    );
+}
+
+#[test]
+fn unbound_var_suggestion() {
+    assert_eq!(
+        typecheck::check(Expr::Let(
+            Ident::new("count"),
+            Type::Var(Ident::fresh()),
+            box Expr::Const(Literal::Number(1)),
+            box Expr::Var(
+                Ident::new("cuont"),
+                Type::Var(Ident::fresh()),
+                Position { start: 0, end: 5 }
+            ),
+            Position { start: 0, end: 0 },
+            None
+        )),
+        Err(typecheck::Error::UnboundVar {
+            pos: Position { start: 0, end: 5 },
+            name: Ident::new("cuont"),
+            suggestion: Some(Ident::new("count")),
+        })
+    );
+}
+
+#[test]
+fn ambiguous_record_get() {
+    // the type of `r` is never determined, so `r.x` can not be resolved
+    let program = parser::program("func get_x r: t :Int { r.x } get_x").unwrap();
+    assert_eq!(
+        typecheck::check(program.expr),
+        Err(typecheck::Error::Ambiguous {
+            pos: Position { start: 23, end: 27 },
+        })
+    );
+}
+
+fn check_src(src: &str) -> Result<(Expr, Type), typecheck::Error> {
+    typecheck::check(parser::program(src).unwrap().expr)
+}
+
+#[test]
+fn record_fields_in_any_order() {
+    // the fields are unified by their labels, whatever the order of the maps is
+    assert!(check_src(
+        "func f p: {x: Int, y: Bool, z: Char} : Bool { p.y }\nf {z = 'a', y = true, x = 1}"
+    )
+    .is_ok());
+    assert!(check_src("func f p: {x: Int, y: Bool} : Int { p.x }\nf {x = true, y = 1}").is_err());
+    assert!(check_src("func f p: {x: Int, y: Bool} : Int { p.x }\nf {x = 1, z = true}").is_err());
+}
+
+#[test]
+fn array_equation() {
+    assert_eq!(
+        check_src("let a = [1, 2];\nlet b = [3, 4];\na == b").map(|(_, typ)| typ),
+        Ok(Type::Bool)
+    );
+    assert!(check_src("let a = [1, 2];\nlet b = [3, 4, 5];\na == b").is_err());
+    assert!(check_src("let a = [1, 2];\nlet b = ['a', 'b'];\na == b").is_err());
+}
+
+#[test]
+fn type_alias() {
+    // the name of a type stands for its definition
+    let (e, _) = check_src("type P = {x: Int};\nfunc f p: P : Int { p.x }\nf {x = 1}").unwrap();
+    match e {
+        Expr::LetType(_, _, box Expr::Func { param_type, .. }, _) => assert_eq!(
+            param_type,
+            Type::Record(vec![(Ident::new("x"), Type::Int)].into_iter().collect())
+        ),
+        e => panic!("unexpected expression: {:?}", e),
+    }
+    assert!(check_src("type P = {x: Int};\nfunc f p: P : Int { p.x }\nf {x = true}").is_err());
+}
+
+#[test]
+fn type_hole() {
+    // the arrays given to `first` are known only to the importers of the module
+    let src = "func first a: {arr: _, len: Int} : Int { a.arr[0] }";
+    let program = parser::program(src).unwrap();
+    assert_eq!(
+        typecheck::check(program.expr.clone()),
+        Err(typecheck::Error::Ambiguous {
+            pos: Position { start: 41, end: 48 },
+        })
+    );
+    assert!(typecheck::check_module(program.expr).is_ok());
+    assert_eq!(
+        check_src(&format!("{}\nfirst {{arr = [7, 8], len = 2}}", src)).map(|(_, typ)| typ),
+        Ok(Type::Int)
+    );
+}
+
+#[test]
+fn type_hole_conflict() {
+    let check_module = |src: &str| typecheck::check_module(parser::program(src).unwrap().expr);
+    // the accesses to an unknown type have to agree with each other
+    assert!(check_module(
+        "func f a: {arr: _, len: Int} : Int { if a.arr[0] { 1 } else { a.arr[1] } }"
+    )
+    .is_err());
+    assert!(check_module("func g r: _ : Int { if r.b { r.b } else { 1 } }").is_err());
+    assert!(check_module("func h a: _ : Int { a.x + a[0] }").is_err());
+    assert!(check_module("func k r: _ : Int { r.x + r.y }").is_ok());
+}
+
+#[test]
+fn extern_func() {
+    let src = "extern func add {a: Int, b: Int} :Int;\nextern func putchar c: Int :Int;\n";
+    assert_eq!(
+        check_src(&format!("{}putchar (add {{a = 1, b = 2}})", src)).map(|(_, typ)| typ),
+        Ok(Type::Int)
+    );
+    assert!(check_src(&format!("{}putchar 'a'", src)).is_err());
+    assert!(check_src(&format!("{}add {{a = 1}}", src)).is_err());
+}
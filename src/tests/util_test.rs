@@ -0,0 +1,31 @@
+use util;
+
+fn similar(name: &str, candidates: &[&str]) -> Option<String> {
+    util::similar_name(name, candidates.iter().map(|name| name.to_string()))
+}
+
+#[test]
+fn edit_distance() {
+    assert_eq!(util::edit_distance("", ""), 0);
+    assert_eq!(util::edit_distance("abc", ""), 3);
+    assert_eq!(util::edit_distance("kitten", "sitting"), 3);
+    assert_eq!(util::edit_distance("lenght", "length"), 1);
+    assert_eq!(util::edit_distance("あいう", "あう"), 1);
+}
+
+#[test]
+fn similar_name() {
+    assert_eq!(
+        similar("lenght", &["width", "length"]),
+        Some("length".to_string())
+    );
+    assert_eq!(similar("x", &["x", "y"]), Some("y".to_string()));
+    assert_eq!(similar("count", &["total", "sum"]), None);
+}
+
+#[test]
+fn similar_name_ties() {
+    // the same answer whichever order the candidates come in, e.g) from a `HashMap`
+    assert_eq!(similar("ab", &["ac", "aa", "ad"]), Some("aa".to_string()));
+    assert_eq!(similar("ab", &["ad", "ac", "aa"]), Some("aa".to_string()));
+}
@@ -2,9 +2,11 @@ use ast::*;
 use env::Env;
 use ident::Ident;
 use std::collections::VecDeque;
+use util;
 
 mod subst;
 mod unify;
+pub use self::subst::Subst;
 use self::unify::Constraint;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,10 +24,24 @@ pub enum Error {
     UnboundVar {
         pos: Position,
         name: Ident,
+        suggestion: Option<Ident>,
     },
-    Other {
+    MissingField {
+        pos: Position,
+        typ: Type,
+        label: Ident,
+    },
+    NotRecord {
+        pos: Position,
+        typ: Type,
+        label: Ident,
+    },
+    NotArray {
+        pos: Position,
+        typ: Type,
+    },
+    Ambiguous {
         pos: Position,
-        message: String,
     },
 }
 
@@ -35,6 +51,35 @@ pub fn check(e: Expr) -> Result<(Expr, Type), Error> {
     Ok((subst.apply_expr(e), subst.apply_type(typ)))
 }
 
+// same as `check` for the definitions of a module, whose parameters may be records and arrays of
+// types unknown until the importers use them, e.g) `func sum a: {arr: _, len: Int}`
+pub fn check_module(e: Expr) -> Result<(Expr, Type), Error> {
+    let (constraints, typ) = gather_constraint_from_expr(&e, &Env::new())?;
+    let subst = unify::solve_partial(constraints)?;
+    Ok((subst.apply_expr(e), subst.apply_type(typ)))
+}
+
+// typechecks `e` with names defined by previous inputs in `env`, whose types may contain
+// variables solved in `subst`. returns the substitution extended by `e`.
+pub fn check_incremental(
+    e: Expr,
+    env: &Env<Type>,
+    subst: &Subst,
+) -> Result<(Expr, Type, Subst), Error> {
+    let (constraints, typ) = gather_constraint_from_expr(&e, env)?;
+    // solved equations go first, so that errors are reported at the new input
+    let mut solved: VecDeque<_> = subst
+        .0
+        .iter()
+        .map(|(name, typ)| {
+            Constraint::Equation(Type::Var(name.clone()), typ.clone(), Position::dummy())
+        })
+        .collect();
+    solved.extend(constraints);
+    let subst = unify::solve(solved)?;
+    Ok((subst.apply_expr(e), subst.apply_type(typ), subst))
+}
+
 fn gather_constraint_from_expr(
     e: &Expr,
     env: &Env<Type>,
@@ -55,6 +100,7 @@ fn gather_constraint_from_expr(
                 Err(Error::UnboundVar {
                     pos: pos.clone(),
                     name: name.clone(),
+                    suggestion: similar_name(name, env),
                 })
             }
         }
@@ -66,6 +112,7 @@ fn gather_constraint_from_expr(
             box ref body,
             box ref left,
             ref pos,
+            doc: _,
         } => {
             let env = env.add(
                 name.clone(),
@@ -108,7 +155,7 @@ fn gather_constraint_from_expr(
             ));
             Ok((constraints, ret_type))
         }
-        Expr::Let(ref name, ref typ, box ref e1, box ref e2, ref pos) => {
+        Expr::Let(ref name, ref typ, box ref e1, box ref e2, ref pos, _) => {
             let mut constraints = VecDeque::new();
 
             let (mut constraints1, typ1) = gather_constraint_from_expr(e1, env)?;
@@ -122,9 +169,16 @@ fn gather_constraint_from_expr(
 
             Ok((constraints, typ2))
         }
-        Expr::LetType(ref name, ref typ, box ref e) => {
+        Expr::LetType(ref name, ref typ, box ref e, _) => {
             let env = env.add(name.clone(), typ.clone());
-            gather_constraint_from_expr(e, &env)
+            let (mut constraints, typ_) = gather_constraint_from_expr(e, &env)?;
+            // the name of the type stands for its definition
+            constraints.push_front(Constraint::Equation(
+                Type::Var(name.clone()),
+                typ.clone(),
+                Position::dummy(),
+            ));
+            Ok((constraints, typ_))
         }
         Expr::If(box ref cond, box ref e1, box ref e2, ref pos) => {
             let mut constraints = VecDeque::new();
@@ -197,10 +251,32 @@ fn gather_constraint_from_expr(
             let (constraints, _) = gather_constraint_from_expr(e, env)?;
             Ok((constraints, Type::Unit))
         }
+        Expr::Extern(_, ref args, ref typ, _) => {
+            let mut constraints = VecDeque::new();
+            for e in args {
+                let (mut constraints_, _) = gather_constraint_from_expr(e, env)?;
+                constraints.append(&mut constraints_);
+            }
+            Ok((constraints, typ.clone()))
+        }
+        Expr::Assert(box ref e, ref pos) => {
+            let (mut constraints, typ) = gather_constraint_from_expr(e, env)?;
+            constraints.push_back(Constraint::Equation(typ, Type::Bool, pos.clone()));
+            Ok((constraints, Type::Unit))
+        }
         Expr::EmptyMark => Ok((VecDeque::new(), Type::EmptyMark)),
     }
 }
 
+fn similar_name(name: &Ident, env: &Env<Type>) -> Option<Ident> {
+    let candidates = env
+        .0
+        .iter()
+        .map(|(name, _)| format!("{}", name))
+        .filter(|name| !name.starts_with('<'));
+    util::similar_name(&format!("{}", name), candidates).map(|name| Ident::new(&name))
+}
+
 fn gather_constraint_from_lit(
     lit: &Literal,
     env: &Env<Type>,
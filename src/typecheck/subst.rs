@@ -19,6 +19,6 @@ impl Subst {
     pub fn apply_expr(&self, e: Expr) -> Expr {
         self.0
             .iter()
-            .fold(e, |acc, (ref name, ref typ)| acc.subst_type(name, typ))
+            .fold(e, |acc, (ref name, ref typ)| acc.subst_type_everywhere(name, typ))
     }
 }
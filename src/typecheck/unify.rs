@@ -1,6 +1,6 @@
 use super::subst::Subst;
 use super::*;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 
 #[derive(Debug, Clone)]
 pub enum Constraint {
@@ -10,8 +10,22 @@ pub enum Constraint {
 }
 
 pub fn solve(constraints: VecDeque<Constraint>) -> Result<Subst, Error> {
+    solve_with(constraints, false)
+}
+
+// same as `solve`, but leaves the types of records and arrays unknown instead of reporting them
+// as ambiguous, e.g) `a` of `func first a: _ { a[0] }`. the accesses to them only have to agree
+// with each other, the importers check them again with the types they decide.
+pub fn solve_partial(constraints: VecDeque<Constraint>) -> Result<Subst, Error> {
+    solve_with(constraints, true)
+}
+
+fn solve_with(constraints: VecDeque<Constraint>, partial: bool) -> Result<Subst, Error> {
     let mut queue = VecDeque::from(constraints);
     let mut subst = Subst::new();
+    // number of constraints postponed in a row, all of them are waiting for each other
+    // when it exceeds the rest of the queue
+    let mut postponed = 0;
 
     while !queue.is_empty() {
         match queue.pop_front().unwrap() {
@@ -25,19 +39,28 @@ pub fn solve(constraints: VecDeque<Constraint>) -> Result<Subst, Error> {
                 queue.push_back(Constraint::Equation(typ12, typ22, pos));
             }
             Constraint::Equation(Type::Record(fields1), Type::Record(fields2), pos) => {
-                let fields1_keys: Vec<_> = fields1.keys().collect();
-                let fields2_keys: Vec<_> = fields2.keys().collect();
-                if fields1_keys != fields2_keys {
+                // the fields are matched by their labels, not by the order of the maps
+                if fields1.len() != fields2.len()
+                    || fields1.keys().any(|label| !fields2.contains_key(label))
+                {
                     return Err(Error::Unify {
                         pos: pos.clone(),
                         typ1: Type::Record(fields1),
                         typ2: Type::Record(fields2),
                     });
                 }
-                for (field1, field2) in fields1.into_iter().zip(fields2.into_iter()) {
-                    queue.push_back(Constraint::Equation(field1.1, field2.1, pos.clone()));
+                for (label, typ1) in fields1.into_iter() {
+                    let typ2 = fields2[&label].clone();
+                    queue.push_back(Constraint::Equation(typ1, typ2, pos.clone()));
                 }
             }
+            Constraint::Equation(
+                Type::Array(box elem_typ1, len1),
+                Type::Array(box elem_typ2, len2),
+                pos,
+            ) if len1 == len2 => {
+                queue.push_back(Constraint::Equation(elem_typ1, elem_typ2, pos));
+            }
             Constraint::Equation(Type::Var(name), typ, pos)
             | Constraint::Equation(typ, Type::Var(name), pos) => match subst.0.get(&name) {
                 Some(typ_) => {
@@ -71,32 +94,39 @@ pub fn solve(constraints: VecDeque<Constraint>) -> Result<Subst, Error> {
                 });
             }
             Constraint::RecordAt(typ1, label, typ2, pos) => match subst.apply_type(typ1) {
-                typ1 @ Type::Record(_) => {
-                    let typ1_str = format!("{:?}", typ1);
-                    let fields = if let Type::Record(fields) = typ1 {
-                        fields
-                    } else {
-                        unreachable!()
-                    };
-                    let elem_typ = match fields.into_iter().find(|(ref label_, _)| label_ == &label)
-                    {
-                        Some((_, typ)) => typ,
+                Type::Record(fields) => {
+                    let elem_typ = match fields.get(&label) {
+                        Some(typ) => typ.clone(),
                         None => {
-                            return Err(Error::Other {
+                            return Err(Error::MissingField {
                                 pos,
-                                message: format!("`{}` does not have field `{}`", typ1_str, label),
+                                typ: Type::Record(fields),
+                                label,
                             })
                         }
                     };
                     queue.push_back(Constraint::Equation(elem_typ, typ2, pos));
                 }
                 Type::Var(name) => {
+                    postponed += 1;
+                    let stuck = postponed > queue.len();
                     queue.push_back(Constraint::RecordAt(Type::Var(name), label, typ2, pos));
+                    if stuck {
+                        if !partial {
+                            return Err(Error::Ambiguous { pos });
+                        }
+                        if !merge_postponed(&mut queue, &subst)? {
+                            break;
+                        }
+                        postponed = 0;
+                    }
+                    continue;
                 }
                 typ1 => {
-                    return Err(Error::Other {
+                    return Err(Error::NotRecord {
                         pos,
-                        message: format!("`{}` cannnot be indexed with label `{}`", typ1, label),
+                        typ: typ1,
+                        label,
                     });
                 }
             },
@@ -105,17 +135,100 @@ pub fn solve(constraints: VecDeque<Constraint>) -> Result<Subst, Error> {
                     queue.push_back(Constraint::Equation(elem_typ, elem_typ_, pos));
                 }
                 Type::Var(name) => {
+                    postponed += 1;
+                    let stuck = postponed > queue.len();
                     queue.push_back(Constraint::Array(Type::Var(name), elem_typ, pos));
+                    if stuck {
+                        if !partial {
+                            return Err(Error::Ambiguous { pos });
+                        }
+                        if !merge_postponed(&mut queue, &subst)? {
+                            break;
+                        }
+                        postponed = 0;
+                    }
+                    continue;
                 }
                 arr_typ => {
-                    return Err(Error::Other {
-                        pos,
-                        message: format!("`{}` cannot be indexed with integer", arr_typ),
-                    });
+                    return Err(Error::NotArray { pos, typ: arr_typ });
                 }
             },
         }
+        postponed = 0;
     }
 
     Ok(subst)
 }
+
+// equates the fields of the same label of each unknown record type in the postponed `queue`, and
+// the elements of each unknown array type, keeping one access of them. returns whether any of
+// them can be solved again, otherwise the accesses left in `queue` agree with some types.
+fn merge_postponed(queue: &mut VecDeque<Constraint>, subst: &Subst) -> Result<bool, Error> {
+    let mut fields: HashMap<(Ident, Ident), Type> = HashMap::new();
+    let mut elems: HashMap<Ident, Type> = HashMap::new();
+    let mut rest = VecDeque::new();
+    let mut equations = vec![];
+    // whether any of them is no longer waiting
+    let mut solvable = false;
+    for constraint in queue.drain(..) {
+        match constraint {
+            Constraint::RecordAt(typ, label, field_typ, pos) => {
+                let name = match subst.apply_type(typ.clone()) {
+                    Type::Var(name) => name,
+                    _ => {
+                        rest.push_back(Constraint::RecordAt(typ, label, field_typ, pos));
+                        solvable = true;
+                        continue;
+                    }
+                };
+                if elems.contains_key(&name) {
+                    return Err(Error::NotRecord {
+                        pos: pos,
+                        typ: Type::Var(name),
+                        label: label,
+                    });
+                }
+                let key = (name.clone(), label.clone());
+                match fields.get(&key) {
+                    Some(typ_) => {
+                        equations.push(Constraint::Equation(typ_.clone(), field_typ, pos));
+                    }
+                    None => {
+                        fields.insert(key, field_typ.clone());
+                        rest.push_back(Constraint::RecordAt(typ, label, field_typ, pos));
+                    }
+                }
+            }
+            Constraint::Array(typ, elem_typ, pos) => {
+                let name = match subst.apply_type(typ.clone()) {
+                    Type::Var(name) => name,
+                    _ => {
+                        rest.push_back(Constraint::Array(typ, elem_typ, pos));
+                        solvable = true;
+                        continue;
+                    }
+                };
+                if fields.keys().any(|(name_, _)| *name_ == name) {
+                    return Err(Error::NotArray {
+                        pos: pos,
+                        typ: Type::Var(name),
+                    });
+                }
+                match elems.get(&name) {
+                    Some(typ_) => {
+                        equations.push(Constraint::Equation(typ_.clone(), elem_typ, pos));
+                    }
+                    None => {
+                        elems.insert(name, elem_typ.clone());
+                        rest.push_back(Constraint::Array(typ, elem_typ, pos));
+                    }
+                }
+            }
+            constraint => rest.push_back(constraint),
+        }
+    }
+    let merged = solvable || !equations.is_empty();
+    *queue = rest;
+    queue.extend(equations);
+    Ok(merged)
+}
@@ -1,22 +1,79 @@
-pub fn pos_to_location(src: &str, mut pos: usize) -> (usize, usize) {
-    let mut line = 0;
-    let mut column = 0;
-    for c in src.chars() {
-        if c == '\n' {
-            line += 1;
-            column = 0;
-        } else {
-            column += 1;
-        }
-        if pos == 0 {
-            return (line, column);
-        } else {
-            pos -= 1;
+use std::path::{Path, PathBuf};
+
+pub fn alert(msg: &str) -> String {
+    format!("\u{001B}[31m{}\u{001B}[39m", msg)
+}
+
+pub fn warn(msg: &str) -> String {
+    format!("\u{001B}[33m{}\u{001B}[39m", msg)
+}
+
+// edit distance in which swapping two adjacent characters costs 1 as well
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in 0..a.len() + 1 {
+        d[i][0] = i;
+    }
+    for j in 0..b.len() + 1 {
+        d[0][j] = j;
+    }
+    for i in 1..a.len() + 1 {
+        for j in 1..b.len() + 1 {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = std::cmp::min(
+                std::cmp::min(d[i - 1][j] + 1, d[i][j - 1] + 1),
+                d[i - 1][j - 1] + cost,
+            );
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = std::cmp::min(d[i][j], d[i - 2][j - 2] + 1);
+            }
         }
     }
-    (line, column)
+    d[a.len()][b.len()]
 }
 
-pub fn alert(msg: &str) -> String {
-    format!("\u{001B}[31m{}\u{001B}[39m", msg)
+// the most similar candidate to `name`, if it is similar enough to be a typo.
+// candidates equally similar are chosen by their names, not by the order they are given in
+pub fn similar_name<I: Iterator<Item = String>>(name: &str, candidates: I) -> Option<String> {
+    let max_distance = std::cmp::max(name.chars().count() / 3, 1);
+    candidates
+        .filter(|candidate| candidate.as_str() != name)
+        .map(|candidate| (edit_distance(name, &candidate), candidate))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+// the mumrik source files under `dir` in order, skipping hidden directories and those in `skip`,
+// which are given as canonical paths
+pub fn source_files(dir: &Path, skip: &[PathBuf]) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    collect_source_files(dir, skip, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect_source_files(
+    dir: &Path,
+    skip: &[PathBuf],
+    files: &mut Vec<PathBuf>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)?.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        let hidden = entry
+            .file_name()
+            .to_str()
+            .map_or(true, |name| name.starts_with('.'));
+        if path.is_dir() {
+            let canonical = path.canonicalize().unwrap_or_else(|_| path.clone());
+            if !hidden && !skip.contains(&canonical) {
+                collect_source_files(&path, skip, files)?;
+            }
+        } else if path.extension().map_or(false, |ext| ext == "mm") {
+            files.push(path);
+        }
+    }
+    Ok(())
 }
@@ -0,0 +1,156 @@
+mod common;
+
+use common::{diagnostics, mumrik, project, stderr, stdout, write_files};
+use std::process::Command;
+
+#[test]
+fn emit_ast() {
+    let dir = project(&[(
+        "main.mm",
+        "import lib.math as m;\npub func twice x: Int :Int { x * 2 }\ntwice 21\n",
+    )]);
+    let output = mumrik(dir.path(), &["build", "--emit", "ast=-"]);
+    assert!(output.status.success());
+    let ast = stdout(&output);
+    // the types are left to the typechecker
+    assert!(ast.starts_with(
+        "import lib.math as m;\n// pub: twice\nlet rec twice = (func x:Int :Int => (x as <fresh-"
+    ));
+    assert!(ast.ends_with(">) (21)\n"));
+}
+
+#[test]
+fn emit_typed_ast_and_nf() {
+    let dir = project(&[(
+        "main.mm",
+        "func twice x: Int :Int { x * 2 }\nprintln (twice 21)\n",
+    )]);
+    let output = mumrik(dir.path(), &["build", "--emit", "typed-ast,nf"]);
+    assert!(output.status.success());
+    let typed_ast = std::fs::read_to_string(dir.path().join("a.typed-ast")).unwrap();
+    assert!(typed_ast.ends_with(
+        "let rec twice = (func x:Int :Int => (x as Int) * (2));\nprintln (twice as Int -> (Int)) (21)\n"
+    ));
+    let nf = std::fs::read_to_string(dir.path().join("a.nf")).unwrap();
+    assert_eq!(
+        nf,
+        "func twice(x: Int) :Int {\n    (*x) * (2)\n}\nprintln twice(21)\n"
+    );
+}
+
+#[test]
+fn unwritable_artifact() {
+    let dir = project(&[("main.mm", "1\n")]);
+    let output = mumrik(
+        dir.path(),
+        &[
+            "build",
+            "--emit",
+            "ast=missing/main.ast",
+            "--message-format=json",
+        ],
+    );
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0015");
+}
+
+#[test]
+fn extern_c_function() {
+    let dir = project(&[
+        (
+            "mumrik-conf.toml",
+            "[build]\nsrc = \"main.mm\"\noutput = \"a.out\"\ndep = \".\"\nc-sources = [\"even.c\"]\n",
+        ),
+        (
+            "even.c",
+            "#include <stdbool.h>\nbool is_even(int n) { return n % 2 == 0; }\nint to_int(bool b) { return b ? 1 : 0; }\n",
+        ),
+        (
+            "main.mm",
+            "extern func is_even n: Int :Bool;\nextern func to_int b: Bool :Int;\nprintln (to_int (is_even 4));\nprintln (to_int (is_even 3))\n",
+        ),
+    ]);
+    let output = mumrik(dir.path(), &["build", "--emit", "llvm-ir=-"]);
+    assert!(output.status.success());
+    // `Bool` is extended to `int` as C does
+    let ll = stdout(&output);
+    assert!(ll.contains("declare zeroext i1 @is_even(i32)\n"));
+    assert!(ll.contains("declare i32 @to_int(i1 zeroext)\n"));
+
+    let output = mumrik(dir.path(), &["build"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = Command::new(dir.path().join("a.out")).output().unwrap();
+    assert_eq!(stdout(&output), "1\n0\n");
+}
+
+#[test]
+fn c_library() {
+    let dir = project(&[
+        (
+            "mumrik-conf.toml",
+            "[build]\nsrc = \"calc.mm\"\noutput = \"a.out\"\ndep = \".\"\nc-sources = [\"c/twice.c\"]\n\n[lib]\nname = \"calc\"\n",
+        ),
+        ("c/twice.c", "int twice(int x) { return x * 2; }\n"),
+        (
+            "calc.mm",
+            "extern func twice x: Int :Int;\npub func add {a: Int, b: Int} :Int { a + b }\npub func is_even x: Int :Bool { x / 2 * 2 == x }\npub func negate b: Bool :Bool { if b { false } else { true } }\npub func quad x: Int :Int { twice (twice x) }\n",
+        ),
+        (
+            "main.c",
+            "#include <stdio.h>\n#include \"calc.h\"\nint main(void) {\n    printf(\"%d %d %d %d\\n\", calc_add(2, 3), calc_is_even(4), calc_negate(true), calc_quad(5));\n    return 0;\n}\n",
+        ),
+    ]);
+    let output = mumrik(dir.path(), &["build"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+
+    // a C program calls the library, linked statically and dynamically
+    for (exe, args) in [
+        ("static", vec!["build/libcalc.a"]),
+        ("shared", vec!["-Lbuild", "-lcalc"]),
+    ]
+    .iter()
+    {
+        let output = Command::new("gcc")
+            .args(&["main.c", "-Ibuild", "-o", exe])
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", stderr(&output));
+        let output = Command::new(dir.path().join(exe))
+            .env("LD_LIBRARY_PATH", dir.path().join("build"))
+            .output()
+            .unwrap();
+        assert_eq!(stdout(&output), "5 1 0 20\n");
+    }
+}
+
+#[test]
+fn library_errors() {
+    let config = "[build]\nsrc = \"calc.mm\"\noutput = \"a.out\"\ndep = \".\"\nc-sources = [\"twice.c\"]\n\n[lib]\nname = \"calc\"\n";
+    let dir = project(&[
+        ("mumrik-conf.toml", config),
+        ("twice.c", "int twice(int x) { return x * 2 }\n"),
+        ("calc.mm", "pub func add {a: Int, b: Int} :Int { a + b }\n"),
+    ]);
+    // the output of the C compiler is in the diagnostic
+    let output = mumrik(dir.path(), &["build", "--message-format=json"]);
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0017");
+    assert!(diags[0]["message"].as_str().unwrap().contains("twice.c"));
+
+    write_files(
+        dir.path(),
+        &[
+            ("twice.c", "int twice(int x) { return x * 2; }\n"),
+            ("calc.mm", "pub let answer = 42;\n"),
+        ],
+    );
+    let output = mumrik(dir.path(), &["build", "--message-format=json"]);
+    assert!(!output.status.success());
+    assert_eq!(diagnostics(&output)[0]["code"], "E0018");
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::{diagnostics, mumrik, project, write_files};
+use std::path::Path;
+
+const MAIN: &str = "import lib.a;\nlet dbl = (func x: Int => x * 2);\ndbl (a.inc 1)\n";
+const A: &str = "import lib.b;\npub let inc = (func x: Int :Int => b.add {x = x, y = 1});\n";
+const B: &str = "pub func add {x: Int, y: Int} :Int { x + y }\n";
+
+fn cache_entries(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir.join("build/cache"))
+        .unwrap()
+        .map(|entry| std::fs::read_to_string(entry.unwrap().path()).unwrap())
+        .collect()
+}
+
+#[test]
+fn reuses_modules() {
+    let dir = project(&[("main.mm", MAIN), ("lib/a.mm", A), ("lib/b.mm", B)]);
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(4));
+    let entries = cache_entries(dir.path());
+    assert!(!entries.is_empty());
+    // fresh names are numbered per process, so none of them is cached
+    assert!(entries.iter().all(|entry| !entry.contains("<fresh-")));
+
+    // the anonymous function of `lib.a` never clashes with that of the program
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(cache_entries(dir.path()), entries);
+}
+
+#[test]
+fn invalidates_importers() {
+    let dir = project(&[("main.mm", MAIN), ("lib/a.mm", A), ("lib/b.mm", B)]);
+    assert!(mumrik(dir.path(), &["check"]).status.success());
+
+    // `lib.a` is unchanged, but is checked again against the new `lib.b`
+    write_files(
+        dir.path(),
+        &[(
+            "lib/b.mm",
+            "pub func add {x: Int, y: Int} :Bool { x == y }\n",
+        )],
+    );
+    let output = mumrik(dir.path(), &["check", "--message-format=json"]);
+    assert_eq!(output.status.code(), Some(1));
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0004");
+    assert!(diags[0]["span"]["file"].as_str().unwrap().ends_with("a.mm"));
+
+    write_files(dir.path(), &[("lib/b.mm", B)]);
+    assert!(mumrik(dir.path(), &["check"]).status.success());
+}
+
+#[test]
+fn ignores_broken_entries() {
+    let dir = project(&[("main.mm", MAIN), ("lib/a.mm", A), ("lib/b.mm", B)]);
+    assert!(mumrik(dir.path(), &["check"]).status.success());
+    for entry in std::fs::read_dir(dir.path().join("build/cache")).unwrap() {
+        std::fs::write(entry.unwrap().path(), "{").unwrap();
+    }
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn invalidates_on_lint_settings() {
+    let config = |level: &str| {
+        format!(
+            "[build]\nsrc = \"main.mm\"\noutput = \"a.out\"\ndep = \".\"\nprelude = false\n\n[lint]\n{} = [\"unused-variable\"]\n",
+            level
+        )
+    };
+    let dir = project(&[
+        ("mumrik-conf.toml", &config("allow")),
+        ("main.mm", "import lib.b;\nb.add {x = 1, y = 2}\n"),
+        (
+            "lib/b.mm",
+            "pub func add {x: Int, y: Int} :Int { let z = 1; x + y }\n",
+        ),
+    ]);
+    assert!(mumrik(dir.path(), &["check"]).status.success());
+    assert!(!cache_entries(dir.path()).is_empty());
+
+    // `lib.b` imports nothing, not even the prelude, and is still checked again with the new
+    // settings
+    write_files(dir.path(), &[("mumrik-conf.toml", &config("deny"))]);
+    let output = mumrik(dir.path(), &["check", "--message-format=json"]);
+    assert_eq!(output.status.code(), Some(1));
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "W0001");
+    assert!(diags[0]["span"]["file"].as_str().unwrap().ends_with("b.mm"));
+}
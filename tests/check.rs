@@ -0,0 +1,97 @@
+mod common;
+
+use common::{diagnostics, mumrik, project};
+
+#[test]
+fn valid_program() {
+    let dir = project(&[("main.mm", "func twice x: Int :Int { x * 2 }\ntwice 21\n")]);
+    let output = mumrik(dir.path(), &["check"]);
+    assert!(output.status.success());
+    // nothing is generated
+    assert!(!dir.path().join("a.out").exists());
+}
+
+#[test]
+fn type_error() {
+    let dir = project(&[("main.mm", "let x = 1;\nx + true\n")]);
+    let output = mumrik(dir.path(), &["check", "--message-format=json"]);
+    assert_eq!(output.status.code(), Some(1));
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0004");
+    assert_eq!(diags[0]["span"]["file"], "./main.mm");
+    assert_eq!(diags[0]["span"]["line_start"], 2);
+}
+
+#[test]
+fn given_file() {
+    let dir = project(&[("src/app.mm", "1 + 'a'\n")]);
+    let output = mumrik(dir.path(), &["check", "src/app.mm"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(common::stderr(&output).contains("src/app.mm:1:"));
+}
+
+#[test]
+fn error_in_imported_module() {
+    let dir = project(&[
+        ("main.mm", "import util;\nutil.twice 2\n"),
+        ("util.mm", "pub func twice x: Int :Int { x * true }\n"),
+    ]);
+    let output = mumrik(dir.path(), &["check", "--message-format=json"]);
+    assert_eq!(output.status.code(), Some(1));
+    let diags = diagnostics(&output);
+    assert!(diags[0]["span"]["file"]
+        .as_str()
+        .unwrap()
+        .ends_with("util.mm"));
+}
+
+#[test]
+fn warnings() {
+    let dir = project(&[("main.mm", "let unused = 1;\n2\n")]);
+    let output = mumrik(dir.path(), &["check", "--message-format=json"]);
+    assert!(output.status.success());
+    assert_eq!(diagnostics(&output)[0]["severity"], "warning");
+
+    // denied lints fail the check
+    common::write_files(
+        dir.path(),
+        &[("mumrik-conf.toml", CONFIG_DENYING_UNUSED_VARIABLE)],
+    );
+    let output = mumrik(dir.path(), &["check", "--message-format=json"]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(diagnostics(&output)[0]["severity"], "error");
+}
+
+const CONFIG_DENYING_UNUSED_VARIABLE: &str = r#"[build]
+src = "main.mm"
+output = "a.out"
+dep = "."
+
+[lint]
+deny = ["unused-variable"]
+"#;
+
+#[test]
+fn import_suggestion() {
+    let dir = project(&[
+        ("main.mm", "import lib.utill;\nutill.twice 2\n"),
+        ("lib/util.mm", "pub func twice x: Int :Int { x * 2 }\n"),
+        ("lib/other.mm", "pub func id x: Int :Int { x }\n"),
+    ]);
+    let output = mumrik(dir.path(), &["check", "--message-format=json"]);
+    let diags = diagnostics(&output);
+    assert_eq!(diags[0]["code"], "E0008");
+    assert_eq!(
+        diags[0]["notes"],
+        serde_json::json!(["help: did you mean `lib.util`?"])
+    );
+}
+
+#[test]
+fn json_kept_apart_from_program_output() {
+    let dir = project(&[("main.mm", "let unused = 1;\nprintln 2\n")]);
+    let output = mumrik(dir.path(), &["eval", "--message-format=json"]);
+    assert_eq!(common::stdout(&output), "2\n");
+    assert_eq!(diagnostics(&output)[0]["code"], "W0001");
+}
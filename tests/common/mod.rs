@@ -0,0 +1,48 @@
+// helpers running the `mumrik` executable in a temporary project
+#![allow(dead_code)]
+
+extern crate serde_json;
+extern crate tempfile;
+
+use std::path::Path;
+use std::process::{Command, Output};
+
+// a directory with `files`, given as pairs of the path and the content
+pub fn project(files: &[(&str, &str)]) -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    write_files(dir.path(), files);
+    dir
+}
+
+pub fn write_files(dir: &Path, files: &[(&str, &str)]) {
+    for (path, content) in files.iter() {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+}
+
+// runs `mumrik <args>` in `dir`
+pub fn mumrik(dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_mumrik"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+// the diagnostics printed by `--message-format=json`
+pub fn diagnostics(output: &Output) -> Vec<serde_json::Value> {
+    stderr(output)
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
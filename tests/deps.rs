@@ -0,0 +1,156 @@
+extern crate tempfile;
+
+mod common;
+
+use common::{diagnostics, mumrik, project, stderr, write_files};
+use std::path::Path;
+use std::process::Command;
+
+fn git(dir: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(&[
+            "-c",
+            "user.name=mumrik",
+            "-c",
+            "user.email=mumrik@example.com",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "git {:?}", args);
+    String::from_utf8_lossy(&output.stdout).trim().to_string()
+}
+
+// commits `files` into the repository in `dir`, and returns the commit
+fn commit(dir: &Path, files: &[(&str, &str)]) -> String {
+    write_files(dir, files);
+    git(dir, &["add", "--all"]);
+    git(dir, &["commit", "--quiet", "--message", "update"]);
+    git(dir, &["rev-parse", "HEAD"])
+}
+
+// a git repository whose `util` module has `answer`, which returns `n`
+fn repo(n: i32) -> (tempfile::TempDir, String) {
+    let dir = tempfile::tempdir().unwrap();
+    git(dir.path(), &["init", "--quiet"]);
+    let commit = commit(dir.path(), &[("util.mm", &answer(n))]);
+    (dir, commit)
+}
+
+fn answer(n: i32) -> String {
+    format!("pub func answer _: Unit :Int {{ {} }}\n", n)
+}
+
+fn config(deps: &str) -> String {
+    format!(
+        "[build]\nsrc = \"main.mm\"\noutput = \"a.out\"\ndep = \"deps\"\n\n[dependencies]\n{}",
+        deps
+    )
+}
+
+fn url(dir: &Path) -> String {
+    format!("file://{}", dir.display())
+}
+
+const MAIN: &str = "import foo.util;\nutil.answer unit\n";
+
+#[test]
+fn git_dependency_pinned_in_lockfile() {
+    let (repo, first) = repo(7);
+    let dir = project(&[
+        (
+            "mumrik-conf.toml",
+            &config(&format!("foo = {{ git = \"{}\" }}\n", url(repo.path()))),
+        ),
+        ("main.mm", MAIN),
+    ]);
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+    assert!(dir.path().join("deps/foo/util.mm").is_file());
+    let lock = std::fs::read_to_string(dir.path().join("mumrik.lock")).unwrap();
+    assert!(lock.contains(&format!("commit = \"{}\"", first)));
+
+    // the new commit is not fetched while the lockfile pins the old one
+    commit(repo.path(), &[("util.mm", &answer(8))]);
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+
+    // and is resolved again without it
+    std::fs::remove_file(dir.path().join("mumrik.lock")).unwrap();
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(8), "{}", stderr(&output));
+    let lock = std::fs::read_to_string(dir.path().join("mumrik.lock")).unwrap();
+    assert!(!lock.contains(&first));
+}
+
+#[test]
+fn git_dependency_with_new_rev() {
+    let (repo, _) = repo(7);
+    let dep = |rev: &str| format!("foo = {{ git = \"{}\"{} }}\n", url(repo.path()), rev);
+    let dir = project(&[("mumrik-conf.toml", &config(&dep(""))), ("main.mm", MAIN)]);
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+
+    // a branch made after the clone is fetched, although the lockfile pins the package
+    git(repo.path(), &["checkout", "--quiet", "-b", "feature"]);
+    let feature = commit(repo.path(), &[("util.mm", &answer(9))]);
+    write_files(
+        dir.path(),
+        &[("mumrik-conf.toml", &config(&dep(", rev = \"feature\"")))],
+    );
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(9), "{}", stderr(&output));
+    let lock = std::fs::read_to_string(dir.path().join("mumrik.lock")).unwrap();
+    assert!(lock.contains(&format!("commit = \"{}\"", feature)));
+}
+
+#[test]
+fn path_dependency() {
+    let dir = project(&[
+        (
+            "app/mumrik-conf.toml",
+            &config("foo = { path = \"../foo\" }\n"),
+        ),
+        ("app/main.mm", MAIN),
+        ("foo/util.mm", &answer(5)),
+    ]);
+    let app = dir.path().join("app");
+    // a directory is never fetched
+    let output = mumrik(&app, &["check", "main.mm"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = mumrik(&app, &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(5), "{}", stderr(&output));
+    let lock = std::fs::read_to_string(app.join("mumrik.lock")).unwrap();
+    assert!(lock.contains("name = \"foo\""));
+
+    // the changes in the directory are imported as they are
+    write_files(dir.path(), &[("foo/util.mm", &answer(6))]);
+    let output = mumrik(&app, &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(6), "{}", stderr(&output));
+}
+
+#[test]
+fn check_never_fetches() {
+    let (repo, _) = repo(7);
+    let dir = project(&[
+        (
+            "mumrik-conf.toml",
+            &config(&format!("foo = {{ git = \"{}\" }}\n", url(repo.path()))),
+        ),
+        ("main.mm", MAIN),
+    ]);
+    // the import is unknown until the dependency is fetched
+    let output = mumrik(dir.path(), &["check", "main.mm", "--message-format=json"]);
+    assert!(!output.status.success());
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0008");
+    assert!(!dir.path().join("deps").exists());
+    assert!(!dir.path().join("mumrik.lock").exists());
+
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    assert_eq!(output.status.code(), Some(7), "{}", stderr(&output));
+    let output = mumrik(dir.path(), &["check", "main.mm"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use common::{mumrik, project, stderr};
+
+#[test]
+fn modules_imported_twice() {
+    let dir = project(&[
+        ("main.mm", "import lib.a;\nimport lib.b;\na.f (b.g 1)\n"),
+        ("lib/a.mm", "import lib.b;\n/// f\npub func f x: Int :Int { b.g x }\n"),
+        ("lib/b.mm", "/// g\npub func g x: Int :Int { x }\n"),
+    ]);
+    let output = mumrik(dir.path(), &["doc", "--format=markdown"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(common::stdout(&output).contains("documented 3 modules"));
+    let b = std::fs::read_to_string(dir.path().join("build/doc/lib.b.md")).unwrap();
+    assert!(b.contains("## `func g x: Int :Int`"));
+}
+
+#[test]
+fn workspace_build_dir() {
+    let dir = project(&[
+        ("mumrik-conf.toml", "[workspace]\nmembers = [\"app\"]\n"),
+        (
+            "app/mumrik-conf.toml",
+            "[build]\nsrc = \"main.mm\"\noutput = \"a.out\"\ndep = \"deps\"\n",
+        ),
+        ("app/main.mm", "1\n"),
+    ]);
+    let output = mumrik(&dir.path().join("app"), &["doc"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(dir.path().join("build/doc/index.html").is_file());
+    assert!(!dir.path().join("app/build").exists());
+}
+
+#[test]
+fn type_error() {
+    let dir = project(&[("main.mm", "1 + true\n")]);
+    let output = mumrik(dir.path(), &["doc"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("E0004"));
+    assert!(!dir.path().join("build/doc").exists());
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::{mumrik, project, stderr};
+
+// the exit status of `main.mm` with `lets` before `expr`, after `import std.string;`
+fn eval_string(lets: &str, expr: &str) -> Option<i32> {
+    let main = format!("import std.string;\n{}{}\n", lets, expr);
+    let dir = project(&[("main.mm", &main)]);
+    let output = mumrik(dir.path(), &["eval", "main.mm"]);
+    // the strings which `expr` does not use are only warned about
+    assert!(!stderr(&output).contains("error"), "{}", stderr(&output));
+    output.status.code()
+}
+
+// strings of one size, padded with dots
+const STRINGS: &str = "let num = {chars = ['-', '4', '2', '.'], len = 3};\nlet neg = {chars = ['-', '.', '.', '.'], len = 1};\nlet word = {chars = ['4', '2', 'a', '.'], len = 3};\n";
+
+#[test]
+fn string() {
+    let yes = |cond: &str| format!("if {} {{ 1 }} else {{ 0 }}", cond);
+    assert_eq!(eval_string(STRINGS, "0 - string.parse_int num"), Some(42));
+    assert_eq!(eval_string(STRINGS, &yes("string.is_integer num")), Some(1));
+    assert_eq!(eval_string(STRINGS, &yes("string.is_integer neg")), Some(0));
+    assert_eq!(
+        eval_string(STRINGS, &yes("string.is_integer word")),
+        Some(0)
+    );
+    assert_eq!(
+        eval_string(STRINGS, &yes("string.starts_with {s = num, prefix = neg}")),
+        Some(1)
+    );
+    assert_eq!(
+        eval_string(STRINGS, &yes("string.equal {a = num, b = neg}")),
+        Some(0)
+    );
+    assert_eq!(
+        eval_string(STRINGS, "string.find {s = word, c = 'a'}"),
+        Some(2)
+    );
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{diagnostics, mumrik, project, stdout};
+
+#[test]
+fn collects_test_files_once() {
+    let dir = project(&[
+        (
+            "main.mm",
+            "import lib.util;\ntest \"main\" { assert (util.twice 1 == 2) }\nutil.twice 2\n",
+        ),
+        (
+            "lib/util.mm",
+            "pub func twice x: Int :Int { x * 2 }\ntest \"util\" { assert (twice 2 == 4) }\n",
+        ),
+        ("other.mm", "test \"other\" { assert true }\n"),
+        // neither generated files nor hidden directories have tests of the project
+        ("build/gen.mm", "test \"generated\" { assert false }\n"),
+        (".hidden/gen.mm", "test \"hidden\" { assert false }\n"),
+    ]);
+    let output = mumrik(dir.path(), &["test"]);
+    let out = stdout(&output);
+    assert!(output.status.success(), "{}", out);
+    assert!(out.contains("running 3 tests"), "{}", out);
+}
+
+#[test]
+fn json_results() {
+    let dir = project(&[(
+        "main.mm",
+        "test \"pass\" { assert true }\ntest \"fail\" { println 1; assert false }\n0\n",
+    )]);
+    let output = mumrik(dir.path(), &["test", "--message-format=json"]);
+    assert_eq!(output.status.code(), Some(1));
+    // stdout has only the results
+    let results: Vec<serde_json::Value> = stdout(&output)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(results.len(), 4);
+    assert_eq!(results[0]["test_count"], 2);
+    assert_eq!(results[1]["event"], "ok");
+    assert_eq!(results[2]["event"], "failed");
+    assert_eq!(results[2]["stdout"], "1\n");
+    assert_eq!(results[3]["failed"], 1);
+    // the failure is a diagnostic
+    let diags = diagnostics(&output);
+    assert_eq!(diags.len(), 1);
+    assert_eq!(diags[0]["code"], "E0016");
+}
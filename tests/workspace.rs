@@ -0,0 +1,70 @@
+extern crate tempfile;
+
+mod common;
+
+use common::{mumrik, project, stderr, stdout};
+
+const ROOT: &str = "[workspace]\nmembers = [\"app\", \"util\"]\n";
+
+const APP: &str = "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[build]\nsrc = \"main.mm\"\noutput = \"a.out\"\ndep = \"deps\"\n\n[dependencies]\nutil = { path = \"../util\" }\n";
+
+const UTIL: &str = "[package]\nname = \"util\"\nversion = \"0.1.0\"\n\n[build]\nsrc = \"lib.mm\"\noutput = \"a.out\"\ndep = \"deps\"\n";
+
+// a workspace whose `app` imports `util`, where each of them has a test
+fn workspace(main: &str) -> tempfile::TempDir {
+    project(&[
+        ("mumrik-conf.toml", ROOT),
+        ("app/mumrik-conf.toml", APP),
+        ("app/main.mm", main),
+        ("util/mumrik-conf.toml", UTIL),
+        (
+            "util/lib.mm",
+            "pub func double x: Int :Int { x * 2 }\ntest \"double\" { assert (double 2 == 4) }\n",
+        ),
+    ])
+}
+
+const MAIN: &str = "import util.lib;\nfunc quad x: Int :Int { lib.double (lib.double x) }\ntest \"quad\" { assert (quad 1 == 4) }\nquad 1\n";
+
+#[test]
+fn all_members_in_root() {
+    let dir = workspace(MAIN);
+    let output = mumrik(dir.path(), &["test"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    // each member with its own `src`
+    let stderr = stderr(&output);
+    assert!(stderr.contains("member: app"));
+    assert!(stderr.contains("member: util"));
+    // `app` also runs the tests of `util`, which it imports
+    let stdout = stdout(&output);
+    assert!(stdout.contains("test result: ok. 2 passed; 0 failed"));
+    assert!(stdout.contains("test result: ok. 1 passed; 0 failed"));
+    // the lockfile is shared by the members
+    let lock = std::fs::read_to_string(dir.path().join("mumrik.lock")).unwrap();
+    assert!(lock.contains("name = \"util\""));
+    assert!(!dir.path().join("app/mumrik.lock").exists());
+}
+
+#[test]
+fn package_option() {
+    let dir = workspace(MAIN);
+    let output = mumrik(dir.path(), &["check", "-p", "app"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert!(stderr(&output).contains("member: app"));
+    assert!(!stderr(&output).contains("member: util"));
+
+    let output = mumrik(dir.path(), &["check", "-p", "utl"]);
+    assert!(!output.status.success());
+    assert!(stderr(&output).contains("the workspace has no member `utl`, did you mean `util`?"));
+}
+
+#[test]
+fn failure_in_a_member() {
+    let dir = workspace("import util.lib;\nlib.double true\n");
+    let output = mumrik(dir.path(), &["check"]);
+    assert_eq!(output.status.code(), Some(1));
+    // the other members are still checked
+    let stderr = stderr(&output);
+    assert!(stderr.contains("member: util"));
+    assert!(stderr.contains("failed in `app`"));
+}